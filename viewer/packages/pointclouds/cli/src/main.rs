@@ -0,0 +1,282 @@
+/*!
+ * Copyright 2022 Cognite AS
+ */
+
+//! Assigns object ids to the points of a LAS or XYZ file, using the same shapes and assignment as
+//! the viewer
+
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use nalgebra_glm::DVec3;
+
+use pointclouds_core::assignment::{self, ObjectSet};
+use pointclouds_core::inputs;
+use pointclouds_core::las;
+use pointclouds_core::linalg::BoundingBox;
+use pointclouds_core::object_id_width::ObjectIdWidth;
+use pointclouds_core::point_octree::PointOctree;
+
+mod shapes_file;
+mod xyz;
+
+use shapes_file::ShapesFile;
+
+const USAGE: &str = "\
+Usage: assign-points --points <FILE> --shapes <FILE> [--output <FILE>] [--format <FORMAT>]
+
+Options:
+  --points <FILE>    Points to assign, as a LAS or LAZ file (.las, .laz) or as text with x, y and z
+                     on each line
+  --shapes <FILE>    JSON list of shapes, or an object with `objects`, `meshes`, `overlap_policy`
+                     and `inflation`
+  --output <FILE>    Where to write the result, standard output if left out
+  --format <FORMAT>  `ids` writes the object id of each point on its own line, `xyz` writes
+                     `x y z object_id` lines [default: ids]
+  -h, --help         Print this help
+
+Like in the viewer, points are assigned at 32-bit float precision relative to an offset. The viewer
+uses the offset of each sector and this tool the minimum corner of all points, so points within
+rounding error of a shape's surface can get a different object id than in the viewer";
+
+#[derive(Debug, PartialEq)]
+enum OutputFormat {
+    ObjectIds,
+    AnnotatedXyz,
+}
+
+#[derive(Debug, PartialEq)]
+struct Arguments {
+    points_path: PathBuf,
+    shapes_path: PathBuf,
+    output_path: Option<PathBuf>,
+    output_format: OutputFormat,
+}
+
+fn parse_arguments(mut args: impl Iterator<Item = String>) -> Result<Arguments, String> {
+    let mut points_path = None;
+    let mut shapes_path = None;
+    let mut output_path = None;
+    let mut output_format = OutputFormat::ObjectIds;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+
+        match arg.as_str() {
+            "--points" => points_path = Some(PathBuf::from(value()?)),
+            "--shapes" => shapes_path = Some(PathBuf::from(value()?)),
+            "--output" => output_path = Some(PathBuf::from(value()?)),
+            "--format" => {
+                output_format = match value()?.as_str() {
+                    "ids" => OutputFormat::ObjectIds,
+                    "xyz" => OutputFormat::AnnotatedXyz,
+                    other => return Err(format!("Unknown output format '{}'", other)),
+                }
+            }
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        }
+    }
+
+    Ok(Arguments {
+        points_path: points_path.ok_or("Missing --points")?,
+        shapes_path: shapes_path.ok_or("Missing --shapes")?,
+        output_path,
+        output_format,
+    })
+}
+
+fn read_points(path: &Path) -> Result<Vec<DVec3>, String> {
+    let is_las = path.extension().map_or(false, |extension| {
+        extension.eq_ignore_ascii_case("las") || extension.eq_ignore_ascii_case("laz")
+    });
+
+    if is_las {
+        let bytes = fs::read(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        las::read_positions(&bytes).map(|(_, positions)| positions)
+    } else {
+        let text =
+            fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        xyz::parse_xyz(&text)
+    }
+    .map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn assign_object_ids(positions: &[DVec3], shapes_file: ShapesFile) -> Result<Vec<u32>, String> {
+    let objects = inputs::create_objects(
+        shapes_file.objects,
+        &shapes_file.meshes,
+        ObjectIdWidth::U32,
+        &shapes_file.inflation,
+    )?;
+
+    // Rounded like the positions the viewer gets, which are 32-bit floats relative to an offset
+    let point_offset = positions.iter().copied().collect::<BoundingBox>().min;
+    let coordinates: Vec<f32> = positions
+        .iter()
+        .flat_map(|position| {
+            let relative_position = position - point_offset;
+            [
+                relative_position.x as f32,
+                relative_position.y as f32,
+                relative_position.z as f32,
+            ]
+        })
+        .collect();
+    let points = inputs::create_points(&coordinates, &point_offset);
+    let octree = PointOctree::new(
+        points
+            .iter()
+            .map(|point| point.vec)
+            .collect::<BoundingBox>(),
+        points,
+    );
+
+    let mut object_ids = vec![0; positions.len()];
+    assignment::assign_object_ids(
+        &octree,
+        &ObjectSet::new(objects),
+        shapes_file.overlap_policy,
+        &mut object_ids,
+    );
+
+    Ok(object_ids)
+}
+
+fn write_output(
+    output: &mut impl Write,
+    format: &OutputFormat,
+    positions: &[DVec3],
+    object_ids: &[u32],
+) -> std::io::Result<()> {
+    match format {
+        OutputFormat::ObjectIds => object_ids
+            .iter()
+            .try_for_each(|object_id| writeln!(output, "{}", object_id)),
+        OutputFormat::AnnotatedXyz => xyz::write_annotated_xyz(output, positions, object_ids),
+    }?;
+
+    output.flush()
+}
+
+fn run(arguments: Arguments) -> Result<(), String> {
+    let positions = read_points(&arguments.points_path)?;
+
+    let shapes_text = fs::read_to_string(&arguments.shapes_path)
+        .map_err(|error| format!("{}: {}", arguments.shapes_path.display(), error))?;
+    let shapes_file = ShapesFile::parse(&shapes_text)?;
+
+    let object_ids = assign_object_ids(&positions, shapes_file)?;
+
+    match &arguments.output_path {
+        Some(path) => {
+            let file =
+                fs::File::create(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+            write_output(
+                &mut BufWriter::new(file),
+                &arguments.output_format,
+                &positions,
+                &object_ids,
+            )
+            .map_err(|error| format!("{}: {}", path.display(), error))
+        }
+        None => write_output(
+            &mut BufWriter::new(std::io::stdout().lock()),
+            &arguments.output_format,
+            &positions,
+            &object_ids,
+        )
+        .map_err(|error| error.to_string()),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let arguments = match parse_arguments(args.into_iter()) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(arguments) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::{assign_object_ids, parse_arguments, OutputFormat, ShapesFile};
+
+    fn to_args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn parses_arguments() {
+        let arguments = parse_arguments(to_args(&[
+            "--shapes",
+            "shapes.json",
+            "--points",
+            "points.las",
+            "--format",
+            "xyz",
+        ]))
+        .unwrap();
+
+        assert_eq!(arguments.points_path.to_str(), Some("points.las"));
+        assert_eq!(arguments.shapes_path.to_str(), Some("shapes.json"));
+        assert_eq!(arguments.output_path, None);
+        assert_eq!(arguments.output_format, OutputFormat::AnnotatedXyz);
+
+        assert!(parse_arguments(to_args(&["--points", "points.las"])).is_err());
+        assert!(parse_arguments(to_args(&["--points"])).is_err());
+        assert!(parse_arguments(to_args(&["--unknown"])).is_err());
+    }
+
+    #[test]
+    fn assigns_points_to_shapes_of_file() {
+        let shapes_file = ShapesFile::parse(
+            r#"{
+                "objects": [
+                    { "object_id": 1, "sphere": { "center": [100, 0, 0], "radius": 1 } },
+                    { "object_id": 70000, "sphere": { "center": [101, 0, 0], "radius": 1 } }
+                ],
+                "overlap_policy": "first_wins",
+                "inflation": { "scale_factor": 1, "max_increase": 0 }
+            }"#,
+        )
+        .unwrap();
+        let positions = vec![
+            vec3(99.5, 0.0, 0.0),
+            vec3(100.5, 0.0, 0.0),
+            vec3(101.5, 0.0, 0.0),
+            vec3(103.0, 0.0, 0.0),
+        ];
+
+        assert_eq!(
+            assign_object_ids(&positions, shapes_file).unwrap(),
+            vec![1, 1, 70000, 0]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use pointclouds_core::inflation::InputInflation;
+use pointclouds_core::inputs::{InputShape, InputTriangleMeshBuffers};
+use pointclouds_core::overlap_policy::OverlapPolicy;
+
+use serde::Deserialize;
+
+/// Contents of the shapes file. It is either a list of shapes in the same format as the objects
+/// passed to `assign_points` in the viewer, or an object holding such a list together with the
+/// meshes and settings of the call
+#[derive(Deserialize)]
+pub struct ShapesFile {
+    pub objects: Vec<InputShape>,
+    #[serde(default)]
+    pub meshes: Vec<InputTriangleMeshBuffers>,
+    #[serde(default)]
+    pub overlap_policy: OverlapPolicy,
+    #[serde(default)]
+    pub inflation: InputInflation,
+}
+
+impl ShapesFile {
+    pub fn parse(text: &str) -> Result<ShapesFile, String> {
+        let value: serde_json::Value = serde_json::from_str(text)
+            .map_err(|error| format!("Shapes file is not valid JSON: {}", error))?;
+
+        let shapes_file = if value.is_array() {
+            serde_json::from_value(value).map(|objects| ShapesFile {
+                objects,
+                meshes: vec![],
+                overlap_policy: OverlapPolicy::default(),
+                inflation: InputInflation::default(),
+            })
+        } else {
+            serde_json::from_value(value)
+        };
+
+        shapes_file.map_err(|error| format!("Got error while deserializing shapes: {}", error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pointclouds_core::overlap_policy::OverlapPolicy;
+
+    use super::ShapesFile;
+
+    const SPHERE: &str = r#"{ "object_id": 3, "sphere": { "center": [0, 0, 0], "radius": 1 } }"#;
+
+    #[test]
+    fn accepts_list_of_shapes() {
+        let shapes_file = ShapesFile::parse(&format!("[{}]", SPHERE)).unwrap();
+
+        assert_eq!(shapes_file.objects.len(), 1);
+        assert_eq!(shapes_file.overlap_policy, OverlapPolicy::LastWins);
+    }
+
+    #[test]
+    fn accepts_shapes_with_settings() {
+        let text = format!(
+            r#"{{ "objects": [{}, {}], "overlap_policy": "first_wins", "inflation": {{ "scale_factor": 1 }} }}"#,
+            SPHERE, SPHERE
+        );
+
+        let shapes_file = ShapesFile::parse(&text).unwrap();
+
+        assert_eq!(shapes_file.objects.len(), 2);
+        assert_eq!(shapes_file.overlap_policy, OverlapPolicy::FirstWins);
+        assert!(shapes_file.meshes.is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_settings_values() {
+        let text = format!(
+            r#"{{ "objects": [{}], "overlap_policy": "random" }}"#,
+            SPHERE
+        );
+
+        assert!(ShapesFile::parse(&text).is_err());
+        assert!(ShapesFile::parse("[{").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use std::io::Write;
+
+use nalgebra_glm::{vec3, DVec3};
+
+/// Parses points from text with one point per line. The first three fields of a line are its x,
+/// y and z coordinates, separated by whitespace or commas, and any further fields are ignored.
+/// Empty lines and lines starting with `#` are skipped
+pub fn parse_xyz(text: &str) -> Result<Vec<DVec3>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(line_index, line)| {
+            let coordinates = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|field| !field.is_empty())
+                .take(3)
+                .map(str::parse::<f64>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|error| format!("Line {}: {}", line_index + 1, error))?;
+
+            match coordinates[..] {
+                [x, y, z] => Ok(vec3(x, y, z)),
+                _ => Err(format!(
+                    "Line {}: expected 3 coordinates, got {}",
+                    line_index + 1,
+                    coordinates.len()
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Writes one line `x y z object_id` per point
+pub fn write_annotated_xyz(
+    output: &mut impl Write,
+    positions: &[DVec3],
+    object_ids: &[u32],
+) -> std::io::Result<()> {
+    for (position, object_id) in positions.iter().zip(object_ids) {
+        writeln!(
+            output,
+            "{} {} {} {}",
+            position.x, position.y, position.z, object_id
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::{parse_xyz, write_annotated_xyz};
+
+    #[test]
+    fn parses_whitespace_and_comma_separated_points() {
+        let text = "# x y z\n1 2 3\n\n4.5,5.5,-6.5,255\n  7\t8 9e1\n";
+
+        assert_eq!(
+            parse_xyz(text).unwrap(),
+            vec![
+                vec3(1.0, 2.0, 3.0),
+                vec3(4.5, 5.5, -6.5),
+                vec3(7.0, 8.0, 90.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_line_of_invalid_point() {
+        assert_eq!(
+            parse_xyz("1 2 3\n1 2\n").unwrap_err(),
+            "Line 2: expected 3 coordinates, got 2"
+        );
+        assert!(parse_xyz("1 2 x\n").unwrap_err().starts_with("Line 1:"));
+    }
+
+    #[test]
+    fn annotated_points_can_be_parsed_again() {
+        let positions = vec![vec3(1.0 / 3.0, 1e7 + 0.125, -2.0)];
+
+        let mut output = vec![];
+        write_annotated_xyz(&mut output, &positions, &[42]).unwrap();
+        let text = String::from_utf8(output).unwrap();
+
+        assert!(text.ends_with(" 42\n"));
+        assert_eq!(parse_xyz(&text).unwrap(), positions);
+    }
+}
@@ -0,0 +1,275 @@
+use nalgebra_glm::DVec3;
+
+use crate::bvh::Bvh;
+use crate::inputs::ParsedObject;
+use crate::linalg::Vec3WithIndex;
+use crate::overlap_policy::{OverlapPolicy, OverlapResolver};
+use crate::point_memberships::PointMemberships;
+use crate::point_octree::PointOctree;
+
+/// Parsed objects together with a bounding volume hierarchy over their shapes. Building it once
+/// and reusing it for the octrees of many sectors keeps the cost of objects far from a sector low
+pub struct ObjectSet {
+    objects: Vec<ParsedObject>,
+    bvh: Bvh,
+}
+
+impl ObjectSet {
+    pub fn new(objects: Vec<ParsedObject>) -> ObjectSet {
+        let bvh = Bvh::new(
+            objects
+                .iter()
+                .map(|object| object.shape.create_bounding_box())
+                .collect(),
+        );
+
+        ObjectSet { objects, bvh }
+    }
+
+    pub fn objects(&self) -> &[ParsedObject] {
+        &self.objects
+    }
+
+    /// Calls `visitor` with every point of `octree` and the index of each object containing it
+    pub fn for_each_point_in_objects(
+        &self,
+        octree: &PointOctree,
+        visitor: impl FnMut(&Vec3WithIndex, usize),
+    ) {
+        octree.for_each_point_in_bvh_items(
+            &self.bvh,
+            |object_index, point| self.objects[object_index].shape.contains_point(point),
+            visitor,
+        );
+    }
+}
+
+/// Offers every (point, object) containment to a resolver with the given policy
+pub fn resolve_overlaps<'a>(
+    octree: &PointOctree,
+    object_set: &'a ObjectSet,
+    overlap_policy: OverlapPolicy,
+) -> OverlapResolver<'a> {
+    let mut resolver =
+        OverlapResolver::new(overlap_policy, object_set.objects(), octree.points().len());
+
+    object_set.for_each_point_in_objects(octree, |point, object_index| {
+        resolver.offer(point, object_index)
+    });
+
+    resolver
+}
+
+/// Writes the id of the object each point is assigned to into `object_ids`, which is indexed by
+/// the original point index. Points outside all objects get id 0
+pub fn assign_object_ids(
+    octree: &PointOctree,
+    object_set: &ObjectSet,
+    overlap_policy: OverlapPolicy,
+    object_ids: &mut [u32],
+) {
+    let resolver = resolve_overlaps(octree, object_set, overlap_policy);
+
+    object_ids.copy_from_slice(&resolver.get_object_ids());
+}
+
+/// Like `assign_object_ids`, but also writes the signed distance from each point to the shape of
+/// the object it was assigned to, which is negative inside the shape. Distances are measured to
+/// the shape before inflation, so points in the inflated margin get small positive distances.
+/// Points outside all objects get distance NaN
+pub fn assign_object_ids_with_distances(
+    octree: &PointOctree,
+    object_set: &ObjectSet,
+    overlap_policy: OverlapPolicy,
+    object_ids: &mut [u32],
+    distances: &mut [f32],
+) {
+    let resolver = resolve_overlaps(octree, object_set, overlap_policy);
+    let objects = object_set.objects();
+
+    object_ids.copy_from_slice(&resolver.get_object_ids());
+
+    distances.fill(f32::NAN);
+    for point in octree.points() {
+        if let Some(object_index) = resolver.get_winning_object_index(point.index) {
+            distances[point.index] = objects[object_index]
+                .get_uninflated_shape()
+                .signed_distance(&point.vec) as f32;
+        }
+    }
+}
+
+/// Every object each point is inside, ordered as the objects were given
+pub fn assign_memberships(octree: &PointOctree, object_set: &ObjectSet) -> PointMemberships {
+    let mut pairs = vec![];
+    object_set.for_each_point_in_objects(octree, |point, object_index| {
+        pairs.push((point.index, object_index))
+    });
+
+    pairs.sort_by_key(|(_, object_index)| *object_index);
+
+    let objects = object_set.objects();
+    let id_pairs: Vec<(usize, u32)> = pairs
+        .iter()
+        .map(|(point_index, object_index)| {
+            (*point_index, objects[*object_index].shape.get_object_id())
+        })
+        .collect();
+
+    PointMemberships::from_pairs(octree.points().len(), &id_pairs)
+}
+
+/// The id of the object a single point would be assigned to, or 0 if it is not inside any object
+pub fn get_object_id_at(
+    position: &DVec3,
+    objects: &[ParsedObject],
+    overlap_policy: OverlapPolicy,
+) -> u32 {
+    let point = Vec3WithIndex {
+        vec: *position,
+        index: 0,
+    };
+    let mut resolver = OverlapResolver::new(overlap_policy, objects, 1);
+
+    objects
+        .iter()
+        .enumerate()
+        .for_each(|(object_index, object)| {
+            if object.shape.create_bounding_box().contains_point(position)
+                && object.shape.contains_point(position)
+            {
+                resolver.offer(&point, object_index);
+            }
+        });
+
+    resolver.get_object_ids()[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{vec3, DMat4};
+
+    use super::{
+        assign_memberships, assign_object_ids, assign_object_ids_with_distances, get_object_id_at,
+        ObjectSet,
+    };
+    use crate::inflation::InputInflation;
+    use crate::inputs::{create_objects, ParsedObject};
+    use crate::linalg::{BoundingBox, Vec3WithIndex};
+    use crate::object_id_width::ObjectIdWidth;
+    use crate::overlap_policy::OverlapPolicy;
+    use crate::point_octree::PointOctree;
+    use crate::shapes::{OrientedBox, Sphere};
+
+    fn create_points_along_x() -> Vec<Vec3WithIndex> {
+        (0..5)
+            .map(|index| Vec3WithIndex {
+                vec: vec3(index as f64 * 0.5 - 1.0, 0.0, 0.0),
+                index,
+            })
+            .collect()
+    }
+
+    fn create_overlapping_objects() -> Vec<ParsedObject> {
+        vec![
+            ParsedObject {
+                shape: Box::new(OrientedBox::new(DMat4::identity(), 1)),
+                priority: 0,
+                uninflated_shape: None,
+            },
+            ParsedObject {
+                shape: Box::new(Sphere::new(vec3(1.0, 0.0, 0.0), 0.6, 2)),
+                priority: 0,
+                uninflated_shape: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn later_objects_win_by_default() {
+        let octree = PointOctree::new(
+            BoundingBox::get_base_cube_bounding_box(),
+            create_points_along_x(),
+        );
+        let object_set = ObjectSet::new(create_overlapping_objects());
+
+        let mut object_ids = vec![0; 5];
+        assign_object_ids(
+            &octree,
+            &object_set,
+            OverlapPolicy::default(),
+            &mut object_ids,
+        );
+
+        assert_eq!(object_ids, vec![1, 1, 1, 2, 2]);
+        assert_eq!(
+            get_object_id_at(
+                &vec3(0.5, 0.0, 0.0),
+                object_set.objects(),
+                OverlapPolicy::FirstWins
+            ),
+            1
+        );
+        assert_eq!(
+            get_object_id_at(
+                &vec3(5.0, 0.0, 0.0),
+                object_set.objects(),
+                OverlapPolicy::FirstWins
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn memberships_are_ordered_as_the_objects() {
+        let octree = PointOctree::new(
+            BoundingBox::get_base_cube_bounding_box(),
+            create_points_along_x(),
+        );
+        let object_set = ObjectSet::new(create_overlapping_objects());
+
+        let memberships = assign_memberships(&octree, &object_set);
+
+        assert_eq!(memberships.offsets, vec![0, 1, 2, 3, 5, 7]);
+        assert_eq!(memberships.object_ids, vec![1, 1, 1, 1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn distances_are_measured_to_the_shape_before_inflation() {
+        let sphere = serde_json::from_str(
+            r#"{ "object_id": 1, "sphere": { "center": [0, 0, 0], "radius": 0.5 } }"#,
+        )
+        .unwrap();
+        let objects = create_objects(
+            vec![sphere],
+            &[],
+            ObjectIdWidth::default(),
+            &InputInflation::default(),
+        )
+        .unwrap();
+        let points = [0.0, 0.5, 0.55]
+            .iter()
+            .enumerate()
+            .map(|(index, x)| Vec3WithIndex {
+                vec: vec3(*x, 0.0, 0.0),
+                index,
+            })
+            .collect();
+        let octree = PointOctree::new(BoundingBox::get_base_cube_bounding_box(), points);
+
+        let mut object_ids = vec![0; 3];
+        let mut distances = vec![0.0; 3];
+        assign_object_ids_with_distances(
+            &octree,
+            &ObjectSet::new(objects),
+            OverlapPolicy::default(),
+            &mut object_ids,
+            &mut distances,
+        );
+
+        assert_eq!(object_ids, vec![1, 1, 1]);
+        assert!((distances[0] + 0.5).abs() < 1e-6);
+        assert!(distances[1].abs() < 1e-6);
+        assert!((distances[2] - 0.05).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,366 @@
+use std::cell::Cell;
+use std::ops::Range;
+
+use nalgebra_glm::DVec3;
+
+use crate::linalg::BoundingBox;
+
+pub const MAX_ITEMS_PER_LEAF: usize = 4;
+
+#[derive(Debug)]
+enum BvhNodeContent {
+    Children([usize; 2]),
+    Items(Range<usize>),
+}
+
+#[derive(Debug)]
+struct BvhNode {
+    bounding_box: BoundingBox,
+    content: BvhNodeContent,
+}
+
+/// Bounding volume hierarchy over a list of bounding boxes. Queries report indices into the
+/// list the hierarchy was built from
+#[derive(Debug)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    item_indices: Vec<usize>,
+    item_boxes: Vec<BoundingBox>,
+}
+
+impl Bvh {
+    pub fn new(item_boxes: Vec<BoundingBox>) -> Self {
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(2 * item_boxes.len() / MAX_ITEMS_PER_LEAF + 1),
+            item_indices: (0..item_boxes.len()).collect(),
+            item_boxes,
+        };
+
+        let item_centers: Vec<DVec3> = bvh.item_boxes.iter().map(BoundingBox::center).collect();
+        bvh.build_node(0..bvh.item_indices.len(), &item_centers);
+
+        bvh
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        self.nodes[0].bounding_box
+    }
+
+    fn build_node(&mut self, range: Range<usize>, item_centers: &[DVec3]) -> usize {
+        let bounding_box = self.item_indices[range.clone()]
+            .iter()
+            .fold(BoundingBox::default(), |union, &item| {
+                union.union(&self.item_boxes[item])
+            });
+
+        let node_index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bounding_box,
+            content: BvhNodeContent::Items(range.clone()),
+        });
+
+        if range.len() <= MAX_ITEMS_PER_LEAF {
+            return node_index;
+        }
+
+        let split_axis = self.get_split_axis(&range, item_centers);
+        let middle = range.start + range.len() / 2;
+        self.item_indices[range.clone()].select_nth_unstable_by(middle - range.start, |a, b| {
+            item_centers[*a][split_axis].total_cmp(&item_centers[*b][split_axis])
+        });
+
+        let left = self.build_node(range.start..middle, item_centers);
+        let right = self.build_node(middle..range.end, item_centers);
+
+        self.nodes[node_index].content = BvhNodeContent::Children([left, right]);
+
+        node_index
+    }
+
+    /// Split along the axis where the item centers are spread the most
+    fn get_split_axis(&self, range: &Range<usize>, item_centers: &[DVec3]) -> usize {
+        let center_bounds: BoundingBox = self.item_indices[range.clone()]
+            .iter()
+            .map(|&item| item_centers[item])
+            .collect();
+
+        center_bounds.extent().imax()
+    }
+
+    /// Returns the first item whose bounding box contains `point` and for which `predicate` holds
+    pub fn find_containing(
+        &self,
+        point: &DVec3,
+        mut predicate: impl FnMut(usize) -> bool,
+    ) -> Option<usize> {
+        let mut found = None;
+
+        self.visit(
+            &mut |node_box| node_box.contains_point(point),
+            &mut |item| {
+                if predicate(item) {
+                    found = Some(item);
+                }
+                found.is_some()
+            },
+        );
+
+        found
+    }
+
+    /// Calls `visitor` with every item whose bounding box is hit by the ray from `origin` along
+    /// `direction`
+    pub fn for_each_hit_by_ray(
+        &self,
+        origin: &DVec3,
+        direction: &DVec3,
+        mut visitor: impl FnMut(usize),
+    ) {
+        self.visit(
+            &mut |node_box| node_box.intersects_ray(origin, direction),
+            &mut |item| {
+                visitor(item);
+                false
+            },
+        );
+    }
+
+    /// Smallest `item_distance` over all items, skipping items and nodes whose bounding box is
+    /// further from `point` than the smallest distance found so far. `item_distance` must never
+    /// be smaller than the distance from `point` to the item's bounding box, but may be negative
+    /// for items whose bounding box contains `point`, like signed distances
+    pub fn find_min_distance(
+        &self,
+        point: &DVec3,
+        mut item_distance: impl FnMut(usize) -> f64,
+    ) -> f64 {
+        let min_distance = Cell::new(f64::INFINITY);
+
+        self.visit(
+            &mut |node_box| node_box.distance_to_point(point) <= min_distance.get().max(0.0),
+            &mut |item| {
+                min_distance.set(min_distance.get().min(item_distance(item)));
+                false
+            },
+        );
+
+        min_distance.get()
+    }
+
+    /// Frontier covering all items, to start a joint traversal with another tree from. See
+    /// `refine_frontier`
+    pub fn get_root_frontier(&self) -> Vec<usize> {
+        if self.item_indices.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        }
+    }
+
+    /// A frontier is a set of nodes covering the items that may overlap some region. This narrows
+    /// `frontier` down to the nodes overlapping `bounding_box`, replacing nodes that are larger
+    /// than the box by their children. Refining the frontier as the nodes of another tree get
+    /// smaller traverses both trees jointly
+    pub fn refine_frontier(&self, frontier: &[usize], bounding_box: &BoundingBox) -> Vec<usize> {
+        let box_size = bounding_box.extent().max();
+
+        let mut refined = vec![];
+        let mut stack = frontier.to_vec();
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+
+            if !node.bounding_box.overlaps(bounding_box) {
+                continue;
+            }
+
+            match &node.content {
+                BvhNodeContent::Children(children)
+                    if node.bounding_box.extent().max() > box_size =>
+                {
+                    stack.extend(children)
+                }
+                _ => refined.push(node_index),
+            }
+        }
+
+        refined
+    }
+
+    /// Items below the nodes of `frontier` whose bounding box overlaps `bounding_box`
+    pub fn get_items_overlapping(
+        &self,
+        frontier: &[usize],
+        bounding_box: &BoundingBox,
+    ) -> Vec<usize> {
+        let mut items = vec![];
+
+        self.visit_from(
+            frontier,
+            &mut |node_box| node_box.overlaps(bounding_box),
+            &mut |item| {
+                items.push(item);
+                false
+            },
+        );
+
+        items
+    }
+
+    fn visit(
+        &self,
+        node_filter: &mut impl FnMut(&BoundingBox) -> bool,
+        item_visitor: &mut impl FnMut(usize) -> bool,
+    ) {
+        self.visit_from(&self.get_root_frontier(), node_filter, item_visitor);
+    }
+
+    /// Depth-first traversal of all nodes below `start_nodes` accepted by `node_filter`. Items in
+    /// accepted leaves are passed on to `item_visitor` if their own bounding box is accepted as
+    /// well. Traversal stops as soon as `item_visitor` returns true
+    fn visit_from(
+        &self,
+        start_nodes: &[usize],
+        node_filter: &mut impl FnMut(&BoundingBox) -> bool,
+        item_visitor: &mut impl FnMut(usize) -> bool,
+    ) {
+        let mut stack = start_nodes.to_vec();
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+
+            if !node_filter(&node.bounding_box) {
+                continue;
+            }
+
+            match &node.content {
+                BvhNodeContent::Children(children) => stack.extend(children),
+                BvhNodeContent::Items(range) => {
+                    for &item in &self.item_indices[range.clone()] {
+                        if node_filter(&self.item_boxes[item]) && item_visitor(item) {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::Bvh;
+    use crate::linalg::BoundingBox;
+
+    fn create_unit_boxes_along_x(count: usize) -> Vec<BoundingBox> {
+        (0..count)
+            .map(|i| BoundingBox {
+                min: vec3(i as f64, 0.0, 0.0),
+                max: vec3(i as f64 + 1.0, 1.0, 1.0),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bvh_bounding_box_encloses_all_items() {
+        let bvh = Bvh::new(create_unit_boxes_along_x(100));
+
+        assert_eq!(bvh.bounding_box().min, vec3(0.0, 0.0, 0.0));
+        assert_eq!(bvh.bounding_box().max, vec3(100.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn find_containing_returns_box_around_point() {
+        let bvh = Bvh::new(create_unit_boxes_along_x(100));
+
+        assert_eq!(
+            bvh.find_containing(&vec3(42.5, 0.5, 0.5), |_| true),
+            Some(42)
+        );
+        assert_eq!(bvh.find_containing(&vec3(42.5, 1.5, 0.5), |_| true), None);
+        assert_eq!(bvh.find_containing(&vec3(42.5, 0.5, 0.5), |_| false), None);
+    }
+
+    #[test]
+    fn ray_visits_only_boxes_along_its_path() {
+        let bvh = Bvh::new(create_unit_boxes_along_x(100));
+
+        let mut visited = vec![];
+        bvh.for_each_hit_by_ray(&vec3(50.5, 5.0, 0.5), &vec3(0.0, -1.0, 0.0), |item| {
+            visited.push(item)
+        });
+        assert_eq!(visited, vec![50]);
+
+        visited.clear();
+        bvh.for_each_hit_by_ray(&vec3(50.5, 0.5, 0.5), &vec3(1.0, 0.0, 0.0), |item| {
+            visited.push(item)
+        });
+        assert_eq!(visited.len(), 50);
+    }
+
+    #[test]
+    fn min_distance_visits_only_nearby_boxes() {
+        let boxes = create_unit_boxes_along_x(100);
+        let point = vec3(42.5, 0.5, 0.5);
+
+        let mut visited = 0;
+        let min_distance = Bvh::new(boxes.clone()).find_min_distance(&point, |item| {
+            visited += 1;
+            boxes[item].distance_to_point(&point) - if item == 42 { 0.5 } else { 0.0 }
+        });
+
+        assert_eq!(min_distance, -0.5);
+        assert!(visited < 50, "{}", visited);
+    }
+
+    #[test]
+    fn min_distance_finds_deepest_of_boxes_around_point() {
+        let point = vec3(0.5, 0.5, 0.5);
+        for depths in [[0.1, 0.3], [0.3, 0.1]] {
+            let bvh = Bvh::new(vec![
+                BoundingBox {
+                    min: vec3(0.0, 0.0, 0.0),
+                    max: vec3(1.0, 1.0, 1.0),
+                };
+                2
+            ]);
+
+            assert_eq!(bvh.find_min_distance(&point, |item| -depths[item]), -0.3);
+        }
+    }
+
+    #[test]
+    fn empty_bvh_finds_nothing() {
+        let bvh = Bvh::new(vec![]);
+
+        assert_eq!(bvh.find_containing(&vec3(0.0, 0.0, 0.0), |_| true), None);
+    }
+
+    #[test]
+    fn refined_frontier_covers_exactly_the_overlapping_items() {
+        let bvh = Bvh::new(create_unit_boxes_along_x(100));
+        let region = BoundingBox {
+            min: vec3(10.5, 0.2, 0.2),
+            max: vec3(12.5, 0.8, 0.8),
+        };
+
+        let frontier = bvh.refine_frontier(&bvh.get_root_frontier(), &region);
+
+        let mut covered = bvh.get_items_overlapping(&frontier, &region);
+        covered.sort_unstable();
+
+        assert_eq!(covered, vec![10, 11, 12]);
+        assert!(frontier.len() < 10);
+        assert!(bvh
+            .refine_frontier(
+                &frontier,
+                &BoundingBox {
+                    min: vec3(50.0, 0.0, 0.0),
+                    max: vec3(51.0, 1.0, 1.0),
+                }
+            )
+            .is_empty());
+    }
+}
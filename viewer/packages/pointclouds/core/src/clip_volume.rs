@@ -0,0 +1,111 @@
+use nalgebra_glm::{dot, DVec3};
+
+use crate::linalg::{BoundingBox, Plane};
+
+/// How a bounding box lies relative to a `ClipVolume`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoxClassification {
+    Inside,
+    Outside,
+    Intersecting,
+}
+
+/// Convex volume of the points `p` with `dot(normal, p) <= offset` for all of its planes, like a
+/// camera frustum or a section box. Without planes, it contains everything
+#[derive(Clone, Debug)]
+pub struct ClipVolume {
+    planes: Vec<Plane>,
+}
+
+impl ClipVolume {
+    pub fn new(planes: Vec<Plane>) -> ClipVolume {
+        ClipVolume { planes }
+    }
+
+    pub fn contains_point(&self, point: &DVec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) <= 0.0)
+    }
+
+    pub fn classify_box(&self, bounding_box: &BoundingBox) -> BoxClassification {
+        let center = bounding_box.center();
+        let half_extent = bounding_box.extent() / 2.0;
+
+        let mut classification = BoxClassification::Inside;
+
+        for plane in &self.planes {
+            let center_distance = plane.signed_distance(&center);
+            let radius = dot(&plane.normal.abs(), &half_extent);
+
+            if center_distance - radius > 0.0 {
+                return BoxClassification::Outside;
+            }
+
+            if center_distance + radius > 0.0 {
+                classification = BoxClassification::Intersecting;
+            }
+        }
+
+        classification
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::{BoxClassification, ClipVolume};
+    use crate::linalg::{BoundingBox, Plane};
+
+    fn create_unit_section_box() -> ClipVolume {
+        ClipVolume::new(
+            (0..3)
+                .flat_map(|axis| {
+                    let mut normal = vec3(0.0, 0.0, 0.0);
+                    normal[axis] = 1.0;
+                    [Plane::new(normal, 1.0), Plane::new(-normal, 1.0)]
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn boxes_are_classified_against_section_box() {
+        let section_box = create_unit_section_box();
+
+        let inside = BoundingBox {
+            min: vec3(-0.5, -0.5, -0.5),
+            max: vec3(0.5, 0.5, 0.5),
+        };
+        let outside = BoundingBox {
+            min: vec3(1.5, -0.5, -0.5),
+            max: vec3(2.5, 0.5, 0.5),
+        };
+        let intersecting = BoundingBox {
+            min: vec3(0.5, -0.5, -0.5),
+            max: vec3(1.5, 0.5, 0.5),
+        };
+
+        assert_eq!(section_box.classify_box(&inside), BoxClassification::Inside);
+        assert_eq!(
+            section_box.classify_box(&outside),
+            BoxClassification::Outside
+        );
+        assert_eq!(
+            section_box.classify_box(&intersecting),
+            BoxClassification::Intersecting
+        );
+    }
+
+    #[test]
+    fn volume_without_planes_contains_everything() {
+        let volume = ClipVolume::new(vec![]);
+
+        assert!(volume.contains_point(&vec3(1e9, -1e9, 0.0)));
+        assert_eq!(
+            volume.classify_box(&BoundingBox::infinite()),
+            BoxClassification::Inside
+        );
+    }
+}
@@ -0,0 +1,646 @@
+//! Reading of COPC (Cloud Optimized Point Cloud) files. A COPC file is a LAS 1.4 file with LAZ
+//! compressed points, where each chunk holds the points of one octree node. The octree is
+//! described by hierarchy pages, which list the byte range of each node and of further pages.
+//! All parts are located by byte ranges, so only the header, the pages and the nodes in view
+//! have to be fetched
+
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+
+use nalgebra_glm::{vec3, DVec3};
+
+use crate::ept::{self, DecodedEptTile};
+use crate::las::{self, LasHeader};
+use crate::laz::{self, LazVlr, LAYERED_CHUNKED_COMPRESSOR};
+
+pub const COPC_USER_ID: &str = "copc";
+pub const COPC_INFO_RECORD_ID: u16 = 1;
+pub const COPC_HIERARCHY_RECORD_ID: u16 = 1000;
+
+const COPC_INFO_SIZE: usize = 160;
+const HIERARCHY_ENTRY_SIZE: usize = 32;
+
+fn read_f64(bytes: &[u8], offset: usize) -> f64 {
+    f64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i32(bytes: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Offsets and sizes are read from the file, so a range past the end of a `u64` is an error
+fn get_range(offset: u64, size: u64) -> Result<Range<u64>, String> {
+    offset
+        .checked_add(size)
+        .map(|end| offset..end)
+        .ok_or_else(|| {
+            format!(
+                "COPC range of {} bytes at {} is out of bounds",
+                size, offset
+            )
+        })
+}
+
+/// The bytes of a range of a file that is fully in memory
+pub fn get_bytes<'a>(bytes: &'a [u8], range: &Range<u64>) -> Result<&'a [u8], String> {
+    usize::try_from(range.start)
+        .ok()
+        .zip(usize::try_from(range.end).ok())
+        .and_then(|(start, end)| bytes.get(start..end))
+        .ok_or_else(|| format!("COPC file ended before byte {}", range.end))
+}
+
+/// The COPC info VLR. The octree is a cube around `center`, and the root hierarchy page is at
+/// an absolute offset in the file
+#[derive(Clone, Debug, PartialEq)]
+pub struct CopcInfo {
+    pub center: DVec3,
+    pub halfsize: f64,
+    /// Distance between points at the root level
+    pub spacing: f64,
+    pub root_hierarchy_offset: u64,
+    pub root_hierarchy_size: u64,
+    pub gps_time_min: f64,
+    pub gps_time_max: f64,
+}
+
+impl CopcInfo {
+    pub fn parse(data: &[u8]) -> Result<CopcInfo, String> {
+        if data.len() < COPC_INFO_SIZE {
+            return Err(format!(
+                "COPC info VLR must be {} bytes, got {}",
+                COPC_INFO_SIZE,
+                data.len()
+            ));
+        }
+
+        Ok(CopcInfo {
+            center: vec3(read_f64(data, 0), read_f64(data, 8), read_f64(data, 16)),
+            halfsize: read_f64(data, 24),
+            spacing: read_f64(data, 32),
+            root_hierarchy_offset: read_u64(data, 40),
+            root_hierarchy_size: read_u64(data, 48),
+            gps_time_min: read_f64(data, 56),
+            gps_time_max: read_f64(data, 64),
+        })
+    }
+
+    pub fn root_hierarchy_range(&self) -> Result<Range<u64>, String> {
+        get_range(self.root_hierarchy_offset, self.root_hierarchy_size)
+    }
+}
+
+/// An octree node, by its level and its position among the `2^level` nodes along each axis
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VoxelKey {
+    pub level: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl VoxelKey {
+    pub const ROOT: VoxelKey = VoxelKey {
+        level: 0,
+        x: 0,
+        y: 0,
+        z: 0,
+    };
+
+    /// The name of the node in the EPT hierarchy, `level-x-y-z`, which the octree loader uses
+    /// as key
+    pub fn name(&self) -> String {
+        format!("{}-{}-{}-{}", self.level, self.x, self.y, self.z)
+    }
+
+    pub fn parent(&self) -> Option<VoxelKey> {
+        (self.level > 0).then_some(VoxelKey {
+            level: self.level - 1,
+            x: self.x >> 1,
+            y: self.y >> 1,
+            z: self.z >> 1,
+        })
+    }
+
+    /// Minimum and maximum corner of the node
+    pub fn bounds(&self, info: &CopcInfo) -> (DVec3, DVec3) {
+        let size = 2.0 * info.halfsize / 2f64.powi(self.level);
+        let min = info.center.add_scalar(-info.halfsize)
+            + vec3(self.x as f64, self.y as f64, self.z as f64) * size;
+        (min, min.add_scalar(size))
+    }
+}
+
+/// An entry of a hierarchy page. It is either a node with `point_count` points in one LAZ
+/// chunk, or, with a point count of -1, another hierarchy page for the nodes below `key`
+#[derive(Clone, Debug, PartialEq)]
+pub struct CopcEntry {
+    pub key: VoxelKey,
+    pub offset: u64,
+    pub byte_size: usize,
+    pub point_count: i32,
+}
+
+impl CopcEntry {
+    pub fn is_page(&self) -> bool {
+        self.point_count == -1
+    }
+
+    pub fn byte_range(&self) -> Result<Range<u64>, String> {
+        get_range(self.offset, self.byte_size as u64)
+    }
+}
+
+pub fn parse_hierarchy_page(bytes: &[u8]) -> Result<Vec<CopcEntry>, String> {
+    if bytes.len() % HIERARCHY_ENTRY_SIZE != 0 {
+        return Err(format!(
+            "COPC hierarchy page of {} bytes does not hold a whole number of entries",
+            bytes.len()
+        ));
+    }
+
+    bytes
+        .chunks_exact(HIERARCHY_ENTRY_SIZE)
+        .map(|entry| {
+            let key = VoxelKey {
+                level: read_i32(entry, 0),
+                x: read_i32(entry, 4),
+                y: read_i32(entry, 8),
+                z: read_i32(entry, 12),
+            };
+            let byte_size = read_i32(entry, 24);
+            let point_count = read_i32(entry, 28);
+
+            if key.level < 0 || key.x < 0 || key.y < 0 || key.z < 0 || key.level > 30 {
+                return Err(format!("Invalid COPC node key {}", key.name()));
+            }
+            if byte_size < 0 || point_count < -1 {
+                return Err(format!(
+                    "COPC node {} has {} bytes and {} points",
+                    key.name(),
+                    byte_size,
+                    point_count
+                ));
+            }
+
+            Ok(CopcEntry {
+                key,
+                offset: read_u64(entry, 16),
+                byte_size: byte_size as usize,
+                point_count,
+            })
+        })
+        .collect()
+}
+
+/// Point counts by node name, in the format of the EPT hierarchy files. Pages that are not
+/// loaded yet have a count of -1
+pub fn hierarchy_point_counts(entries: &[CopcEntry]) -> BTreeMap<String, i32> {
+    entries
+        .iter()
+        .map(|entry| (entry.key.name(), entry.point_count))
+        .collect()
+}
+
+/// What is needed to read the hierarchy and the nodes of a COPC file, parsed from the start of
+/// the file up to the point data
+#[derive(Clone, Debug)]
+pub struct CopcFile {
+    pub header: LasHeader,
+    pub info: CopcInfo,
+    pub laz_vlr: LazVlr,
+}
+
+impl CopcFile {
+    pub fn parse(bytes: &[u8]) -> Result<CopcFile, String> {
+        let header = LasHeader::parse(bytes)?;
+
+        if header.version != (1, 4) || !(6..=8).contains(&header.format_id()) {
+            return Err(format!(
+                "COPC files must be LAS 1.4 with point data format 6, 7 or 8, got LAS {}.{} with \
+                 format {}",
+                header.version.0,
+                header.version.1,
+                header.format_id()
+            ));
+        }
+
+        let info = las::read_vlrs(bytes, &header)?
+            .into_iter()
+            .find(|vlr| vlr.user_id == COPC_USER_ID && vlr.record_id == COPC_INFO_RECORD_ID)
+            .ok_or_else(|| "COPC file has no COPC info VLR".to_string())
+            .and_then(|vlr| CopcInfo::parse(vlr.data))?;
+
+        let laz_vlr = LazVlr::find(bytes, &header)?;
+        if laz_vlr.compressor != LAYERED_CHUNKED_COMPRESSOR {
+            return Err(format!(
+                "COPC points must be compressed in layers, got LAZ compressor {}",
+                laz_vlr.compressor
+            ));
+        }
+        if laz_vlr.point_size() != header.point_record_length as usize {
+            return Err(format!(
+                "LAZ items make {} byte points, but the header has {} byte points",
+                laz_vlr.point_size(),
+                header.point_record_length
+            ));
+        }
+
+        Ok(CopcFile {
+            header,
+            info,
+            laz_vlr,
+        })
+    }
+
+    /// Reads all hierarchy pages of a file that is fully in memory, giving the entries of all
+    /// nodes. Each page is read once, so pages that refer back to each other are an error
+    pub fn read_hierarchy(&self, bytes: &[u8]) -> Result<Vec<CopcEntry>, String> {
+        let mut nodes = vec![];
+        let mut pages = vec![self.info.root_hierarchy_range()?];
+        let mut visited_offsets = HashSet::new();
+        while let Some(page) = pages.pop() {
+            if !visited_offsets.insert(page.start) {
+                return Err(format!(
+                    "COPC hierarchy page at {} is referred to more than once",
+                    page.start
+                ));
+            }
+
+            for entry in parse_hierarchy_page(get_bytes(bytes, &page)?)? {
+                if entry.is_page() {
+                    pages.push(entry.byte_range()?);
+                } else {
+                    nodes.push(entry);
+                }
+            }
+        }
+
+        nodes.sort_by_key(|entry| entry.key);
+        Ok(nodes)
+    }
+
+    /// Decompresses the chunk of a node, fetched from the byte range of its entry, into
+    /// uncompressed point records
+    pub fn decode_node(&self, chunk: &[u8], entry: &CopcEntry) -> Result<Vec<u8>, String> {
+        if entry.is_page() {
+            return Err(format!(
+                "COPC entry {} is a hierarchy page, not a node",
+                entry.key.name()
+            ));
+        }
+        if chunk.len() != entry.byte_size {
+            return Err(format!(
+                "COPC node {} has {} bytes, got {}",
+                entry.key.name(),
+                entry.byte_size,
+                chunk.len()
+            ));
+        }
+
+        let num_points = usize::try_from(entry.point_count).map_err(|_| {
+            format!(
+                "COPC node {} has {} points",
+                entry.key.name(),
+                entry.point_count
+            )
+        })?;
+        laz::decompress_chunk(chunk, &self.laz_vlr, num_points)
+    }
+
+    /// Decodes the chunk of a node into the same buffers as an EPT tile, with positions
+    /// relative to the minimum corner of the node
+    pub fn decode_node_tile(
+        &self,
+        chunk: &[u8],
+        entry: &CopcEntry,
+        point_offset: &DVec3,
+    ) -> Result<DecodedEptTile, String> {
+        let records = self.decode_node(chunk, entry)?;
+        let (mins, _) = entry.key.bounds(&self.info);
+        ept::decode_las_point_records(&records, &self.header, &mins, point_offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::{
+        get_bytes, hierarchy_point_counts, parse_hierarchy_page, CopcEntry, CopcFile, VoxelKey,
+        COPC_HIERARCHY_RECORD_ID, COPC_INFO_RECORD_ID, COPC_USER_ID,
+    };
+    use crate::las;
+    use crate::laz::test_encoder::{
+        compress_chunk, create_chunk_table, create_las_header, create_laszip_vlr_data,
+        create_laz_file, create_vlr, items_for_format,
+    };
+    use crate::laz::test_fixtures::{assert_points_match_reference, read_fixture};
+    use crate::laz::{LASZIP_RECORD_ID, LASZIP_USER_ID};
+
+    const POINT_SIZE: usize = 30;
+
+    fn key(level: i32, x: i32, y: i32, z: i32) -> VoxelKey {
+        VoxelKey { level, x, y, z }
+    }
+
+    /// Format 6 records at positions in units of 0.01
+    fn create_records(positions: &[[i32; 3]], classification: u8) -> Vec<u8> {
+        let mut records = vec![];
+        for position in positions {
+            let mut record = [0u8; POINT_SIZE];
+            for (i, value) in position.iter().enumerate() {
+                record[4 * i..4 * i + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            record[14] = 0x11;
+            record[16] = classification;
+            records.extend(record);
+        }
+        records
+    }
+
+    fn hierarchy_entry(key: VoxelKey, offset: u64, byte_size: usize, point_count: i32) -> Vec<u8> {
+        let mut bytes = vec![];
+        for value in [key.level, key.x, key.y, key.z] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(offset.to_le_bytes());
+        bytes.extend((byte_size as i32).to_le_bytes());
+        bytes.extend(point_count.to_le_bytes());
+        bytes
+    }
+
+    /// A COPC file with a cube from 0 to 100 holding `nodes`. The nodes below `paged_key` are in
+    /// a hierarchy page of their own
+    fn create_copc_file(nodes: &[(VoxelKey, Vec<u8>)], paged_key: VoxelKey) -> Vec<u8> {
+        let items = items_for_format(6, 0);
+        let num_points = nodes
+            .iter()
+            .map(|(_, records)| records.len())
+            .sum::<usize>()
+            / POINT_SIZE;
+
+        let mut info = vec![];
+        for value in [50.0f64, 50.0, 50.0, 50.0, 1.0] {
+            info.extend(value.to_le_bytes());
+        }
+        // The root page is written last, and its range filled in then
+        let root_hierarchy_position = 375 + 54 + info.len();
+        info.resize(160, 0);
+
+        let vlrs = [
+            create_vlr(COPC_USER_ID, COPC_INFO_RECORD_ID, &info),
+            create_vlr(
+                LASZIP_USER_ID,
+                LASZIP_RECORD_ID,
+                &create_laszip_vlr_data(&items, u32::MAX),
+            ),
+        ];
+        let mut bytes = create_las_header(6, POINT_SIZE, num_points, &vlrs);
+        let point_data_start = bytes.len();
+        bytes.extend(0i64.to_le_bytes());
+
+        let mut entries = vec![];
+        for (key, records) in nodes {
+            let chunk = compress_chunk(records, &items);
+            let point_count = (records.len() / POINT_SIZE) as i32;
+            entries.push((*key, bytes.len() as u64, chunk.len(), point_count));
+            bytes.extend(chunk);
+        }
+
+        let chunk_table_offset = bytes.len() as i64;
+        bytes[point_data_start..point_data_start + 8]
+            .copy_from_slice(&chunk_table_offset.to_le_bytes());
+        let chunk_sizes: Vec<_> = entries.iter().map(|entry| entry.3 as usize).collect();
+        let chunk_byte_sizes: Vec<_> = entries.iter().map(|entry| entry.2).collect();
+        bytes.extend(create_chunk_table(&chunk_sizes, &chunk_byte_sizes, true));
+
+        let is_paged = |key: &VoxelKey| {
+            let mut ancestor = Some(*key);
+            while let Some(current) = ancestor {
+                if current == paged_key {
+                    return true;
+                }
+                ancestor = current.parent();
+            }
+            false
+        };
+
+        let mut child_page = vec![];
+        let mut root_page = vec![];
+        for &(key, offset, byte_size, point_count) in &entries {
+            let page = if is_paged(&key) {
+                &mut child_page
+            } else {
+                &mut root_page
+            };
+            page.extend(hierarchy_entry(key, offset, byte_size, point_count));
+        }
+
+        let evlr_header = create_vlr(COPC_USER_ID, COPC_HIERARCHY_RECORD_ID, &[]);
+        bytes.extend(&evlr_header[..54]);
+        bytes.extend([0u8; 6]);
+        let child_page_offset = bytes.len() as u64;
+        bytes.extend(&child_page);
+        root_page.extend(hierarchy_entry(
+            paged_key,
+            child_page_offset,
+            child_page.len(),
+            -1,
+        ));
+        let root_page_offset = bytes.len() as u64;
+        bytes.extend(&root_page);
+
+        bytes[root_hierarchy_position..root_hierarchy_position + 8]
+            .copy_from_slice(&root_page_offset.to_le_bytes());
+        bytes[root_hierarchy_position + 8..root_hierarchy_position + 16]
+            .copy_from_slice(&(root_page.len() as u64).to_le_bytes());
+
+        bytes
+    }
+
+    fn create_nodes() -> Vec<(VoxelKey, Vec<u8>)> {
+        vec![
+            (
+                key(0, 0, 0, 0),
+                create_records(&[[0, 0, 0], [5000, 5000, 5000], [10000, 10000, 10000]], 1),
+            ),
+            (key(1, 0, 0, 0), create_records(&[[100, 200, 300]], 2)),
+            (
+                key(1, 1, 1, 1),
+                create_records(&[[6000, 7000, 8000], [9000, 9000, 9000]], 3),
+            ),
+            (
+                key(2, 0, 0, 1),
+                create_records(&[[1000, 1000, 3000], [2000, 2000, 4000]], 4),
+            ),
+        ]
+    }
+
+    #[test]
+    fn node_bounds_divide_the_cube() {
+        let bytes = create_copc_file(&create_nodes(), key(1, 0, 0, 0));
+        let file = CopcFile::parse(&bytes).unwrap();
+
+        assert_eq!(
+            key(1, 1, 0, 1).bounds(&file.info),
+            (vec3(50.0, 0.0, 50.0), vec3(100.0, 50.0, 100.0))
+        );
+        assert_eq!(key(2, 0, 0, 1).name(), "2-0-0-1");
+        assert_eq!(key(2, 3, 2, 1).parent(), Some(key(1, 1, 1, 0)));
+        assert_eq!(VoxelKey::ROOT.parent(), None);
+    }
+
+    #[test]
+    fn hierarchy_is_read_page_by_page() {
+        let bytes = create_copc_file(&create_nodes(), key(1, 0, 0, 0));
+
+        // Only the start of the file is needed to read the header and the VLRs
+        let file = CopcFile::parse(&bytes[..file_start_length(&bytes)]).unwrap();
+        assert_eq!(file.info.spacing, 1.0);
+
+        let range = file.info.root_hierarchy_range().unwrap();
+        let root_page = parse_hierarchy_page(get_bytes(&bytes, &range).unwrap()).unwrap();
+        let point_counts = hierarchy_point_counts(&root_page);
+        assert_eq!(
+            point_counts.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("0-0-0-0".to_string(), 3),
+                ("1-0-0-0".to_string(), -1),
+                ("1-1-1-1".to_string(), 2)
+            ]
+        );
+
+        let page = root_page.iter().find(|entry| entry.is_page()).unwrap();
+        let range = page.byte_range().unwrap();
+        let child_page = parse_hierarchy_page(get_bytes(&bytes, &range).unwrap()).unwrap();
+        assert_eq!(
+            child_page.iter().map(|entry| entry.key).collect::<Vec<_>>(),
+            vec![key(1, 0, 0, 0), key(2, 0, 0, 1)]
+        );
+
+        let all_nodes = file.read_hierarchy(&bytes).unwrap();
+        assert_eq!(all_nodes.len(), 4);
+        assert!(all_nodes.iter().all(|entry| !entry.is_page()));
+    }
+
+    fn file_start_length(bytes: &[u8]) -> usize {
+        las::LasHeader::parse(bytes).unwrap().offset_to_point_data as usize
+    }
+
+    #[test]
+    fn nodes_are_decoded_from_their_byte_ranges() {
+        let nodes = create_nodes();
+        let bytes = create_copc_file(&nodes, key(1, 0, 0, 0));
+        let file = CopcFile::parse(&bytes).unwrap();
+
+        for entry in file.read_hierarchy(&bytes).unwrap() {
+            let chunk = get_bytes(&bytes, &entry.byte_range().unwrap()).unwrap();
+            let records = &nodes.iter().find(|(key, _)| *key == entry.key).unwrap().1;
+
+            assert_eq!(&file.decode_node(chunk, &entry).unwrap(), records);
+        }
+
+        // The whole file can also be read as a LAZ file
+        let (_, records) = las::read_point_records(&bytes).unwrap();
+        let all_records: Vec<u8> = nodes.into_iter().flat_map(|(_, records)| records).collect();
+        assert_eq!(records.to_vec(), all_records);
+    }
+
+    #[test]
+    fn nodes_are_decoded_relative_to_their_corner() {
+        let bytes = create_copc_file(&create_nodes(), key(1, 0, 0, 0));
+        let file = CopcFile::parse(&bytes).unwrap();
+        let entry = file
+            .read_hierarchy(&bytes)
+            .unwrap()
+            .into_iter()
+            .find(|entry| entry.key == key(1, 1, 1, 1))
+            .unwrap();
+        let chunk = get_bytes(&bytes, &entry.byte_range().unwrap()).unwrap();
+
+        let decoded = file
+            .decode_node_tile(chunk, &entry, &vec3(1.0, 2.0, 3.0))
+            .unwrap();
+
+        assert_eq!(decoded.num_points, 2);
+        assert_eq!(decoded.positions, vec![10.0, 20.0, 30.0, 40.0, 40.0, 40.0]);
+        assert_eq!(decoded.classifications, Some(vec![3, 3]));
+        assert_eq!(decoded.points[0].vec, vec3(11.0, 22.0, 33.0));
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        let bytes = create_copc_file(&create_nodes(), key(1, 0, 0, 0));
+        let file = CopcFile::parse(&bytes).unwrap();
+
+        let laz_file = create_laz_file(6, 0, &create_records(&[[0, 0, 0]], 0), &[1]);
+        assert!(CopcFile::parse(&laz_file).is_err());
+        assert!(CopcFile::parse(&bytes[..500]).is_err());
+
+        assert!(parse_hierarchy_page(&[0; 33]).is_err());
+        let mut negative_key = [0u8; 32];
+        negative_key[4..8].copy_from_slice(&(-1i32).to_le_bytes());
+        assert!(parse_hierarchy_page(&negative_key).is_err());
+
+        let entry = file.read_hierarchy(&bytes).unwrap().remove(0);
+        let chunk = get_bytes(&bytes, &entry.byte_range().unwrap()).unwrap();
+        assert!(file.decode_node(&chunk[1..], &entry).is_err());
+        let page = CopcEntry {
+            point_count: -1,
+            ..entry
+        };
+        assert!(file.decode_node(chunk, &page).is_err());
+    }
+
+    #[test]
+    fn hierarchy_pages_referring_back_are_rejected() {
+        let mut bytes = create_copc_file(&create_nodes(), key(1, 0, 0, 0));
+        let mut file = CopcFile::parse(&bytes).unwrap();
+
+        let page_offset = bytes.len() as u64;
+        bytes.extend(hierarchy_entry(key(1, 0, 0, 0), page_offset, 32, -1));
+        file.info.root_hierarchy_offset = page_offset;
+        file.info.root_hierarchy_size = 32;
+
+        assert!(file.read_hierarchy(&bytes).is_err());
+    }
+
+    #[test]
+    fn ranges_past_the_end_are_rejected() {
+        let bytes = create_copc_file(&create_nodes(), key(1, 0, 0, 0));
+        let mut file = CopcFile::parse(&bytes).unwrap();
+        let entry = CopcEntry {
+            key: key(1, 0, 0, 0),
+            offset: u64::MAX - 4,
+            byte_size: 32,
+            point_count: -1,
+        };
+
+        assert!(entry.byte_range().is_err());
+        assert!(get_bytes(&bytes, &(0..bytes.len() as u64 + 1)).is_err());
+
+        file.info.root_hierarchy_offset = u64::MAX - 4;
+        assert!(file.info.root_hierarchy_range().is_err());
+        assert!(file.read_hierarchy(&bytes).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs the fixtures written by testdata/create_fixtures.sh"]
+    fn files_written_by_pdal_are_read_node_by_node() {
+        let bytes = read_fixture("points.copc.laz");
+        let file = CopcFile::parse(&bytes).unwrap();
+
+        let mut records = vec![];
+        for entry in file.read_hierarchy(&bytes).unwrap() {
+            let chunk = get_bytes(&bytes, &entry.byte_range().unwrap()).unwrap();
+            records.extend(file.decode_node(chunk, &entry).unwrap());
+        }
+
+        assert_points_match_reference(&file.header, &records, "points.copc.csv", false);
+    }
+}
@@ -0,0 +1,608 @@
+use nalgebra_glm::{vec3, DVec3};
+use serde::Deserialize;
+
+use crate::las::{self, LasHeader};
+use crate::linalg::Vec3WithIndex;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EptAttributeType {
+    Signed,
+    Unsigned,
+    Float,
+}
+
+/// One dimension of the EPT schema. Points are stored as consecutive records with the dimensions
+/// in schema order
+#[derive(Clone, Debug, Deserialize)]
+pub struct EptAttributeSchema {
+    pub name: String,
+    pub size: usize,
+    #[serde(rename = "type")]
+    pub attribute_type: EptAttributeType,
+}
+
+/// How the tiles of an EPT dataset are stored, the `dataType` of `ept.json`
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EptDataType {
+    /// Point records laid out as in the schema
+    #[default]
+    Binary,
+    /// LAZ files, which describe their own point format
+    Laszip,
+}
+
+/// Describes how to decode the points of one EPT tile. Positions are stored as integers and
+/// become `value * scale + offset`. Decoded positions are made relative to `mins`, the minimum
+/// corner of the tile. LAZ tiles use the scale and offset of their LAS header, and ignore the
+/// schema
+#[derive(Clone, Debug, Deserialize)]
+pub struct EptTileLayout {
+    #[serde(default)]
+    pub data_type: EptDataType,
+    pub schema: Vec<EptAttributeSchema>,
+    pub scale: [f64; 3],
+    pub offset: [f64; 3],
+    pub mins: [f64; 3],
+}
+
+/// Point attribute buffers of a tile, in the layout the viewer uploads to the GPU. Optional
+/// attributes are only present if they are in the schema
+#[derive(Debug)]
+pub struct DecodedEptTile {
+    pub num_points: usize,
+    /// xyz relative to `mins`
+    pub positions: Vec<f32>,
+    /// Bounds of the positions before they are rounded to `f32`
+    pub tight_min: DVec3,
+    pub tight_max: DVec3,
+    pub mean: DVec3,
+    /// RGBA with alpha left at 0. 16-bit colors are scaled down to 8 bits
+    pub colors: Option<Vec<u8>>,
+    pub intensities: Option<Vec<f32>>,
+    pub classifications: Option<Vec<u8>>,
+    pub return_numbers: Option<Vec<u8>>,
+    pub numbers_of_returns: Option<Vec<u8>>,
+    pub point_source_ids: Option<Vec<u16>>,
+    /// The decoded positions offset by the `point_offset` given when decoding, for assigning
+    /// the points to objects
+    pub points: Vec<Vec3WithIndex>,
+}
+
+#[derive(Clone, Copy)]
+enum AttributeKind {
+    I8,
+    I16,
+    I32,
+    U8,
+    U16,
+    U32,
+    F32,
+    F64,
+    /// Bits of a byte, as in the packed fields of LAS point records
+    Bits {
+        shift: u32,
+        mask: u8,
+    },
+}
+
+#[derive(Clone, Copy)]
+struct AttributeReader {
+    offset: usize,
+    kind: AttributeKind,
+}
+
+impl AttributeReader {
+    fn new(attribute: &EptAttributeSchema, offset: usize) -> Result<AttributeReader, String> {
+        let kind = match (attribute.attribute_type, attribute.size) {
+            (EptAttributeType::Signed, 1) => AttributeKind::I8,
+            (EptAttributeType::Signed, 2) => AttributeKind::I16,
+            (EptAttributeType::Signed, 4) => AttributeKind::I32,
+            (EptAttributeType::Unsigned, 1) => AttributeKind::U8,
+            (EptAttributeType::Unsigned, 2) => AttributeKind::U16,
+            (EptAttributeType::Unsigned, 4) => AttributeKind::U32,
+            (EptAttributeType::Float, 4) => AttributeKind::F32,
+            (EptAttributeType::Float, 8) => AttributeKind::F64,
+            (attribute_type, size) => {
+                return Err(format!(
+                    "Unsupported byte size {} for {:?} attribute '{}'",
+                    size, attribute_type, attribute.name
+                ))
+            }
+        };
+
+        Ok(AttributeReader { offset, kind })
+    }
+
+    /// `record` must be a whole point record, which the schema has been checked against
+    fn read(&self, record: &[u8]) -> f64 {
+        let bytes = &record[self.offset..];
+        match self.kind {
+            AttributeKind::I8 => bytes[0] as i8 as f64,
+            AttributeKind::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            AttributeKind::I32 => {
+                i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+            }
+            AttributeKind::U8 => bytes[0] as f64,
+            AttributeKind::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            AttributeKind::U32 => {
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+            }
+            AttributeKind::F32 => {
+                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+            }
+            AttributeKind::F64 => f64::from_le_bytes(bytes[..8].try_into().unwrap()),
+            AttributeKind::Bits { shift, mask } => ((bytes[0] >> shift) & mask) as f64,
+        }
+    }
+}
+
+fn find_attribute(layout: &EptTileLayout, name: &str) -> Result<Option<AttributeReader>, String> {
+    let mut offset = 0;
+
+    for attribute in &layout.schema {
+        if attribute.name == name {
+            return AttributeReader::new(attribute, offset).map(Some);
+        }
+        offset += attribute.size;
+    }
+
+    Ok(None)
+}
+
+fn find_attributes<const N: usize>(
+    layout: &EptTileLayout,
+    names: [&str; N],
+) -> Result<Option<[AttributeReader; N]>, String> {
+    let mut readers = Vec::with_capacity(N);
+
+    for name in names {
+        match find_attribute(layout, name)? {
+            Some(reader) => readers.push(reader),
+            None => return Ok(None),
+        }
+    }
+
+    Ok(readers.try_into().ok())
+}
+
+/// Converts like storing a number in a JavaScript typed array of unsigned integers with
+/// `2^bits` values: NaN and infinities become 0, and other values are truncated and wrapped
+fn to_wrapped_unsigned(value: f64, bits: u32) -> u32 {
+    if value.is_finite() {
+        value.trunc().rem_euclid(2f64.powi(bits as i32)) as u32
+    } else {
+        0
+    }
+}
+
+/// Readers for the attributes the viewer uses, at their place in a point record
+struct PointRecordReaders {
+    xyz: [AttributeReader; 3],
+    rgb: Option<[AttributeReader; 3]>,
+    intensity: Option<AttributeReader>,
+    classification: Option<AttributeReader>,
+    return_number: Option<AttributeReader>,
+    number_of_returns: Option<AttributeReader>,
+    point_source_id: Option<AttributeReader>,
+}
+
+impl PointRecordReaders {
+    fn from_ept_layout(layout: &EptTileLayout) -> Result<PointRecordReaders, String> {
+        Ok(PointRecordReaders {
+            xyz: find_attributes(layout, ["X", "Y", "Z"])?
+                .ok_or("EPT schema must contain the dimensions X, Y and Z")?,
+            rgb: find_attributes(layout, ["Red", "Green", "Blue"])?,
+            intensity: find_attribute(layout, "Intensity")?,
+            classification: find_attribute(layout, "Classification")?,
+            return_number: find_attribute(layout, "ReturnNumber")?,
+            number_of_returns: find_attribute(layout, "NumberOfReturns")?,
+            point_source_id: find_attribute(layout, "PointSourceId")?,
+        })
+    }
+
+    /// Readers for the standard fields of LAS point data formats 0 to 10
+    fn from_las_point_format(
+        format: u8,
+        record_length: usize,
+    ) -> Result<PointRecordReaders, String> {
+        const MIN_RECORD_LENGTHS: [usize; 11] = [20, 28, 26, 34, 57, 63, 30, 36, 38, 59, 67];
+
+        let min_record_length = *MIN_RECORD_LENGTHS
+            .get(format as usize)
+            .ok_or_else(|| format!("Unsupported LAS point data format {}", format))?;
+        if record_length < min_record_length {
+            return Err(format!(
+                "LAS point data format {} needs at least {} byte points, got {}",
+                format, min_record_length, record_length
+            ));
+        }
+
+        let reader = |offset, kind| AttributeReader { offset, kind };
+        let bits = |offset, shift, mask| reader(offset, AttributeKind::Bits { shift, mask });
+        let rgb = |offset: usize| Some([0, 2, 4].map(|i| reader(offset + i, AttributeKind::U16)));
+
+        // Formats 6 and up have wider return fields and a byte for the classification
+        let is_extended = format >= 6;
+        let (return_number, number_of_returns, classification, point_source_id) = if is_extended {
+            (
+                bits(14, 0, 0xF),
+                bits(14, 4, 0xF),
+                reader(16, AttributeKind::U8),
+                20,
+            )
+        } else {
+            (bits(14, 0, 0x7), bits(14, 3, 0x7), bits(15, 0, 0x1F), 18)
+        };
+
+        Ok(PointRecordReaders {
+            xyz: [0, 4, 8].map(|offset| reader(offset, AttributeKind::I32)),
+            rgb: match format {
+                2 => rgb(20),
+                3 | 5 => rgb(28),
+                7 | 8 | 10 => rgb(30),
+                _ => None,
+            },
+            intensity: Some(reader(12, AttributeKind::U16)),
+            classification: Some(classification),
+            return_number: Some(return_number),
+            number_of_returns: Some(number_of_returns),
+            point_source_id: Some(reader(point_source_id, AttributeKind::U16)),
+        })
+    }
+}
+
+/// Decodes point records into the buffers of a tile. Positions become
+/// `value * scale + offset - mins`
+fn decode_point_records(
+    records: &[u8],
+    record_length: usize,
+    readers: &PointRecordReaders,
+    scale: &DVec3,
+    offset: &DVec3,
+    mins: &DVec3,
+    point_offset: &DVec3,
+) -> DecodedEptTile {
+    let num_points = records.len() / record_length;
+    let PointRecordReaders {
+        xyz: xyz_readers,
+        rgb: rgb_readers,
+        intensity: intensity_reader,
+        classification: classification_reader,
+        return_number: return_number_reader,
+        number_of_returns: number_of_returns_reader,
+        point_source_id: point_source_id_reader,
+    } = *readers;
+
+    // Colors are assumed to be 8-bit unless a value does not fit
+    let is_two_byte_color = rgb_readers.map_or(false, |readers| {
+        records.chunks_exact(record_length).any(|record| {
+            readers
+                .iter()
+                .any(|reader| reader.read(record) > u8::MAX as f64)
+        })
+    });
+
+    let mut decoded = DecodedEptTile {
+        num_points,
+        positions: Vec::with_capacity(3 * num_points),
+        tight_min: DVec3::repeat(f64::MAX),
+        tight_max: DVec3::repeat(-f64::MAX),
+        mean: DVec3::zeros(),
+        colors: rgb_readers.map(|_| Vec::with_capacity(4 * num_points)),
+        intensities: intensity_reader.map(|_| Vec::with_capacity(num_points)),
+        classifications: classification_reader.map(|_| Vec::with_capacity(num_points)),
+        return_numbers: return_number_reader.map(|_| Vec::with_capacity(num_points)),
+        numbers_of_returns: number_of_returns_reader.map(|_| Vec::with_capacity(num_points)),
+        point_source_ids: point_source_id_reader.map(|_| Vec::with_capacity(num_points)),
+        points: Vec::with_capacity(num_points),
+    };
+
+    let push_byte = |buffer: &mut Option<Vec<u8>>, reader: Option<AttributeReader>, record| {
+        if let (Some(buffer), Some(reader)) = (buffer, reader) {
+            buffer.push(to_wrapped_unsigned(reader.read(record), 8) as u8);
+        }
+    };
+
+    for (index, record) in records.chunks_exact(record_length).enumerate() {
+        let position = vec3(
+            xyz_readers[0].read(record) * scale[0] + offset[0] - mins[0],
+            xyz_readers[1].read(record) * scale[1] + offset[1] - mins[1],
+            xyz_readers[2].read(record) * scale[2] + offset[2] - mins[2],
+        );
+
+        decoded.mean += position / num_points as f64;
+        decoded.tight_min = decoded.tight_min.inf(&position);
+        decoded.tight_max = decoded.tight_max.sup(&position);
+
+        let rounded_position = position.map(|coordinate| coordinate as f32);
+        decoded.positions.extend(rounded_position.iter());
+        decoded.points.push(Vec3WithIndex {
+            vec: rounded_position.map(|coordinate| coordinate as f64) + point_offset,
+            index,
+        });
+
+        if let (Some(colors), Some(readers)) = (&mut decoded.colors, rgb_readers) {
+            for reader in readers {
+                let value = reader.read(record);
+                let value = if is_two_byte_color {
+                    value / 256.0
+                } else {
+                    value
+                };
+                colors.push(to_wrapped_unsigned(value, 8) as u8);
+            }
+            colors.push(0);
+        }
+
+        if let (Some(intensities), Some(reader)) = (&mut decoded.intensities, intensity_reader) {
+            intensities.push(reader.read(record) as f32);
+        }
+
+        push_byte(&mut decoded.classifications, classification_reader, record);
+        push_byte(&mut decoded.return_numbers, return_number_reader, record);
+        push_byte(
+            &mut decoded.numbers_of_returns,
+            number_of_returns_reader,
+            record,
+        );
+
+        if let (Some(point_source_ids), Some(reader)) =
+            (&mut decoded.point_source_ids, point_source_id_reader)
+        {
+            point_source_ids.push(to_wrapped_unsigned(reader.read(record), 16) as u16);
+        }
+    }
+
+    decoded
+}
+
+/// Decodes the points of an uncompressed EPT tile, giving the same buffers as decoding it in
+/// JavaScript did. The points for assignment are created in the same pass
+pub fn decode_ept_binary(
+    buffer: &[u8],
+    layout: &EptTileLayout,
+    point_offset: &DVec3,
+) -> Result<DecodedEptTile, String> {
+    let record_length: usize = layout.schema.iter().map(|attribute| attribute.size).sum();
+
+    if record_length == 0 || buffer.len() % record_length != 0 {
+        return Err(format!(
+            "EPT buffer of {} bytes does not hold a whole number of {} byte points",
+            buffer.len(),
+            record_length
+        ));
+    }
+
+    let readers = PointRecordReaders::from_ept_layout(layout)?;
+
+    Ok(decode_point_records(
+        buffer,
+        record_length,
+        &readers,
+        &layout.scale.into(),
+        &layout.offset.into(),
+        &layout.mins.into(),
+        point_offset,
+    ))
+}
+
+/// Decodes uncompressed LAS point records of any point data format from 0 to 10 into the
+/// buffers of a tile. Positions are made relative to `mins`
+pub fn decode_las_point_records(
+    records: &[u8],
+    header: &LasHeader,
+    mins: &DVec3,
+    point_offset: &DVec3,
+) -> Result<DecodedEptTile, String> {
+    let record_length = header.point_record_length as usize;
+    let readers = PointRecordReaders::from_las_point_format(header.format_id(), record_length)?;
+
+    Ok(decode_point_records(
+        records,
+        record_length,
+        &readers,
+        &header.scale,
+        &header.offset,
+        mins,
+        point_offset,
+    ))
+}
+
+/// Decodes the points of a LAZ compressed EPT tile into the same buffers as an uncompressed
+/// tile
+pub fn decode_ept_laszip(
+    buffer: &[u8],
+    layout: &EptTileLayout,
+    point_offset: &DVec3,
+) -> Result<DecodedEptTile, String> {
+    let (header, records) = las::read_point_records(buffer)?;
+    decode_las_point_records(&records, &header, &layout.mins.into(), point_offset)
+}
+
+/// Decodes a tile stored as given by the `data_type` of the layout
+pub fn decode_ept_tile(
+    buffer: &[u8],
+    layout: &EptTileLayout,
+    point_offset: &DVec3,
+) -> Result<DecodedEptTile, String> {
+    match layout.data_type {
+        EptDataType::Binary => decode_ept_binary(buffer, layout, point_offset),
+        EptDataType::Laszip => decode_ept_laszip(buffer, layout, point_offset),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::{
+        decode_ept_binary, decode_ept_tile, EptAttributeSchema, EptAttributeType, EptDataType,
+        EptTileLayout,
+    };
+    use crate::laz::test_encoder::create_laz_file;
+
+    fn attribute(name: &str, size: usize, attribute_type: EptAttributeType) -> EptAttributeSchema {
+        EptAttributeSchema {
+            name: name.to_string(),
+            size,
+            attribute_type,
+        }
+    }
+
+    fn create_layout(color_size: usize) -> EptTileLayout {
+        EptTileLayout {
+            data_type: EptDataType::Binary,
+            schema: vec![
+                attribute("X", 4, EptAttributeType::Signed),
+                attribute("Y", 4, EptAttributeType::Signed),
+                attribute("Z", 4, EptAttributeType::Signed),
+                attribute("Intensity", 2, EptAttributeType::Unsigned),
+                attribute("Classification", 1, EptAttributeType::Unsigned),
+                attribute("Red", color_size, EptAttributeType::Unsigned),
+                attribute("Green", color_size, EptAttributeType::Unsigned),
+                attribute("Blue", color_size, EptAttributeType::Unsigned),
+            ],
+            scale: [0.01, 0.01, 0.01],
+            offset: [100.0, 200.0, 300.0],
+            mins: [100.0, 200.0, 300.0],
+        }
+    }
+
+    fn create_buffer(points: &[([i32; 3], u16, u8, [u16; 3])], color_size: usize) -> Vec<u8> {
+        let mut buffer = vec![];
+        for (xyz, intensity, classification, rgb) in points {
+            xyz.iter()
+                .for_each(|value| buffer.extend(value.to_le_bytes()));
+            buffer.extend(intensity.to_le_bytes());
+            buffer.push(*classification);
+            for value in rgb {
+                buffer.extend(&value.to_le_bytes()[..color_size]);
+            }
+        }
+        buffer
+    }
+
+    #[test]
+    fn decodes_positions_and_attributes() {
+        let buffer = create_buffer(
+            &[
+                ([0, 0, 0], 10, 2, [255, 0, 1]),
+                ([100, -50, 250], 20, 6, [3, 4, 5]),
+            ],
+            1,
+        );
+
+        let decoded =
+            decode_ept_binary(&buffer, &create_layout(1), &vec3(1000.0, 0.0, 0.0)).unwrap();
+
+        assert_eq!(decoded.num_points, 2);
+        assert_eq!(decoded.positions, vec![0.0, 0.0, 0.0, 1.0, -0.5, 2.5]);
+        assert_eq!(decoded.tight_min, vec3(0.0, -0.5, 0.0));
+        assert_eq!(decoded.tight_max, vec3(1.0, 0.0, 2.5));
+        assert_eq!(decoded.mean, vec3(0.5, -0.25, 1.25));
+        assert_eq!(decoded.colors, Some(vec![255, 0, 1, 0, 3, 4, 5, 0]));
+        assert_eq!(decoded.intensities, Some(vec![10.0, 20.0]));
+        assert_eq!(decoded.classifications, Some(vec![2, 6]));
+        assert_eq!(decoded.return_numbers, None);
+        assert_eq!(decoded.point_source_ids, None);
+        assert_eq!(decoded.points[1].vec, vec3(1001.0, -0.5, 2.5));
+        assert_eq!(decoded.points[1].index, 1);
+    }
+
+    #[test]
+    fn two_byte_colors_are_scaled_down() {
+        let buffer = create_buffer(
+            &[
+                ([0, 0, 0], 0, 0, [65535, 256, 255]),
+                ([0, 0, 0], 0, 0, [512, 0, 0]),
+            ],
+            2,
+        );
+
+        let decoded = decode_ept_binary(&buffer, &create_layout(2), &vec3(0.0, 0.0, 0.0)).unwrap();
+
+        assert_eq!(decoded.colors, Some(vec![255, 1, 0, 0, 2, 0, 0, 0]));
+    }
+
+    #[test]
+    fn invalid_tiles_are_rejected() {
+        let layout = create_layout(1);
+        let buffer = create_buffer(&[([0, 0, 0], 0, 0, [0, 0, 0])], 1);
+
+        assert!(decode_ept_binary(&buffer[1..], &layout, &vec3(0.0, 0.0, 0.0)).is_err());
+
+        let mut without_z = layout.clone();
+        without_z.schema[2].name = "W".to_string();
+        assert!(decode_ept_binary(&buffer, &without_z, &vec3(0.0, 0.0, 0.0)).is_err());
+
+        let mut with_bad_size = layout;
+        with_bad_size.schema[0].size = 3;
+        with_bad_size.schema[1].size = 5;
+        assert!(decode_ept_binary(&buffer, &with_bad_size, &vec3(0.0, 0.0, 0.0)).is_err());
+    }
+
+    /// LAS point records of format 2 or 7 with position, intensity, returns, classification,
+    /// point source id and color
+    fn create_las_records(format: u8, points: &[([i32; 3], u8, u8, [u16; 3])]) -> Vec<u8> {
+        let mut records = vec![];
+        for (i, (xyz, returns, classification, rgb)) in points.iter().enumerate() {
+            let mut record = vec![0u8; if format == 2 { 26 } else { 36 }];
+            for (j, value) in xyz.iter().enumerate() {
+                record[4 * j..4 * j + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            record[12..14].copy_from_slice(&(100 * i as u16).to_le_bytes());
+            let (point_source_id, rgb_offset) = if format == 2 {
+                record[14] = returns | (returns << 3);
+                record[15] = *classification;
+                (18, 20)
+            } else {
+                record[14] = returns | (returns << 4);
+                record[16] = *classification;
+                (20, 30)
+            };
+            record[point_source_id..point_source_id + 2].copy_from_slice(&7u16.to_le_bytes());
+            for (j, value) in rgb.iter().enumerate() {
+                let offset = rgb_offset + 2 * j;
+                record[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+            }
+            records.extend(record);
+        }
+        records
+    }
+
+    #[test]
+    fn decodes_laszip_tiles() {
+        let points = [
+            ([0, 0, 0], 1, 2, [0, 256, 65535]),
+            ([100, -50, 250], 2, 6, [512, 0, 0]),
+            ([100, -50, 260], 3, 6, [512, 0, 0]),
+        ];
+        let mut layout = create_layout(1);
+        layout.data_type = EptDataType::Laszip;
+        layout.mins = [0.0, -1.0, 0.0];
+
+        for format in [2, 7] {
+            let buffer = create_laz_file(format, 0, &create_las_records(format, &points), &[2, 2]);
+
+            let decoded = decode_ept_tile(&buffer, &layout, &vec3(10.0, 0.0, 0.0)).unwrap();
+
+            assert_eq!(decoded.num_points, 3);
+            assert_eq!(&decoded.positions[3..6], &[1.0, 0.5, 2.5]);
+            assert_eq!(decoded.tight_max, vec3(1.0, 1.0, 2.6));
+            assert_eq!(decoded.points[0].vec, vec3(10.0, 1.0, 0.0));
+            assert_eq!(
+                decoded.colors,
+                Some(vec![0, 1, 255, 0, 2, 0, 0, 0, 2, 0, 0, 0])
+            );
+            assert_eq!(decoded.intensities, Some(vec![0.0, 100.0, 200.0]));
+            assert_eq!(decoded.classifications, Some(vec![2, 6, 6]));
+            assert_eq!(decoded.return_numbers, Some(vec![1, 2, 3]));
+            assert_eq!(decoded.numbers_of_returns, Some(vec![1, 2, 3]));
+            assert_eq!(decoded.point_source_ids, Some(vec![7, 7, 7]));
+        }
+
+        assert!(decode_ept_tile(&[0; 100], &layout, &vec3(0.0, 0.0, 0.0)).is_err());
+    }
+}
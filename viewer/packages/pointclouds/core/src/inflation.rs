@@ -0,0 +1,240 @@
+use nalgebra_glm::{scaling, DMat4, DVec3};
+use serde::Deserialize;
+
+use crate::linalg::get_instance_half_extents;
+
+const DEFAULT_SCALE_FACTOR: f64 = 1.15;
+const DEFAULT_MAX_INCREASE_METER: f64 = 0.06;
+
+/// Inflation settings as given for a single shape or for a whole call. Settings left out fall back
+/// to those of the call, and then to the defaults
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct InputInflation {
+    scale_factor: Option<f64>,
+    margin: Option<f64>,
+    min_increase: Option<f64>,
+    max_increase: Option<f64>,
+}
+
+impl InputInflation {
+    pub fn or(&self, fallback: &InputInflation) -> InputInflation {
+        InputInflation {
+            scale_factor: self.scale_factor.or(fallback.scale_factor),
+            margin: self.margin.or(fallback.margin),
+            min_increase: self.min_increase.or(fallback.min_increase),
+            max_increase: self.max_increase.or(fallback.max_increase),
+        }
+    }
+}
+
+/// How much shapes are grown to catch points just outside them. Each characteristic length of a
+/// shape, like a radius or a half extent, becomes `length * scale_factor + margin`, with the change
+/// clamped to lie between `min_increase` and `max_increase`. Negative changes shrink the shape.
+/// Half-spaces have no such length and are only moved by the clamped margin
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Inflation {
+    pub scale_factor: f64,
+    pub margin: f64,
+    pub min_increase: f64,
+    /// `None` leaves the cap to the shape. Boxes and ellipsoids are not capped, as they were not
+    /// before inflation became configurable, and other shapes are capped at 6 cm
+    pub max_increase: Option<f64>,
+    /// Subtracts the change instead of adding it, for shapes that are cut out of others
+    pub is_deflation: bool,
+}
+
+impl Default for Inflation {
+    fn default() -> Self {
+        Inflation {
+            scale_factor: DEFAULT_SCALE_FACTOR,
+            margin: 0.0,
+            min_increase: f64::NEG_INFINITY,
+            max_increase: None,
+            is_deflation: false,
+        }
+    }
+}
+
+impl From<InputInflation> for Inflation {
+    fn from(input: InputInflation) -> Self {
+        let default = Inflation::default();
+
+        Inflation {
+            scale_factor: input.scale_factor.unwrap_or(default.scale_factor),
+            margin: input.margin.unwrap_or(default.margin),
+            min_increase: input.min_increase.unwrap_or(default.min_increase),
+            max_increase: input.max_increase.or(default.max_increase),
+            is_deflation: false,
+        }
+    }
+}
+
+impl Inflation {
+    /// Leaves shapes as they are given
+    pub const NONE: Inflation = Inflation {
+        scale_factor: 1.0,
+        margin: 0.0,
+        min_increase: f64::NEG_INFINITY,
+        max_increase: Some(f64::INFINITY),
+        is_deflation: false,
+    };
+
+    /// The inflation that shrinks shapes by as much as this one grows them. Used for the right
+    /// operand of a CSG difference, so that the difference grows outward on every side
+    pub fn deflated(&self) -> Inflation {
+        Inflation {
+            is_deflation: !self.is_deflation,
+            ..*self
+        }
+    }
+
+    /// Uses `max_increase` for shapes whose cap is left to the shape
+    pub fn with_default_max_increase(&self, max_increase: f64) -> Inflation {
+        Inflation {
+            max_increase: Some(self.max_increase.unwrap_or(max_increase)),
+            ..*self
+        }
+    }
+
+    /// How far the surface moves out for the given length, or in for deflations
+    fn get_increase(&self, length: f64) -> f64 {
+        let increase = length * (self.scale_factor - 1.0) + self.margin;
+        let max_increase = self.max_increase.unwrap_or(DEFAULT_MAX_INCREASE_METER);
+        let clamped_increase = increase.min(max_increase).max(self.min_increase);
+
+        if self.is_deflation {
+            -clamped_increase
+        } else {
+            clamped_increase
+        }
+    }
+
+    /// Never returns a negative length
+    pub fn inflate_length(&self, length: f64) -> f64 {
+        (length + self.get_increase(length)).max(0.0)
+    }
+
+    /// How far to move a plane out for shapes without a length to scale, like half-spaces
+    pub fn get_margin_increase(&self) -> f64 {
+        self.get_increase(0.0)
+    }
+
+    pub fn inflate_half_extents(&self, half_extents: &DVec3) -> DVec3 {
+        half_extents.map(|half_extent| self.inflate_length(half_extent))
+    }
+
+    /// Inflates the instance along each of its axes, keeping its center in place
+    pub fn inflate_inv_instance_matrix(&self, inv_instance_matrix: &DMat4) -> DMat4 {
+        let half_extents = get_instance_half_extents(inv_instance_matrix);
+        let inflated_half_extents = self.inflate_half_extents(&half_extents);
+
+        scaling(&half_extents.component_div(&inflated_half_extents)) * inv_instance_matrix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{inverse, scale, translate, vec3, vec4, DMat4};
+
+    use super::{Inflation, InputInflation};
+
+    #[test]
+    fn default_inflation_is_relative_with_absolute_cap() {
+        let inflation = Inflation::default();
+
+        assert!((inflation.inflate_length(0.1) - 0.115).abs() < 1e-12);
+        assert!((inflation.inflate_length(10.0) - 10.06).abs() < 1e-12);
+
+        let box_inflation = inflation.with_default_max_increase(f64::INFINITY);
+        assert!((box_inflation.inflate_length(10.0) - 11.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn margin_is_clamped_and_deflated() {
+        let inflation = Inflation {
+            margin: 0.1,
+            ..Default::default()
+        };
+
+        assert!((inflation.get_margin_increase() - 0.06).abs() < 1e-12);
+        assert!((inflation.deflated().get_margin_increase() + 0.06).abs() < 1e-12);
+        assert_eq!(Inflation::default().get_margin_increase(), 0.0);
+    }
+
+    #[test]
+    fn negative_margin_shrinks_but_never_below_zero() {
+        let inflation = Inflation {
+            scale_factor: 1.0,
+            margin: -0.5,
+            min_increase: f64::NEG_INFINITY,
+            max_increase: Some(f64::INFINITY),
+            is_deflation: false,
+        };
+
+        assert!((inflation.inflate_length(2.0) - 1.5).abs() < 1e-12);
+        assert_eq!(inflation.inflate_length(0.2), 0.0);
+    }
+
+    #[test]
+    fn min_increase_clamps_small_lengths() {
+        let inflation = Inflation {
+            min_increase: 0.05,
+            ..Default::default()
+        };
+
+        assert!((inflation.inflate_length(0.01) - 0.06).abs() < 1e-12);
+    }
+
+    #[test]
+    fn deflation_shrinks_by_the_clamped_increase() {
+        let deflation = Inflation::default().deflated();
+
+        assert!((deflation.inflate_length(0.1) - 0.085).abs() < 1e-12);
+        assert!((deflation.inflate_length(10.0) - 9.94).abs() < 1e-12);
+        assert_eq!(deflation.deflated(), Inflation::default());
+    }
+
+    #[test]
+    fn shape_settings_override_call_settings_field_by_field() {
+        let shape_settings = InputInflation {
+            margin: Some(0.2),
+            ..Default::default()
+        };
+        let call_settings = InputInflation {
+            scale_factor: Some(1.0),
+            margin: Some(0.1),
+            ..Default::default()
+        };
+
+        let inflation: Inflation = shape_settings.or(&call_settings).into();
+
+        assert_eq!(inflation.scale_factor, 1.0);
+        assert_eq!(inflation.margin, 0.2);
+        assert_eq!(inflation.max_increase, Inflation::default().max_increase);
+    }
+
+    #[test]
+    fn inflated_instance_keeps_center_and_grows_each_axis() {
+        let instance_matrix = scale(
+            &translate(&DMat4::identity(), &vec3(5.0, 0.0, 0.0)),
+            &vec3(1.0, 2.0, 4.0),
+        );
+        let inflation = Inflation {
+            scale_factor: 1.0,
+            margin: 1.0,
+            min_increase: f64::NEG_INFINITY,
+            max_increase: Some(f64::INFINITY),
+            is_deflation: false,
+        };
+
+        let inflated_instance =
+            inverse(&inflation.inflate_inv_instance_matrix(&inverse(&instance_matrix)));
+
+        assert!(
+            (inflated_instance * vec4(0.0, 0.0, 0.0, 1.0) - vec4(5.0, 0.0, 0.0, 1.0)).amax() < 1e-9
+        );
+        assert!(
+            (inflated_instance * vec4(1.0, 1.0, 1.0, 1.0) - vec4(7.0, 3.0, 5.0, 1.0)).amax() < 1e-9
+        );
+    }
+}
@@ -0,0 +1,896 @@
+use nalgebra_glm::{max2, min2, vec2, vec3, DMat4, DVec3};
+use std::vec::Vec;
+
+use crate::clip_volume::ClipVolume;
+use crate::inflation::{Inflation, InputInflation};
+use crate::linalg::Vec3WithIndex;
+use crate::linalg::{BoundingBox, Plane};
+use crate::object_id_width::ObjectIdWidth;
+use crate::point_octree::PickRay;
+use crate::shapes;
+
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+struct InputCylinder {
+    center_a: [f64; 3],
+    center_b: [f64; 3],
+    radius: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct InputOrientedBox {
+    inv_instance_matrix: [f64; 16],
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct InputEllipsoid {
+    inv_instance_matrix: [f64; 16],
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct InputSphere {
+    center: [f64; 3],
+    radius: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct InputCapsule {
+    center_a: [f64; 3],
+    center_b: [f64; 3],
+    radius: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct InputCone {
+    center_a: [f64; 3],
+    center_b: [f64; 3],
+    radius_a: f64,
+    radius_b: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct InputTorusSegment {
+    center: [f64; 3],
+    axis: [f64; 3],
+    major_radius: f64,
+    minor_radius: f64,
+    start_angle: f64,
+    end_angle: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct InputSweptPipe {
+    vertices: Vec<[f64; 3]>,
+    radius: f64,
+    bend_radii: Option<Vec<f64>>,
+}
+
+/// Either the bounding planes or the points to take the convex hull of
+#[derive(Clone, Debug, Deserialize)]
+struct InputConvexPolyhedron {
+    planes: Option<Vec<InputHalfSpace>>,
+    points: Option<Vec<[f64; 3]>>,
+}
+
+/// Refers to the buffers at `mesh_index` in the list of meshes passed next to the shapes
+#[derive(Clone, Debug, Deserialize)]
+struct InputTriangleMesh {
+    mesh_index: usize,
+}
+
+/// `rings[0]` is the outline and further rings are holes, all in coordinates of the frame
+#[derive(Clone, Debug, Deserialize)]
+struct InputPrism {
+    origin: [f64; 3],
+    x_axis: [f64; 3],
+    y_axis: [f64; 3],
+    rings: Vec<Vec<[f64; 2]>>,
+    min_height: f64,
+    max_height: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct InputComposite {
+    inner_shapes: Vec<InputGeometry>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InputHalfSpace {
+    normal: [f64; 3],
+    offset: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum InputCsgOperator {
+    Union,
+    Intersection,
+    Difference,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct InputCsg {
+    operator: InputCsgOperator,
+    left: InputGeometry,
+    right: InputGeometry,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InputGeometry {
+    cylinder: Option<Box<InputCylinder>>,
+    oriented_box: Option<Box<InputOrientedBox>>,
+    ellipsoid: Option<Box<InputEllipsoid>>,
+    sphere: Option<Box<InputSphere>>,
+    capsule: Option<Box<InputCapsule>>,
+    cone: Option<Box<InputCone>>,
+    torus_segment: Option<Box<InputTorusSegment>>,
+    swept_pipe: Option<Box<InputSweptPipe>>,
+    convex_polyhedron: Option<Box<InputConvexPolyhedron>>,
+    triangle_mesh: Option<Box<InputTriangleMesh>>,
+    prism: Option<Box<InputPrism>>,
+    composite: Option<Box<InputComposite>>,
+    half_space: Option<Box<InputHalfSpace>>,
+    csg: Option<Box<InputCsg>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InputShape {
+    object_id: u32,
+    priority: Option<i32>,
+    inflation: Option<InputInflation>,
+    #[serde(flatten)]
+    geometry: InputGeometry,
+}
+
+/// A shape together with the settings of the object it was parsed from
+pub struct ParsedObject {
+    pub shape: Box<dyn shapes::Shape>,
+    /// The shape before inflation, where that differs from `shape`
+    pub uninflated_shape: Option<Box<dyn shapes::Shape>>,
+    pub priority: i32,
+}
+
+impl ParsedObject {
+    /// The shape as it was given. Distances are measured to it, so that points on the annotated
+    /// surface are at distance zero
+    pub fn get_uninflated_shape(&self) -> &dyn shapes::Shape {
+        self.uninflated_shape.as_deref().unwrap_or(&*self.shape)
+    }
+}
+
+/// Vertex and index buffers of a triangle mesh, referred to by `mesh_index` from the shapes
+#[derive(Deserialize)]
+pub struct InputTriangleMeshBuffers {
+    vertices: Vec<f32>,
+    indices: Vec<u32>,
+}
+
+impl InputTriangleMeshBuffers {
+    pub fn new(vertices: Vec<f32>, indices: Vec<u32>) -> Self {
+        InputTriangleMeshBuffers { vertices, indices }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct InputBoundingBox {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl From<InputBoundingBox> for BoundingBox {
+    fn from(input: InputBoundingBox) -> Self {
+        BoundingBox {
+            min: vec3(input.min[0], input.min[1], input.min[2]),
+            max: vec3(input.max[0], input.max[1], input.max[2]),
+        }
+    }
+}
+
+/// Points are picked within `radius + t * radius_per_distance` of the ray, where `t` is the
+/// distance along it
+#[derive(Deserialize)]
+pub struct InputPickRay {
+    pub origin: [f64; 3],
+    pub direction: [f64; 3],
+    #[serde(default)]
+    pub radius: f64,
+    #[serde(default)]
+    pub radius_per_distance: f64,
+}
+
+pub fn create_pick_ray(input_ray: InputPickRay) -> Result<PickRay, String> {
+    PickRay::new(
+        vec3(
+            input_ray.origin[0],
+            input_ray.origin[1],
+            input_ray.origin[2],
+        ),
+        &vec3(
+            input_ray.direction[0],
+            input_ray.direction[1],
+            input_ray.direction[2],
+        ),
+        input_ray.radius,
+        input_ray.radius_per_distance,
+    )
+}
+
+/// The normal of a half-space or clip plane, which must have a direction
+fn create_plane_normal(input: &InputHalfSpace) -> Result<DVec3, String> {
+    let normal = vec3(input.normal[0], input.normal[1], input.normal[2]);
+
+    if normal == DVec3::zeros() {
+        return Err("Clip plane normal must be non-zero".to_string());
+    }
+
+    Ok(normal)
+}
+
+/// Planes are given like half-space shapes, keeping the points `p` with `dot(normal, p) <= offset`
+pub fn create_clip_volume(input_planes: &[InputHalfSpace]) -> Result<ClipVolume, String> {
+    let planes = input_planes
+        .iter()
+        .map(|input_plane| {
+            Ok(Plane::new(
+                create_plane_normal(input_plane)?,
+                input_plane.offset,
+            ))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(ClipVolume::new(planes))
+}
+
+/// Points given as consecutive xyz coordinates relative to `point_offset`
+pub fn create_points(coordinates: &[f32], point_offset: &DVec3) -> Vec<Vec3WithIndex> {
+    coordinates
+        .chunks_exact(3)
+        .enumerate()
+        .map(|(i, p)| Vec3WithIndex {
+            vec: vec3(p[0] as f64, p[1] as f64, p[2] as f64) + point_offset,
+            index: i,
+        })
+        .collect()
+}
+
+fn create_cylinder(input: InputCylinder, id: u32, inflation: &Inflation) -> Box<shapes::Cylinder> {
+    Box::new(shapes::Cylinder::new(
+        vec3(input.center_a[0], input.center_a[1], input.center_a[2]),
+        vec3(input.center_b[0], input.center_b[1], input.center_b[2]),
+        inflation.inflate_length(input.radius),
+        id,
+    ))
+}
+
+fn create_box(input: InputOrientedBox, id: u32, inflation: &Inflation) -> Box<shapes::OrientedBox> {
+    let matrix = DMat4::from_column_slice(&input.inv_instance_matrix);
+    Box::new(shapes::OrientedBox::new(
+        inflation
+            .with_default_max_increase(f64::INFINITY)
+            .inflate_inv_instance_matrix(&matrix),
+        id,
+    ))
+}
+
+fn create_ellipsoid(
+    input: InputEllipsoid,
+    id: u32,
+    inflation: &Inflation,
+) -> Box<shapes::Ellipsoid> {
+    let matrix = DMat4::from_column_slice(&input.inv_instance_matrix);
+    Box::new(shapes::Ellipsoid::new(
+        inflation
+            .with_default_max_increase(f64::INFINITY)
+            .inflate_inv_instance_matrix(&matrix),
+        id,
+    ))
+}
+
+fn create_sphere(input: InputSphere, id: u32, inflation: &Inflation) -> Box<shapes::Sphere> {
+    Box::new(shapes::Sphere::new(
+        vec3(input.center[0], input.center[1], input.center[2]),
+        inflation.inflate_length(input.radius),
+        id,
+    ))
+}
+
+fn create_capsule(input: InputCapsule, id: u32, inflation: &Inflation) -> Box<shapes::Capsule> {
+    Box::new(shapes::Capsule::new(
+        vec3(input.center_a[0], input.center_a[1], input.center_a[2]),
+        vec3(input.center_b[0], input.center_b[1], input.center_b[2]),
+        inflation.inflate_length(input.radius),
+        id,
+    ))
+}
+
+fn create_cone(
+    input: InputCone,
+    id: u32,
+    inflation: &Inflation,
+) -> Result<Box<shapes::Cone>, String> {
+    let center_a = vec3(input.center_a[0], input.center_a[1], input.center_a[2]);
+    let center_b = vec3(input.center_b[0], input.center_b[1], input.center_b[2]);
+
+    // Without a height the cone has no axis
+    if center_a == center_b {
+        return Err("Cone must have different end centers".to_string());
+    }
+
+    Ok(Box::new(shapes::Cone::new(
+        center_a,
+        center_b,
+        inflation.inflate_length(input.radius_a),
+        inflation.inflate_length(input.radius_b),
+        id,
+    )))
+}
+
+fn create_torus_segment(
+    input: InputTorusSegment,
+    id: u32,
+    inflation: &Inflation,
+) -> Box<shapes::TorusSegment> {
+    Box::new(shapes::TorusSegment::new(
+        vec3(input.center[0], input.center[1], input.center[2]),
+        vec3(input.axis[0], input.axis[1], input.axis[2]),
+        input.major_radius,
+        inflation.inflate_length(input.minor_radius),
+        input.start_angle,
+        input.end_angle,
+        id,
+    ))
+}
+
+fn create_swept_pipe(
+    input: InputSweptPipe,
+    id: u32,
+    inflation: &Inflation,
+) -> Result<Box<shapes::SweptPipe>, String> {
+    if input.vertices.len() < 2 {
+        return Err("Swept pipe must have at least two vertices".to_string());
+    }
+
+    let bend_radii = input.bend_radii.unwrap_or_default();
+    if !bend_radii.is_empty() && bend_radii.len() != input.vertices.len() - 2 {
+        return Err(format!(
+            "Swept pipe with {} vertices must have {} bend radii, got {}",
+            input.vertices.len(),
+            input.vertices.len() - 2,
+            bend_radii.len()
+        ));
+    }
+
+    let vertices: Vec<_> = input
+        .vertices
+        .iter()
+        .map(|vertex| vec3(vertex[0], vertex[1], vertex[2]))
+        .collect();
+
+    // A segment of zero length has no direction to run the pipe along
+    if let Some(index) = vertices
+        .windows(2)
+        .position(|segment| segment[0] == segment[1])
+    {
+        return Err(format!(
+            "Swept pipe vertices {} and {} must not be equal",
+            index,
+            index + 1
+        ));
+    }
+
+    Ok(Box::new(shapes::SweptPipe::new(
+        &vertices,
+        inflation.inflate_length(input.radius),
+        &bend_radii,
+        id,
+    )))
+}
+
+fn create_convex_polyhedron(
+    input: InputConvexPolyhedron,
+    id: u32,
+    inflation: &Inflation,
+) -> Result<Box<shapes::ConvexPolyhedron>, String> {
+    let polyhedron = if let Some(input_planes) = input.planes {
+        let planes = input_planes
+            .iter()
+            .map(|plane| {
+                Plane::new(
+                    vec3(plane.normal[0], plane.normal[1], plane.normal[2]),
+                    plane.offset,
+                )
+            })
+            .collect();
+        shapes::ConvexPolyhedron::from_planes(planes, id)?
+    } else if let Some(input_points) = input.points {
+        let points: Vec<_> = input_points
+            .iter()
+            .map(|point| vec3(point[0], point[1], point[2]))
+            .collect();
+        shapes::ConvexPolyhedron::from_points(&points, id)?
+    } else {
+        return Err("Convex polyhedron needs either planes or points".to_string());
+    };
+
+    Ok(Box::new(
+        polyhedron.inflated(|distance| inflation.inflate_length(distance)),
+    ))
+}
+
+fn create_triangle_mesh(
+    input: InputTriangleMesh,
+    id: u32,
+    meshes: &[InputTriangleMeshBuffers],
+    inflation: &Inflation,
+) -> Result<Box<shapes::TriangleMeshShape>, String> {
+    let buffers = meshes
+        .get(input.mesh_index)
+        .ok_or_else(|| format!("Triangle mesh index {} out of range", input.mesh_index))?;
+
+    if buffers.vertices.len() % 3 != 0 || buffers.indices.len() % 3 != 0 {
+        return Err("Triangle mesh buffer lengths must be multiples of three".to_string());
+    }
+
+    let vertices: Vec<_> = buffers
+        .vertices
+        .chunks(3)
+        .map(|v| vec3(v[0] as f64, v[1] as f64, v[2] as f64))
+        .collect();
+
+    if buffers
+        .indices
+        .iter()
+        .any(|i| *i as usize >= vertices.len())
+    {
+        return Err("Triangle mesh index buffer refers to missing vertex".to_string());
+    }
+
+    let bounding_box: BoundingBox = vertices.iter().copied().collect();
+    let center = bounding_box.center();
+    let half_extents = bounding_box.extent() / 2.0;
+    let scale = inflation
+        .inflate_half_extents(&half_extents)
+        .component_div(&half_extents.map(|half_extent| half_extent.max(f64::MIN_POSITIVE)));
+    let scaled_vertices = vertices
+        .iter()
+        .map(|vertex| center + (vertex - center).component_mul(&scale))
+        .collect();
+
+    let triangles = buffers
+        .indices
+        .chunks(3)
+        .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+        .collect();
+
+    Ok(Box::new(shapes::TriangleMeshShape::new(
+        scaled_vertices,
+        triangles,
+        id,
+    )))
+}
+
+fn create_prism(
+    input: InputPrism,
+    id: u32,
+    inflation: &Inflation,
+) -> Result<Box<shapes::Prism>, String> {
+    let outline = input
+        .rings
+        .first()
+        .filter(|outline| outline.len() >= 3)
+        .ok_or_else(|| "Prism outline must have at least three corners".to_string())?;
+
+    let x_axis = vec3(input.x_axis[0], input.x_axis[1], input.x_axis[2]);
+    let y_axis = vec3(input.y_axis[0], input.y_axis[1], input.y_axis[2]);
+    // The axes span the plane of the outline, and the prism is extruded along their cross product
+    if x_axis.cross(&y_axis) == DVec3::zeros() {
+        return Err("Prism axes must be non-zero and not parallel".to_string());
+    }
+
+    let (min_corner, max_corner) = outline.iter().fold(
+        (
+            vec2(f64::INFINITY, f64::INFINITY),
+            vec2(f64::NEG_INFINITY, f64::NEG_INFINITY),
+        ),
+        |(min, max), corner| {
+            let corner = vec2(corner[0], corner[1]);
+            (min2(&min, &corner), max2(&max, &corner))
+        },
+    );
+    let center = (min_corner + max_corner) / 2.0;
+    let half_extents = (max_corner - min_corner) / 2.0;
+    let scale = half_extents.map(|half_extent| {
+        inflation.inflate_length(half_extent) / half_extent.max(f64::MIN_POSITIVE)
+    });
+
+    let rings = input
+        .rings
+        .iter()
+        .map(|ring| {
+            ring.iter()
+                .map(|corner| center + (vec2(corner[0], corner[1]) - center).component_mul(&scale))
+                .collect()
+        })
+        .collect();
+
+    let middle_height = (input.min_height + input.max_height) / 2.0;
+    let half_height = inflation.inflate_length((input.max_height - input.min_height) / 2.0);
+
+    Ok(Box::new(shapes::Prism::new(
+        vec3(input.origin[0], input.origin[1], input.origin[2]),
+        x_axis,
+        y_axis,
+        rings,
+        (middle_height - half_height, middle_height + half_height),
+        id,
+    )))
+}
+
+fn create_composite(
+    input: InputComposite,
+    id: u32,
+    meshes: &[InputTriangleMeshBuffers],
+    inflation: &Inflation,
+) -> Result<Box<shapes::Composite>, String> {
+    let inner_shapes: Result<Vec<_>, _> = input
+        .inner_shapes
+        .into_iter()
+        .map(|inner_geometry| create_geometry(inner_geometry, id, meshes, inflation))
+        .collect();
+
+    Ok(Box::new(shapes::Composite::new(inner_shapes?, id)))
+}
+
+fn create_half_space(
+    input: InputHalfSpace,
+    id: u32,
+    inflation: &Inflation,
+) -> Result<Box<shapes::HalfSpace>, String> {
+    let normal = create_plane_normal(&input)?;
+    // The offset is in units of the normal's length
+    let offset = input.offset + inflation.get_margin_increase() * normal.magnitude();
+
+    Ok(Box::new(shapes::HalfSpace::new(normal, offset, id)))
+}
+
+fn create_csg(
+    input: InputCsg,
+    id: u32,
+    meshes: &[InputTriangleMeshBuffers],
+    inflation: &Inflation,
+) -> Result<Box<shapes::Csg>, String> {
+    let operator = match input.operator {
+        InputCsgOperator::Union => shapes::CsgOperator::Union,
+        InputCsgOperator::Intersection => shapes::CsgOperator::Intersection,
+        InputCsgOperator::Difference => shapes::CsgOperator::Difference,
+    };
+
+    // The subtracted shape shrinks as the other grows, so that points in the wall of a pipe minus
+    // its bore stay inside the difference
+    let right_inflation = match operator {
+        shapes::CsgOperator::Difference => inflation.deflated(),
+        _ => *inflation,
+    };
+
+    Ok(Box::new(shapes::Csg::new(
+        operator,
+        create_geometry(input.left, id, meshes, inflation)?,
+        create_geometry(input.right, id, meshes, &right_inflation)?,
+        id,
+    )))
+}
+
+fn create_geometry(
+    geometry: InputGeometry,
+    id: u32,
+    meshes: &[InputTriangleMeshBuffers],
+    inflation: &Inflation,
+) -> Result<Box<dyn shapes::Shape>, String> {
+    if let Some(input_cylinder) = geometry.cylinder {
+        Ok(create_cylinder(*input_cylinder, id, inflation))
+    } else if let Some(input_box) = geometry.oriented_box {
+        Ok(create_box(*input_box, id, inflation))
+    } else if let Some(input_ellipsoid) = geometry.ellipsoid {
+        Ok(create_ellipsoid(*input_ellipsoid, id, inflation))
+    } else if let Some(input_sphere) = geometry.sphere {
+        Ok(create_sphere(*input_sphere, id, inflation))
+    } else if let Some(input_capsule) = geometry.capsule {
+        Ok(create_capsule(*input_capsule, id, inflation))
+    } else if let Some(input_cone) = geometry.cone {
+        Ok(create_cone(*input_cone, id, inflation)?)
+    } else if let Some(input_torus_segment) = geometry.torus_segment {
+        Ok(create_torus_segment(*input_torus_segment, id, inflation))
+    } else if let Some(input_swept_pipe) = geometry.swept_pipe {
+        Ok(create_swept_pipe(*input_swept_pipe, id, inflation)?)
+    } else if let Some(input_convex_polyhedron) = geometry.convex_polyhedron {
+        Ok(create_convex_polyhedron(
+            *input_convex_polyhedron,
+            id,
+            inflation,
+        )?)
+    } else if let Some(input_triangle_mesh) = geometry.triangle_mesh {
+        Ok(create_triangle_mesh(
+            *input_triangle_mesh,
+            id,
+            meshes,
+            inflation,
+        )?)
+    } else if let Some(input_prism) = geometry.prism {
+        Ok(create_prism(*input_prism, id, inflation)?)
+    } else if let Some(input_composite) = geometry.composite {
+        Ok(create_composite(*input_composite, id, meshes, inflation)?)
+    } else if let Some(input_half_space) = geometry.half_space {
+        Ok(create_half_space(*input_half_space, id, inflation)?)
+    } else if let Some(input_csg) = geometry.csg {
+        Ok(create_csg(*input_csg, id, meshes, inflation)?)
+    } else {
+        Err("Unrecognized geometry type found while parsing".to_string())
+    }
+}
+
+fn create_object(
+    obj: InputShape,
+    meshes: &[InputTriangleMeshBuffers],
+    object_id_width: ObjectIdWidth,
+    default_inflation: &InputInflation,
+) -> Result<ParsedObject, String> {
+    object_id_width.check_object_id(obj.object_id)?;
+
+    let inflation: Inflation = obj
+        .inflation
+        .unwrap_or_default()
+        .or(default_inflation)
+        .into();
+
+    let uninflated_shape = if inflation == Inflation::NONE {
+        None
+    } else {
+        Some(create_geometry(
+            obj.geometry.clone(),
+            obj.object_id,
+            meshes,
+            &Inflation::NONE,
+        )?)
+    };
+
+    Ok(ParsedObject {
+        shape: create_geometry(obj.geometry, obj.object_id, meshes, &inflation)?,
+        uninflated_shape,
+        priority: obj.priority.unwrap_or(0),
+    })
+}
+
+/// Creates a shape without object id, for queries
+pub fn create_shape(
+    geometry: InputGeometry,
+    meshes: &[InputTriangleMeshBuffers],
+    default_inflation: &InputInflation,
+) -> Result<Box<dyn shapes::Shape>, String> {
+    create_geometry(geometry, 0, meshes, &(*default_inflation).into())
+}
+
+pub fn create_objects(
+    input_shapes: Vec<InputShape>,
+    meshes: &[InputTriangleMeshBuffers],
+    object_id_width: ObjectIdWidth,
+    default_inflation: &InputInflation,
+) -> Result<Vec<ParsedObject>, String> {
+    input_shapes
+        .into_iter()
+        .map(|input_shape| create_object(input_shape, meshes, object_id_width, default_inflation))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::{create_objects, InputShape};
+    use crate::inflation::InputInflation;
+    use crate::object_id_width::ObjectIdWidth;
+
+    fn parse_shape(json: &str) -> InputShape {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn pipe_minus_bore_contains_wall_with_default_inflation() {
+        let pipe = parse_shape(
+            r#"{
+                "object_id": 1,
+                "csg": {
+                    "operator": "difference",
+                    "left": { "cylinder": {
+                        "center_a": [0, 0, -1], "center_b": [0, 0, 1], "radius": 0.5
+                    } },
+                    "right": { "cylinder": {
+                        "center_a": [0, 0, -2], "center_b": [0, 0, 2], "radius": 0.45
+                    } }
+                }
+            }"#,
+        );
+
+        let objects = create_objects(
+            vec![pipe],
+            &[],
+            ObjectIdWidth::default(),
+            &InputInflation::default(),
+        )
+        .unwrap();
+        let shape = &objects[0].shape;
+
+        for radius in [0.42, 0.46, 0.48, 0.5, 0.53] {
+            assert!(shape.contains_point(&vec3(radius, 0.0, 0.0)), "{}", radius);
+        }
+        for radius in [0.0, 0.3, 0.6] {
+            assert!(!shape.contains_point(&vec3(radius, 0.0, 0.0)), "{}", radius);
+        }
+    }
+
+    #[test]
+    fn default_inflation_scales_boxes_without_cap_and_caps_cylinders() {
+        let shapes = vec![
+            parse_shape(
+                r#"{ "object_id": 1, "oriented_box": { "inv_instance_matrix": [
+                    0.1, 0, 0, 0, 0, 0.1, 0, 0, 0, 0, 0.1, 0, 0, 0, 0, 1
+                ] } }"#,
+            ),
+            parse_shape(
+                r#"{ "object_id": 2, "cylinder": {
+                    "center_a": [0, 0, -1], "center_b": [0, 0, 1], "radius": 10
+                } }"#,
+            ),
+        ];
+
+        let objects = create_objects(
+            shapes,
+            &[],
+            ObjectIdWidth::default(),
+            &InputInflation::default(),
+        )
+        .unwrap();
+
+        assert!(objects[0].shape.contains_point(&vec3(11.4, 0.0, 0.0)));
+        assert!(!objects[0].shape.contains_point(&vec3(11.6, 0.0, 0.0)));
+        assert!(objects[1].shape.contains_point(&vec3(10.05, 0.0, 0.0)));
+        assert!(!objects[1].shape.contains_point(&vec3(10.1, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn default_inflation_scales_ellipsoids_without_cap() {
+        let ellipsoid = parse_shape(
+            r#"{ "object_id": 1, "ellipsoid": { "inv_instance_matrix": [
+                0.1, 0, 0, 0, 0, 0.1, 0, 0, 0, 0, 0.1, 0, 0, 0, 0, 1
+            ] } }"#,
+        );
+
+        let objects = create_objects(
+            vec![ellipsoid],
+            &[],
+            ObjectIdWidth::default(),
+            &InputInflation::default(),
+        )
+        .unwrap();
+
+        assert!(objects[0].shape.contains_point(&vec3(11.4, 0.0, 0.0)));
+        assert!(!objects[0].shape.contains_point(&vec3(11.6, 0.0, 0.0)));
+        assert!(objects[0].shape.contains_point(&vec3(0.0, 0.0, -11.4)));
+    }
+
+    #[test]
+    fn cone_without_height_is_rejected() {
+        let cone = parse_shape(
+            r#"{ "object_id": 1, "cone": {
+                "center_a": [1, 2, 3], "center_b": [1, 2, 3], "radius_a": 1, "radius_b": 0.5
+            } }"#,
+        );
+
+        let error = create_objects(
+            vec![cone],
+            &[],
+            ObjectIdWidth::default(),
+            &InputInflation::default(),
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!(error, "Cone must have different end centers");
+    }
+
+    #[test]
+    fn prism_with_parallel_or_zero_axes_is_rejected() {
+        for (x_axis, y_axis) in [("[1, 0, 0]", "[2, 0, 0]"), ("[0, 0, 0]", "[0, 1, 0]")] {
+            let prism = parse_shape(&format!(
+                r#"{{ "object_id": 1, "prism": {{
+                    "origin": [0, 0, 0], "x_axis": {}, "y_axis": {},
+                    "rings": [[[0, 0], [1, 0], [0, 1]]], "min_height": 0, "max_height": 1
+                }} }}"#,
+                x_axis, y_axis
+            ));
+
+            let error = create_objects(
+                vec![prism],
+                &[],
+                ObjectIdWidth::default(),
+                &InputInflation::default(),
+            )
+            .err()
+            .unwrap();
+
+            assert_eq!(error, "Prism axes must be non-zero and not parallel");
+        }
+    }
+
+    #[test]
+    fn swept_pipe_with_repeated_vertex_is_rejected() {
+        let pipe = parse_shape(
+            r#"{ "object_id": 1, "swept_pipe": {
+                "vertices": [[0, 0, 0], [1, 0, 0], [1, 0, 0], [1, 1, 0]],
+                "radius": 0.1,
+                "bend_radii": [0.2, 0.2]
+            } }"#,
+        );
+
+        let error = create_objects(
+            vec![pipe],
+            &[],
+            ObjectIdWidth::default(),
+            &InputInflation::default(),
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!(error, "Swept pipe vertices 1 and 2 must not be equal");
+    }
+
+    #[test]
+    fn half_space_with_zero_normal_is_rejected() {
+        let difference = parse_shape(
+            r#"{ "object_id": 1, "csg": {
+                "operator": "difference",
+                "left": { "sphere": { "center": [0, 0, 0], "radius": 1 } },
+                "right": { "half_space": { "normal": [0, 0, 0], "offset": 0 } }
+            } }"#,
+        );
+
+        let error = create_objects(
+            vec![difference],
+            &[],
+            ObjectIdWidth::default(),
+            &InputInflation::default(),
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!(error, "Clip plane normal must be non-zero");
+    }
+
+    #[test]
+    fn half_space_moves_by_margin() {
+        let half_space = parse_shape(
+            r#"{
+                "object_id": 1,
+                "inflation": { "margin": 0.05 },
+                "half_space": { "normal": [0, 0, 2], "offset": 2 }
+            }"#,
+        );
+
+        let objects = create_objects(
+            vec![half_space],
+            &[],
+            ObjectIdWidth::default(),
+            &InputInflation::default(),
+        )
+        .unwrap();
+
+        assert!(objects[0].shape.contains_point(&vec3(0.0, 0.0, 1.04)));
+        assert!(!objects[0].shape.contains_point(&vec3(0.0, 0.0, 1.06)));
+        assert!(objects[0]
+            .get_uninflated_shape()
+            .contains_point(&vec3(0.0, 0.0, 1.0)));
+        assert!(!objects[0]
+            .get_uninflated_shape()
+            .contains_point(&vec3(0.0, 0.0, 1.01)));
+    }
+}
@@ -0,0 +1,295 @@
+use std::borrow::Cow;
+
+use nalgebra_glm::{vec3, DVec3};
+
+use crate::laz::{self, LazVlr};
+
+/// Point data format ids with this bit set are LAZ compressed
+const COMPRESSED_FORMAT_BIT: u8 = 0x80;
+const MAX_POINT_DATA_FORMAT: u8 = 10;
+const MIN_HEADER_SIZE: usize = 227;
+const LAS_1_4_HEADER_SIZE: usize = 375;
+
+/// The parts of a LAS public header block needed to read point positions. LAS 1.0 to 1.4 are
+/// supported
+#[derive(Clone, Debug, PartialEq)]
+pub struct LasHeader {
+    pub version: (u8, u8),
+    pub header_size: u16,
+    pub offset_to_point_data: u32,
+    pub number_of_vlrs: u32,
+    pub point_data_format: u8,
+    pub point_record_length: u16,
+    pub number_of_points: u64,
+    pub scale: DVec3,
+    pub offset: DVec3,
+    pub min: DVec3,
+    pub max: DVec3,
+}
+
+fn read_bytes<const N: usize>(bytes: &[u8], offset: usize) -> Result<[u8; N], String> {
+    bytes
+        .get(offset..offset + N)
+        .and_then(|slice| slice.try_into().ok())
+        .ok_or_else(|| format!("LAS data ended early, expected {} bytes at {}", N, offset))
+}
+
+fn read_u8(bytes: &[u8], offset: usize) -> Result<u8, String> {
+    read_bytes::<1>(bytes, offset).map(|b| b[0])
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, String> {
+    read_bytes(bytes, offset).map(u16::from_le_bytes)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    read_bytes(bytes, offset).map(u32::from_le_bytes)
+}
+
+fn read_i32(bytes: &[u8], offset: usize) -> Result<i32, String> {
+    read_bytes(bytes, offset).map(i32::from_le_bytes)
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, String> {
+    read_bytes(bytes, offset).map(u64::from_le_bytes)
+}
+
+fn read_f64(bytes: &[u8], offset: usize) -> Result<f64, String> {
+    read_bytes(bytes, offset).map(f64::from_le_bytes)
+}
+
+fn read_dvec3(bytes: &[u8], offset: usize, stride: usize) -> Result<DVec3, String> {
+    Ok(vec3(
+        read_f64(bytes, offset)?,
+        read_f64(bytes, offset + stride)?,
+        read_f64(bytes, offset + 2 * stride)?,
+    ))
+}
+
+impl LasHeader {
+    pub fn parse(bytes: &[u8]) -> Result<LasHeader, String> {
+        if bytes.get(0..4) != Some(b"LASF") {
+            return Err("Not a LAS file, the signature 'LASF' is missing".to_string());
+        }
+
+        let version = (read_u8(bytes, 24)?, read_u8(bytes, 25)?);
+        let header_size = read_u16(bytes, 94)?;
+
+        if version.0 != 1 || version.1 > 4 {
+            return Err(format!(
+                "Unsupported LAS version {}.{}",
+                version.0, version.1
+            ));
+        }
+
+        let min_header_size = if version.1 >= 4 {
+            LAS_1_4_HEADER_SIZE
+        } else {
+            MIN_HEADER_SIZE
+        };
+        if (header_size as usize) < min_header_size {
+            return Err(format!(
+                "LAS {}.{} header must be at least {} bytes, got {}",
+                version.0, version.1, min_header_size, header_size
+            ));
+        }
+
+        // LAS 1.4 files may leave the legacy point count at 0 for formats 6 and up
+        let number_of_points = if version.1 >= 4 {
+            read_u64(bytes, 247)?
+        } else {
+            read_u32(bytes, 107)? as u64
+        };
+
+        let header = LasHeader {
+            version,
+            header_size,
+            offset_to_point_data: read_u32(bytes, 96)?,
+            number_of_vlrs: read_u32(bytes, 100)?,
+            point_data_format: read_u8(bytes, 104)?,
+            point_record_length: read_u16(bytes, 105)?,
+            number_of_points,
+            scale: read_dvec3(bytes, 131, 8)?,
+            offset: read_dvec3(bytes, 155, 8)?,
+            max: read_dvec3(bytes, 179, 16)?,
+            min: read_dvec3(bytes, 187, 16)?,
+        };
+
+        if header.format_id() > MAX_POINT_DATA_FORMAT {
+            return Err(format!(
+                "Unsupported LAS point data format {}",
+                header.format_id()
+            ));
+        }
+        if header.point_record_length < 12 {
+            return Err(format!(
+                "LAS point records must be at least 12 bytes, got {}",
+                header.point_record_length
+            ));
+        }
+
+        Ok(header)
+    }
+
+    /// The point data format without the compression bit
+    pub fn format_id(&self) -> u8 {
+        self.point_data_format & !COMPRESSED_FORMAT_BIT
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.point_data_format & COMPRESSED_FORMAT_BIT != 0
+    }
+}
+
+/// A variable length record following the header
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vlr<'a> {
+    pub user_id: String,
+    pub record_id: u16,
+    pub data: &'a [u8],
+}
+
+const VLR_HEADER_SIZE: usize = 54;
+
+/// Reads the variable length records between the header and the point data
+pub fn read_vlrs<'a>(bytes: &'a [u8], header: &LasHeader) -> Result<Vec<Vlr<'a>>, String> {
+    let mut offset = header.header_size as usize;
+
+    (0..header.number_of_vlrs)
+        .map(|_| {
+            let user_id = read_bytes::<16>(bytes, offset + 2)?;
+            let record_id = read_u16(bytes, offset + 18)?;
+            let length = read_u16(bytes, offset + 20)? as usize;
+            let data_start = offset + VLR_HEADER_SIZE;
+            let data = bytes
+                .get(data_start..data_start + length)
+                .ok_or_else(|| format!("LAS VLR at {} ends after the data", offset))?;
+            offset = data_start + length;
+
+            Ok(Vlr {
+                user_id: String::from_utf8_lossy(&user_id)
+                    .trim_end_matches('\0')
+                    .to_string(),
+                record_id,
+                data,
+            })
+        })
+        .collect()
+}
+
+/// Reads the header and the point records of a LAS file. The records of LAZ files are
+/// decompressed
+pub fn read_point_records(bytes: &[u8]) -> Result<(LasHeader, Cow<[u8]>), String> {
+    let header = LasHeader::parse(bytes)?;
+
+    if header.is_compressed() {
+        let vlr = LazVlr::find(bytes, &header)?;
+        let records = laz::decompress_points(bytes, &header, &vlr)?;
+        return Ok((header, Cow::Owned(records)));
+    }
+
+    let start = header.offset_to_point_data as usize;
+    let record_length = header.point_record_length as usize;
+    let end = header
+        .number_of_points
+        .checked_mul(record_length as u64)
+        .and_then(|length| length.checked_add(start as u64))
+        .filter(|&end| end <= bytes.len() as u64)
+        .ok_or_else(|| {
+            format!(
+                "LAS file is too short for {} points of {} bytes",
+                header.number_of_points, record_length
+            )
+        })? as usize;
+
+    Ok((header, Cow::Borrowed(&bytes[start..end])))
+}
+
+/// Reads the header and the positions of all points of a LAS or LAZ file, with scale and offset
+/// applied
+pub fn read_positions(bytes: &[u8]) -> Result<(LasHeader, Vec<DVec3>), String> {
+    let (header, records) = read_point_records(bytes)?;
+    let record_length = header.point_record_length as usize;
+
+    let positions = records
+        .chunks_exact(record_length)
+        .map(|record| {
+            let coordinates = vec3(
+                read_i32(record, 0)? as f64,
+                read_i32(record, 4)? as f64,
+                read_i32(record, 8)? as f64,
+            );
+            Ok(coordinates.component_mul(&header.scale) + header.offset)
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok((header, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::{read_positions, LasHeader};
+
+    fn create_las_file(version_minor: u8, point_data_format: u8, points: &[[i32; 3]]) -> Vec<u8> {
+        let header_size: usize = if version_minor >= 4 { 375 } else { 227 };
+        let record_length: usize = 20;
+
+        let mut bytes = vec![0u8; header_size];
+        bytes[0..4].copy_from_slice(b"LASF");
+        bytes[24] = 1;
+        bytes[25] = version_minor;
+        bytes[94..96].copy_from_slice(&(header_size as u16).to_le_bytes());
+        bytes[96..100].copy_from_slice(&(header_size as u32).to_le_bytes());
+        bytes[104] = point_data_format;
+        bytes[105..107].copy_from_slice(&(record_length as u16).to_le_bytes());
+
+        if version_minor >= 4 {
+            bytes[247..255].copy_from_slice(&(points.len() as u64).to_le_bytes());
+        } else {
+            bytes[107..111].copy_from_slice(&(points.len() as u32).to_le_bytes());
+        }
+
+        for (i, scale) in [0.01f64, 0.01, 0.001].iter().enumerate() {
+            bytes[131 + 8 * i..139 + 8 * i].copy_from_slice(&scale.to_le_bytes());
+        }
+        for (i, offset) in [1000.0f64, 2000.0, 0.0].iter().enumerate() {
+            bytes[155 + 8 * i..163 + 8 * i].copy_from_slice(&offset.to_le_bytes());
+        }
+
+        for point in points {
+            let mut record = vec![0u8; record_length];
+            for (i, coordinate) in point.iter().enumerate() {
+                record[4 * i..4 * i + 4].copy_from_slice(&coordinate.to_le_bytes());
+            }
+            bytes.extend(record);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn positions_are_scaled_and_offset() {
+        for version_minor in [2, 4] {
+            let bytes = create_las_file(version_minor, 0, &[[0, 0, 0], [150, -200, 2500]]);
+
+            let (header, positions) = read_positions(&bytes).unwrap();
+
+            assert_eq!(header.number_of_points, 2);
+            assert_eq!(positions[0], vec3(1000.0, 2000.0, 0.0));
+            assert!((positions[1] - vec3(1001.5, 1998.0, 2.5)).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        let bytes = create_las_file(2, 1, &[[0, 0, 0]]);
+
+        assert!(LasHeader::parse(&bytes[..100]).is_err());
+        assert!(LasHeader::parse(b"not a las file").is_err());
+        assert!(read_positions(&bytes[..bytes.len() - 1]).is_err());
+        assert!(read_positions(&create_las_file(2, 0x80 | 1, &[[0, 0, 0]])).is_err());
+        assert!(read_positions(&create_las_file(2, 11, &[[0, 0, 0]])).is_err());
+    }
+}
@@ -0,0 +1,401 @@
+//! Writing a subset of the points of a sector to an uncompressed LAS 1.4 file, with the object
+//! id of each point in an extra bytes dimension
+
+use std::collections::HashSet;
+
+use nalgebra_glm::{vec3, DVec3};
+
+/// Stored coordinates are in millimeters, relative to the point offset of the sector
+pub const EXPORT_SCALE: f64 = 0.001;
+pub const OBJECT_ID_DIMENSION_NAME: &str = "object_id";
+
+const HEADER_SIZE: usize = 375;
+const VLR_HEADER_SIZE: usize = 54;
+const EXTRA_BYTES_USER_ID: &str = "LASF_Spec";
+const EXTRA_BYTES_RECORD_ID: u16 = 4;
+const EXTRA_BYTES_DESCRIPTOR_SIZE: usize = 192;
+const EXTRA_BYTES_U32_TYPE: u8 = 5;
+/// Must be set for point data formats 6 and up
+const WKT_GLOBAL_ENCODING_BIT: u16 = 1 << 4;
+
+/// The points of a sector as the viewer holds them. Positions are xyz relative to the point
+/// offset, and each optional attribute has one value per point. Colors are RGBA
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PointAttributes<'a> {
+    pub positions: &'a [f32],
+    pub colors: Option<&'a [u8]>,
+    pub intensities: Option<&'a [f32]>,
+    pub classifications: Option<&'a [u8]>,
+    pub return_numbers: Option<&'a [u8]>,
+    pub numbers_of_returns: Option<&'a [u8]>,
+    pub point_source_ids: Option<&'a [u16]>,
+}
+
+/// Which points of a sector to export
+#[derive(Clone, Copy, Debug)]
+pub enum PointSelection<'a> {
+    /// The points assigned to any of these objects
+    ObjectIds(&'a [u32]),
+    /// The points where the mask is set
+    Mask(&'a [bool]),
+}
+
+fn check_length<T>(
+    values: Option<&[T]>,
+    per_point: usize,
+    num_points: usize,
+    name: &str,
+) -> Result<(), String> {
+    match values {
+        Some(values) if values.len() != per_point * num_points => Err(format!(
+            "Expected {} {} values for {} points, got {}",
+            per_point * num_points,
+            name,
+            num_points,
+            values.len()
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn write_string(bytes: &mut [u8], offset: usize, length: usize, value: &str) {
+    let value = &value.as_bytes()[..value.len().min(length)];
+    bytes[offset..offset + value.len()].copy_from_slice(value);
+}
+
+fn create_extra_bytes_vlr() -> Vec<u8> {
+    let mut bytes = vec![0u8; VLR_HEADER_SIZE + EXTRA_BYTES_DESCRIPTOR_SIZE];
+    write_string(&mut bytes, 2, 16, EXTRA_BYTES_USER_ID);
+    bytes[18..20].copy_from_slice(&EXTRA_BYTES_RECORD_ID.to_le_bytes());
+    bytes[20..22].copy_from_slice(&(EXTRA_BYTES_DESCRIPTOR_SIZE as u16).to_le_bytes());
+    write_string(&mut bytes, 22, 32, "Object ids");
+
+    let descriptor = &mut bytes[VLR_HEADER_SIZE..];
+    descriptor[2] = EXTRA_BYTES_U32_TYPE;
+    write_string(descriptor, 4, 32, OBJECT_ID_DIMENSION_NAME);
+    write_string(descriptor, 160, 32, "Object the point is assigned to");
+    bytes
+}
+
+/// Writes the selected points to a LAS 1.4 file. `object_ids` are the object ids of all points,
+/// as given by point assignment, and are stored as a `u32` extra bytes dimension. The file
+/// uses point data format 7 if there are colors, and format 6 otherwise. The offset of the file
+/// is `point_offset`, and the scale is `EXPORT_SCALE`
+pub fn export_las(
+    attributes: &PointAttributes,
+    object_ids: &[u32],
+    selection: PointSelection,
+    point_offset: &DVec3,
+) -> Result<Vec<u8>, String> {
+    if attributes.positions.len() % 3 != 0 {
+        return Err(format!(
+            "Expected xyz positions, got {} coordinates",
+            attributes.positions.len()
+        ));
+    }
+    let num_points = attributes.positions.len() / 3;
+
+    check_length(Some(object_ids), 1, num_points, "object id")?;
+    check_length(attributes.colors, 4, num_points, "color")?;
+    check_length(attributes.intensities, 1, num_points, "intensity")?;
+    check_length(attributes.classifications, 1, num_points, "classification")?;
+    check_length(attributes.return_numbers, 1, num_points, "return number")?;
+    check_length(
+        attributes.numbers_of_returns,
+        1,
+        num_points,
+        "number of returns",
+    )?;
+    check_length(
+        attributes.point_source_ids,
+        1,
+        num_points,
+        "point source id",
+    )?;
+
+    let selected: Vec<usize> = match selection {
+        PointSelection::ObjectIds(ids) => {
+            let ids: HashSet<u32> = ids.iter().copied().collect();
+            (0..num_points)
+                .filter(|&index| ids.contains(&object_ids[index]))
+                .collect()
+        }
+        PointSelection::Mask(mask) => {
+            check_length(Some(mask), 1, num_points, "selection mask")?;
+            (0..num_points).filter(|&index| mask[index]).collect()
+        }
+    };
+
+    let format: u8 = if attributes.colors.is_some() { 7 } else { 6 };
+    let core_length = if attributes.colors.is_some() { 36 } else { 30 };
+    let record_length = core_length + 4;
+
+    let vlr = create_extra_bytes_vlr();
+    let offset_to_point_data = HEADER_SIZE + vlr.len();
+
+    let mut min = DVec3::repeat(f64::MAX);
+    let mut max = DVec3::repeat(-f64::MAX);
+    let mut points_by_return = [0u64; 15];
+    let mut records = Vec::with_capacity(selected.len() * record_length);
+
+    for index in selected.iter().copied() {
+        let mut record = vec![0u8; record_length];
+
+        let mut coordinates = [0i32; 3];
+        for (axis, coordinate) in coordinates.iter_mut().enumerate() {
+            let value = (attributes.positions[3 * index + axis] as f64 / EXPORT_SCALE).round();
+            if !(i32::MIN as f64..=i32::MAX as f64).contains(&value) {
+                return Err(format!(
+                    "Point {} is too far from the point offset to be stored",
+                    index
+                ));
+            }
+            *coordinate = value as i32;
+        }
+        for (axis, coordinate) in coordinates.iter().enumerate() {
+            record[4 * axis..4 * axis + 4].copy_from_slice(&coordinate.to_le_bytes());
+        }
+        let position = vec3(
+            coordinates[0] as f64,
+            coordinates[1] as f64,
+            coordinates[2] as f64,
+        ) * EXPORT_SCALE
+            + point_offset;
+        min = min.inf(&position);
+        max = max.sup(&position);
+
+        let intensity = attributes.intensities.map_or(0, |intensities| {
+            intensities[index].round().clamp(0.0, u16::MAX as f32) as u16
+        });
+        record[12..14].copy_from_slice(&intensity.to_le_bytes());
+
+        let return_number = attributes
+            .return_numbers
+            .map_or(1, |values| values[index].min(15));
+        let number_of_returns = attributes
+            .numbers_of_returns
+            .map_or(1, |values| values[index].min(15));
+        record[14] = return_number | (number_of_returns << 4);
+        if (1..=15).contains(&return_number) {
+            points_by_return[return_number as usize - 1] += 1;
+        }
+
+        record[16] = attributes.classifications.map_or(0, |values| values[index]);
+        let point_source_id = attributes
+            .point_source_ids
+            .map_or(0, |values| values[index]);
+        record[20..22].copy_from_slice(&point_source_id.to_le_bytes());
+
+        if let Some(colors) = attributes.colors {
+            for channel in 0..3 {
+                // Spreads 8-bit colors over the 16-bit range, so 255 becomes 65535
+                let value = colors[4 * index + channel] as u16 * 257;
+                record[30 + 2 * channel..32 + 2 * channel].copy_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        record[core_length..].copy_from_slice(&object_ids[index].to_le_bytes());
+        records.extend(record);
+    }
+
+    if selected.is_empty() {
+        min = *point_offset;
+        max = *point_offset;
+    }
+
+    let mut bytes = vec![0u8; HEADER_SIZE];
+    bytes[0..4].copy_from_slice(b"LASF");
+    bytes[6..8].copy_from_slice(&WKT_GLOBAL_ENCODING_BIT.to_le_bytes());
+    bytes[24] = 1;
+    bytes[25] = 4;
+    write_string(&mut bytes, 26, 32, "EXTRACTION");
+    write_string(&mut bytes, 58, 32, "Cognite Reveal");
+    bytes[94..96].copy_from_slice(&(HEADER_SIZE as u16).to_le_bytes());
+    bytes[96..100].copy_from_slice(&(offset_to_point_data as u32).to_le_bytes());
+    bytes[100..104].copy_from_slice(&1u32.to_le_bytes());
+    bytes[104] = format;
+    bytes[105..107].copy_from_slice(&(record_length as u16).to_le_bytes());
+    for axis in 0..3 {
+        let at = |offset: usize| offset + 8 * axis;
+        bytes[at(131)..at(139)].copy_from_slice(&EXPORT_SCALE.to_le_bytes());
+        bytes[at(155)..at(163)].copy_from_slice(&point_offset[axis].to_le_bytes());
+        // Maximum and minimum alternate for each axis
+        let at = |offset: usize| offset + 16 * axis;
+        bytes[at(179)..at(187)].copy_from_slice(&max[axis].to_le_bytes());
+        bytes[at(187)..at(195)].copy_from_slice(&min[axis].to_le_bytes());
+    }
+    bytes[247..255].copy_from_slice(&(selected.len() as u64).to_le_bytes());
+    for (i, count) in points_by_return.iter().enumerate() {
+        bytes[255 + 8 * i..263 + 8 * i].copy_from_slice(&count.to_le_bytes());
+    }
+
+    bytes.extend(vlr);
+    bytes.extend(records);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{vec3, DVec3};
+
+    use super::{export_las, PointAttributes, PointSelection, EXPORT_SCALE};
+    use crate::{ept, las};
+
+    const POSITIONS: [f32; 9] = [0.0, 1.0, 2.0, -1.5, 0.25, 3.0, 10.0, -10.0, 0.001];
+    const OBJECT_IDS: [u32; 3] = [7, 0, 7];
+
+    fn offset() -> DVec3 {
+        vec3(1000.0, 2000.0, 30.0)
+    }
+
+    fn read_object_ids(bytes: &[u8]) -> Vec<u32> {
+        let (header, records) = las::read_point_records(bytes).unwrap();
+        let length = header.point_record_length as usize;
+        records
+            .chunks_exact(length)
+            .map(|record| u32::from_le_bytes(record[length - 4..].try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn exports_selected_objects_with_object_ids() {
+        let colors = [255, 0, 128, 0, 1, 2, 3, 0, 10, 20, 30, 0];
+        let classifications = [2, 5, 6];
+        let attributes = PointAttributes {
+            positions: &POSITIONS,
+            colors: Some(&colors),
+            classifications: Some(&classifications),
+            ..Default::default()
+        };
+        let bytes = export_las(
+            &attributes,
+            &OBJECT_IDS,
+            PointSelection::ObjectIds(&[7]),
+            &offset(),
+        )
+        .unwrap();
+
+        let (header, records) = las::read_point_records(&bytes).unwrap();
+        assert_eq!(header.version, (1, 4));
+        assert_eq!(header.point_data_format, 7);
+        assert_eq!(header.point_record_length, 40);
+        assert_eq!(header.number_of_points, 2);
+        assert_eq!(header.scale, DVec3::repeat(EXPORT_SCALE));
+        assert_eq!(header.offset, offset());
+        assert!((header.min - vec3(1000.0, 1990.0, 30.001)).abs().max() < 1e-9);
+        assert!((header.max - vec3(1010.0, 2001.0, 32.0)).abs().max() < 1e-9);
+
+        let decoded =
+            ept::decode_las_point_records(&records, &header, &offset(), &offset()).unwrap();
+        assert_eq!(decoded.positions, [0.0, 1.0, 2.0, 10.0, -10.0, 0.001]);
+        assert_eq!(decoded.colors.unwrap(), [255, 0, 128, 0, 10, 20, 30, 0]);
+        assert_eq!(decoded.classifications.unwrap(), [2, 6]);
+        assert_eq!(decoded.return_numbers.unwrap(), [1, 1]);
+        assert_eq!(decoded.numbers_of_returns.unwrap(), [1, 1]);
+        assert_eq!(read_object_ids(&bytes), [7, 7]);
+    }
+
+    #[test]
+    fn exports_masked_points_without_colors() {
+        let intensities = [100.0, 2.4, 70000.0];
+        let point_source_ids = [1, 2, 3];
+        let attributes = PointAttributes {
+            positions: &POSITIONS,
+            intensities: Some(&intensities),
+            point_source_ids: Some(&point_source_ids),
+            ..Default::default()
+        };
+        let bytes = export_las(
+            &attributes,
+            &OBJECT_IDS,
+            PointSelection::Mask(&[false, true, true]),
+            &offset(),
+        )
+        .unwrap();
+
+        let (header, records) = las::read_point_records(&bytes).unwrap();
+        assert_eq!(header.point_data_format, 6);
+        assert_eq!(header.point_record_length, 34);
+        assert_eq!(header.number_of_points, 2);
+
+        let decoded =
+            ept::decode_las_point_records(&records, &header, &offset(), &offset()).unwrap();
+        assert_eq!(decoded.positions, [-1.5, 0.25, 3.0, 10.0, -10.0, 0.001]);
+        assert!(decoded.colors.is_none());
+        assert_eq!(decoded.intensities.unwrap(), [2.0, 65535.0]);
+        assert_eq!(decoded.point_source_ids.unwrap(), [2, 3]);
+        assert_eq!(read_object_ids(&bytes), [0, 7]);
+    }
+
+    #[test]
+    fn describes_object_ids_in_extra_bytes_vlr() {
+        let attributes = PointAttributes {
+            positions: &POSITIONS,
+            ..Default::default()
+        };
+        let bytes = export_las(
+            &attributes,
+            &OBJECT_IDS,
+            PointSelection::ObjectIds(&[]),
+            &offset(),
+        )
+        .unwrap();
+
+        let header = las::LasHeader::parse(&bytes).unwrap();
+        assert_eq!(header.number_of_points, 0);
+        assert_eq!(header.min, offset());
+        assert_eq!(header.max, offset());
+
+        let vlrs = las::read_vlrs(&bytes, &header).unwrap();
+        assert_eq!(vlrs.len(), 1);
+        assert_eq!(vlrs[0].user_id, "LASF_Spec");
+        assert_eq!(vlrs[0].record_id, 4);
+        assert_eq!(vlrs[0].data.len(), 192);
+        assert_eq!(vlrs[0].data[2], 5);
+        assert_eq!(&vlrs[0].data[4..13], b"object_id");
+        assert!(vlrs[0].data[13..36].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn rejects_invalid_inputs() {
+        let attributes = PointAttributes {
+            positions: &POSITIONS,
+            classifications: Some(&[1, 2]),
+            ..Default::default()
+        };
+        assert!(export_las(
+            &attributes,
+            &OBJECT_IDS,
+            PointSelection::ObjectIds(&[7]),
+            &offset()
+        )
+        .is_err());
+
+        let attributes = PointAttributes {
+            positions: &POSITIONS,
+            ..Default::default()
+        };
+        assert!(export_las(
+            &attributes,
+            &[7, 0],
+            PointSelection::ObjectIds(&[7]),
+            &offset()
+        )
+        .is_err());
+        assert!(export_las(
+            &attributes,
+            &OBJECT_IDS,
+            PointSelection::Mask(&[true]),
+            &offset()
+        )
+        .is_err());
+
+        let far_away = [0.0, 0.0, 3.0e6];
+        let attributes = PointAttributes {
+            positions: &far_away,
+            ..Default::default()
+        };
+        assert!(export_las(&attributes, &[0], PointSelection::Mask(&[true]), &offset()).is_err());
+        assert!(export_las(&attributes, &[0], PointSelection::Mask(&[false]), &offset()).is_ok());
+    }
+}
@@ -0,0 +1,292 @@
+//! Adaptive arithmetic decoder and the probability models used by LASzip, which are based on
+//! the coder by Amir Said
+
+/// Renormalize the interval when it gets shorter than this
+pub const AC_MIN_LENGTH: u32 = 0x0100_0000;
+pub const AC_MAX_LENGTH: u32 = 0xFFFF_FFFF;
+
+pub const BM_LENGTH_SHIFT: u32 = 13;
+const BM_MAX_COUNT: u32 = 1 << BM_LENGTH_SHIFT;
+
+pub const DM_LENGTH_SHIFT: u32 = 15;
+const DM_MAX_COUNT: u32 = 1 << DM_LENGTH_SHIFT;
+
+/// Adaptive model for a single bit
+#[derive(Clone, Debug)]
+pub struct ArithmeticBitModel {
+    pub bit_0_count: u32,
+    pub bit_count: u32,
+    pub bit_0_prob: u32,
+    pub bits_until_update: u32,
+    pub update_cycle: u32,
+}
+
+impl ArithmeticBitModel {
+    pub fn new() -> ArithmeticBitModel {
+        ArithmeticBitModel {
+            bit_0_count: 1,
+            bit_count: 2,
+            bit_0_prob: 1 << (BM_LENGTH_SHIFT - 1),
+            bits_until_update: 4,
+            update_cycle: 4,
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.bit_count += self.update_cycle;
+        if self.bit_count > BM_MAX_COUNT {
+            self.bit_count = (self.bit_count + 1) >> 1;
+            self.bit_0_count = (self.bit_0_count + 1) >> 1;
+            if self.bit_0_count == self.bit_count {
+                self.bit_count += 1;
+            }
+        }
+
+        let scale = 0x8000_0000u32 / self.bit_count;
+        self.bit_0_prob = (self.bit_0_count * scale) >> (31 - BM_LENGTH_SHIFT);
+
+        self.update_cycle = ((5 * self.update_cycle) >> 2).min(64);
+        self.bits_until_update = self.update_cycle;
+    }
+}
+
+/// Adaptive model for symbols in `0..symbols`. Models with more than 16 symbols get a lookup
+/// table that speeds up decoding
+#[derive(Clone, Debug)]
+pub struct ArithmeticModel {
+    pub symbols: u32,
+    pub last_symbol: u32,
+    pub distribution: Vec<u32>,
+    pub symbol_count: Vec<u32>,
+    decoder_table: Vec<u32>,
+    table_shift: u32,
+    total_count: u32,
+    update_cycle: u32,
+    pub symbols_until_update: u32,
+}
+
+impl ArithmeticModel {
+    pub fn new(symbols: u32) -> ArithmeticModel {
+        assert!((2..=2048).contains(&symbols));
+
+        let (decoder_table, table_shift) = if symbols > 16 {
+            let mut table_bits = 3;
+            while symbols > 1 << (table_bits + 2) {
+                table_bits += 1;
+            }
+            let table_size = 1usize << table_bits;
+            (vec![0; table_size + 2], DM_LENGTH_SHIFT - table_bits)
+        } else {
+            (vec![], 0)
+        };
+
+        let mut model = ArithmeticModel {
+            symbols,
+            last_symbol: symbols - 1,
+            distribution: vec![0; symbols as usize],
+            symbol_count: vec![1; symbols as usize],
+            decoder_table,
+            table_shift,
+            total_count: 0,
+            update_cycle: symbols,
+            symbols_until_update: 0,
+        };
+
+        model.update();
+        model.update_cycle = (symbols + 6) >> 1;
+        model.symbols_until_update = model.update_cycle;
+        model
+    }
+
+    pub fn update(&mut self) {
+        self.total_count += self.update_cycle;
+        if self.total_count > DM_MAX_COUNT {
+            self.total_count = 0;
+            for count in &mut self.symbol_count {
+                *count = (*count + 1) >> 1;
+                self.total_count += *count;
+            }
+        }
+
+        let scale = 0x8000_0000u32 / self.total_count;
+        let mut sum = 0;
+        for (distribution, count) in self.distribution.iter_mut().zip(&self.symbol_count) {
+            *distribution = (scale * sum) >> (31 - DM_LENGTH_SHIFT);
+            sum += count;
+        }
+
+        if !self.decoder_table.is_empty() {
+            let table_size = self.decoder_table.len() - 2;
+            let mut s = 0;
+            for (k, distribution) in self.distribution.iter().enumerate() {
+                let w = (distribution >> self.table_shift) as usize;
+                while s < w {
+                    s += 1;
+                    self.decoder_table[s] = k as u32 - 1;
+                }
+            }
+            self.decoder_table[0] = 0;
+            while s <= table_size {
+                s += 1;
+                self.decoder_table[s] = self.symbols - 1;
+            }
+        }
+
+        self.update_cycle = ((5 * self.update_cycle) >> 2).min((self.symbols + 6) << 3);
+        self.symbols_until_update = self.update_cycle;
+    }
+}
+
+/// Decodes an arithmetic coded byte stream. Reading past the end of the stream gives zeros,
+/// like the padding the encoder writes at the end
+pub struct ArithmeticDecoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    value: u32,
+    length: u32,
+}
+
+impl<'a> ArithmeticDecoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> ArithmeticDecoder<'a> {
+        let mut decoder = ArithmeticDecoder {
+            bytes,
+            position: 0,
+            value: 0,
+            length: AC_MAX_LENGTH,
+        };
+        for _ in 0..4 {
+            decoder.value = (decoder.value << 8) | decoder.next_byte() as u32;
+        }
+        decoder
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        let byte = self.bytes.get(self.position).copied().unwrap_or(0);
+        self.position += 1;
+        byte
+    }
+
+    fn renormalize(&mut self) {
+        while self.length < AC_MIN_LENGTH {
+            self.value = (self.value << 8) | self.next_byte() as u32;
+            self.length <<= 8;
+        }
+    }
+
+    pub fn decode_bit(&mut self, model: &mut ArithmeticBitModel) -> u32 {
+        let x = model.bit_0_prob * (self.length >> BM_LENGTH_SHIFT);
+        let symbol = if self.value < x {
+            self.length = x;
+            model.bit_0_count += 1;
+            0
+        } else {
+            self.value -= x;
+            self.length -= x;
+            1
+        };
+
+        self.renormalize();
+
+        model.bits_until_update -= 1;
+        if model.bits_until_update == 0 {
+            model.update();
+        }
+        symbol
+    }
+
+    pub fn decode_symbol(&mut self, model: &mut ArithmeticModel) -> u32 {
+        let mut x;
+        let mut y = self.length;
+        let mut symbol;
+
+        if !model.decoder_table.is_empty() {
+            self.length >>= DM_LENGTH_SHIFT;
+            let dv = self.value / self.length;
+            let t = (dv >> model.table_shift) as usize;
+
+            symbol = model.decoder_table[t];
+            let mut n = model.decoder_table[t + 1] + 1;
+            while n > symbol + 1 {
+                let k = (symbol + n) >> 1;
+                if model.distribution[k as usize] > dv {
+                    n = k;
+                } else {
+                    symbol = k;
+                }
+            }
+
+            x = model.distribution[symbol as usize] * self.length;
+            if symbol != model.last_symbol {
+                y = model.distribution[symbol as usize + 1] * self.length;
+            }
+        } else {
+            x = 0;
+            symbol = 0;
+            self.length >>= DM_LENGTH_SHIFT;
+            let mut n = model.symbols;
+            let mut k = n >> 1;
+            loop {
+                let z = self.length * model.distribution[k as usize];
+                if z > self.value {
+                    n = k;
+                    y = z;
+                } else {
+                    symbol = k;
+                    x = z;
+                }
+                k = (symbol + n) >> 1;
+                if k == symbol {
+                    break;
+                }
+            }
+        }
+
+        self.value -= x;
+        self.length = y - x;
+        self.renormalize();
+
+        model.symbol_count[symbol as usize] += 1;
+        model.symbols_until_update -= 1;
+        if model.symbols_until_update == 0 {
+            model.update();
+        }
+        symbol
+    }
+
+    /// Reads `bits` raw bits, 1 to 32
+    pub fn read_bits(&mut self, bits: u32) -> u32 {
+        debug_assert!(bits > 0 && bits <= 32);
+
+        if bits > 19 {
+            let lower = self.read_short() as u32;
+            let upper = self.read_bits(bits - 16);
+            return (upper << 16) | lower;
+        }
+
+        self.length >>= bits;
+        let symbol = self.value / self.length;
+        self.value -= self.length * symbol;
+        self.renormalize();
+        symbol
+    }
+
+    pub fn read_short(&mut self) -> u16 {
+        self.length >>= 16;
+        let symbol = self.value / self.length;
+        self.value -= self.length * symbol;
+        self.renormalize();
+        symbol as u16
+    }
+
+    pub fn read_int(&mut self) -> u32 {
+        let lower = self.read_short() as u32;
+        let upper = self.read_short() as u32;
+        (upper << 16) | lower
+    }
+
+    pub fn read_int64(&mut self) -> u64 {
+        let lower = self.read_int() as u64;
+        let upper = self.read_int() as u64;
+        (upper << 32) | lower
+    }
+}
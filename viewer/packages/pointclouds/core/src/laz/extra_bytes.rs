@@ -0,0 +1,109 @@
+//! Extra bytes after the standard fields of a point, compressed byte by byte as differences to
+//! the last point
+
+use super::arithmetic_decoder::{ArithmeticDecoder, ArithmeticModel};
+use super::{ByteReader, LayerReader, LayeredItemDecompressor, PointwiseItemDecompressor};
+
+/// Version 2 of the BYTE item
+pub struct ExtraBytesDecompressor {
+    last: Vec<u8>,
+    models: Vec<ArithmeticModel>,
+}
+
+impl ExtraBytesDecompressor {
+    pub fn new(count: usize) -> ExtraBytesDecompressor {
+        ExtraBytesDecompressor {
+            last: vec![0; count],
+            models: vec![ArithmeticModel::new(256); count],
+        }
+    }
+}
+
+impl PointwiseItemDecompressor for ExtraBytesDecompressor {
+    fn init(&mut self, first: &[u8]) {
+        let count = self.last.len();
+        self.last.copy_from_slice(&first[..count]);
+    }
+
+    fn decompress(&mut self, decoder: &mut ArithmeticDecoder, item: &mut [u8]) {
+        for (last, model) in self.last.iter_mut().zip(&mut self.models) {
+            *last = last.wrapping_add(decoder.decode_symbol(model) as u8);
+        }
+        item[..self.last.len()].copy_from_slice(&self.last);
+    }
+}
+
+#[derive(Clone)]
+struct ExtraBytesContext {
+    last: Vec<u8>,
+    models: Vec<ArithmeticModel>,
+}
+
+impl ExtraBytesContext {
+    fn new(last: &[u8]) -> ExtraBytesContext {
+        ExtraBytesContext {
+            last: last.to_vec(),
+            models: vec![ArithmeticModel::new(256); last.len()],
+        }
+    }
+}
+
+/// Version 3 of the BYTE14 item, with one layer for each byte
+pub struct ExtraBytes14Decompressor<'a> {
+    layers: Vec<LayerReader<'a>>,
+    contexts: [Option<ExtraBytesContext>; 4],
+    current_context: usize,
+}
+
+impl<'a> ExtraBytes14Decompressor<'a> {
+    pub fn new(count: usize) -> ExtraBytes14Decompressor<'a> {
+        ExtraBytes14Decompressor {
+            layers: (0..count).map(|_| LayerReader::default()).collect(),
+            contexts: Default::default(),
+            current_context: 0,
+        }
+    }
+}
+
+impl<'a> LayeredItemDecompressor<'a> for ExtraBytes14Decompressor<'a> {
+    fn read_layer_sizes(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String> {
+        for layer in &mut self.layers {
+            layer.size = reader.read_u32()? as usize;
+        }
+        Ok(())
+    }
+
+    fn read_layers(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String> {
+        self.layers
+            .iter_mut()
+            .try_for_each(|layer| layer.read(reader))
+    }
+
+    fn init(&mut self, first: &[u8], context: &mut usize) {
+        let context = *context;
+        self.contexts = Default::default();
+        self.contexts[context] = Some(ExtraBytesContext::new(&first[..self.layers.len()]));
+        self.current_context = context;
+    }
+
+    fn decompress(&mut self, item: &mut [u8], context: &mut usize) {
+        let context = *context;
+        if self.contexts[context].is_none() {
+            let last = self.contexts[self.current_context].as_ref().map_or_else(
+                || vec![0; self.layers.len()],
+                |current| current.last.clone(),
+            );
+            self.contexts[context] = Some(ExtraBytesContext::new(&last));
+        }
+        self.current_context = context;
+        let state = self.contexts[context].as_mut().unwrap();
+
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            if let Some(decoder) = &mut layer.decoder {
+                let diff = decoder.decode_symbol(&mut state.models[i]) as u8;
+                state.last[i] = state.last[i].wrapping_add(diff);
+            }
+        }
+        item[..state.last.len()].copy_from_slice(&state.last);
+    }
+}
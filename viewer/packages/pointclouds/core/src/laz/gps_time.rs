@@ -0,0 +1,186 @@
+//! GPS times, compressed as differences of their 64-bit representation. Up to four sequences of
+//! times are tracked, since files often interleave points from several flight lines
+
+use super::arithmetic_decoder::{ArithmeticDecoder, ArithmeticModel};
+use super::integer_decompressor::IntegerDecompressor;
+use super::PointwiseItemDecompressor;
+
+pub const GPS_TIME_SIZE: usize = 8;
+
+const GPS_TIME_MULTI: i32 = 500;
+const GPS_TIME_MULTI_MINUS: i32 = -10;
+/// Multiples below this are differences, the first symbol after them means unchanged in the
+/// pointwise item and a full time in the layered item
+const GPS_TIME_MULTI_END: u32 = (GPS_TIME_MULTI - GPS_TIME_MULTI_MINUS + 1) as u32;
+
+/// Decompresses the GPS time of the GPSTIME11 version 2 item, and of the POINT14 version 3
+/// item. `layered` selects the symbols of the latter, which signals unchanged times outside of
+/// this decompressor
+pub struct GpsTimeDecompressor {
+    layered: bool,
+    last: usize,
+    next: usize,
+    last_gps_time: [i64; 4],
+    last_gps_time_diff: [i32; 4],
+    multi_extreme_counter: [i32; 4],
+    multi_model: ArithmeticModel,
+    zero_diff_model: ArithmeticModel,
+    ic_gps_time: IntegerDecompressor,
+}
+
+impl GpsTimeDecompressor {
+    pub fn new(first_gps_time: i64, layered: bool) -> GpsTimeDecompressor {
+        let (multi_symbols, zero_diff_symbols) = if layered {
+            (GPS_TIME_MULTI_END + 4, 5)
+        } else {
+            (GPS_TIME_MULTI_END + 5, 6)
+        };
+
+        GpsTimeDecompressor {
+            layered,
+            last: 0,
+            next: 0,
+            last_gps_time: [first_gps_time, 0, 0, 0],
+            last_gps_time_diff: [0; 4],
+            multi_extreme_counter: [0; 4],
+            multi_model: ArithmeticModel::new(multi_symbols),
+            zero_diff_model: ArithmeticModel::new(zero_diff_symbols),
+            ic_gps_time: IntegerDecompressor::new(32, 9),
+        }
+    }
+
+    /// Returns the bits of the next GPS time
+    pub fn decompress(&mut self, decoder: &mut ArithmeticDecoder) -> i64 {
+        let code_full = if self.layered {
+            GPS_TIME_MULTI_END
+        } else {
+            GPS_TIME_MULTI_END + 1
+        };
+
+        loop {
+            let last = self.last;
+
+            if self.last_gps_time_diff[last] == 0 {
+                let mut multi = decoder.decode_symbol(&mut self.zero_diff_model);
+                // The layered item has no symbol for an unchanged time
+                if self.layered {
+                    multi += 1;
+                }
+
+                match multi {
+                    0 => {}
+                    1 => {
+                        let diff = self.ic_gps_time.decompress(decoder, 0, 0);
+                        self.last_gps_time_diff[last] = diff;
+                        self.last_gps_time[last] =
+                            self.last_gps_time[last].wrapping_add(diff as i64);
+                        self.multi_extreme_counter[last] = 0;
+                    }
+                    2 => self.decompress_full_time(decoder),
+                    _ => {
+                        self.last = (last + multi as usize - 2) & 3;
+                        continue;
+                    }
+                }
+            } else {
+                let multi = decoder.decode_symbol(&mut self.multi_model);
+
+                if multi == 1 {
+                    let diff =
+                        self.ic_gps_time
+                            .decompress(decoder, self.last_gps_time_diff[last], 1);
+                    self.last_gps_time[last] = self.last_gps_time[last].wrapping_add(diff as i64);
+                    self.multi_extreme_counter[last] = 0;
+                } else if multi < GPS_TIME_MULTI_END {
+                    let diff = self.decompress_multiplied_diff(decoder, multi as i32);
+                    self.last_gps_time[last] = self.last_gps_time[last].wrapping_add(diff as i64);
+                } else if multi == code_full {
+                    self.decompress_full_time(decoder);
+                } else if multi > code_full {
+                    self.last = (last + (multi - code_full) as usize) & 3;
+                    continue;
+                }
+            }
+
+            return self.last_gps_time[self.last];
+        }
+    }
+
+    fn decompress_multiplied_diff(&mut self, decoder: &mut ArithmeticDecoder, multi: i32) -> i32 {
+        let last = self.last;
+        let last_diff = self.last_gps_time_diff[last];
+
+        let (diff, is_extreme) = if multi == 0 {
+            (self.ic_gps_time.decompress(decoder, 0, 7), true)
+        } else if multi < GPS_TIME_MULTI {
+            let context = if multi < 10 { 2 } else { 3 };
+            let prediction = multi.wrapping_mul(last_diff);
+            (
+                self.ic_gps_time.decompress(decoder, prediction, context),
+                false,
+            )
+        } else if multi == GPS_TIME_MULTI {
+            let prediction = GPS_TIME_MULTI.wrapping_mul(last_diff);
+            (self.ic_gps_time.decompress(decoder, prediction, 4), true)
+        } else {
+            let multi = GPS_TIME_MULTI - multi;
+            if multi > GPS_TIME_MULTI_MINUS {
+                let prediction = multi.wrapping_mul(last_diff);
+                (self.ic_gps_time.decompress(decoder, prediction, 5), false)
+            } else {
+                let prediction = GPS_TIME_MULTI_MINUS.wrapping_mul(last_diff);
+                (self.ic_gps_time.decompress(decoder, prediction, 6), true)
+            }
+        };
+
+        // Differences far from the last one only replace it once they keep occurring
+        if is_extreme {
+            self.multi_extreme_counter[last] += 1;
+            if self.multi_extreme_counter[last] > 3 {
+                self.last_gps_time_diff[last] = diff;
+                self.multi_extreme_counter[last] = 0;
+            }
+        }
+
+        diff
+    }
+
+    /// Starts a new sequence with a time whose difference does not fit in 32 bits
+    fn decompress_full_time(&mut self, decoder: &mut ArithmeticDecoder) {
+        self.next = (self.next + 1) & 3;
+
+        let last_upper = (self.last_gps_time[self.last] >> 32) as i32;
+        let upper = self.ic_gps_time.decompress(decoder, last_upper, 8) as u32 as u64;
+        let lower = decoder.read_int() as u64;
+
+        self.last = self.next;
+        self.last_gps_time[self.last] = ((upper << 32) | lower) as i64;
+        self.last_gps_time_diff[self.last] = 0;
+        self.multi_extreme_counter[self.last] = 0;
+    }
+}
+
+/// Version 2 of the GPSTIME11 item
+pub struct GpsTime11Decompressor {
+    gps_time: GpsTimeDecompressor,
+}
+
+impl GpsTime11Decompressor {
+    pub fn new() -> GpsTime11Decompressor {
+        GpsTime11Decompressor {
+            gps_time: GpsTimeDecompressor::new(0, false),
+        }
+    }
+}
+
+impl PointwiseItemDecompressor for GpsTime11Decompressor {
+    fn init(&mut self, first: &[u8]) {
+        let first_gps_time = i64::from_le_bytes(first[..GPS_TIME_SIZE].try_into().unwrap());
+        self.gps_time = GpsTimeDecompressor::new(first_gps_time, false);
+    }
+
+    fn decompress(&mut self, decoder: &mut ArithmeticDecoder, item: &mut [u8]) {
+        let gps_time = self.gps_time.decompress(decoder);
+        item[..GPS_TIME_SIZE].copy_from_slice(&gps_time.to_le_bytes());
+    }
+}
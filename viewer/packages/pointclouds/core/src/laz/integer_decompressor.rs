@@ -0,0 +1,96 @@
+use super::arithmetic_decoder::{ArithmeticBitModel, ArithmeticDecoder, ArithmeticModel};
+
+/// Decodes integers stored as a correction to a prediction. The number of bits of the
+/// correction is coded first, then its value within that bit range
+#[derive(Clone)]
+pub struct IntegerDecompressor {
+    k: u32,
+    bits_high: u32,
+    corr_range: u32,
+    corr_min: i32,
+    bits_models: Vec<ArithmeticModel>,
+    corrector_bit_model: ArithmeticBitModel,
+    corrector_models: Vec<ArithmeticModel>,
+}
+
+impl IntegerDecompressor {
+    /// Decompresses values of `bits` bits, with a separate model for the number of correction
+    /// bits in each of the `contexts`
+    pub fn new(bits: u32, contexts: u32) -> IntegerDecompressor {
+        let bits_high = 8;
+
+        let (corr_bits, corr_range, corr_min) = if bits > 0 && bits < 32 {
+            (bits, 1u32 << bits, -((1u32 << (bits - 1)) as i32))
+        } else {
+            (32, 0, i32::MIN)
+        };
+
+        let bits_models = (0..contexts)
+            .map(|_| ArithmeticModel::new(corr_bits + 1))
+            .collect();
+        // Index 0 is unused, corrections with 0 bits are coded with the bit model
+        let corrector_models = (0..=corr_bits)
+            .map(|i| ArithmeticModel::new(1 << i.clamp(1, bits_high)))
+            .collect();
+
+        IntegerDecompressor {
+            k: 0,
+            bits_high,
+            corr_range,
+            corr_min,
+            bits_models,
+            corrector_bit_model: ArithmeticBitModel::new(),
+            corrector_models,
+        }
+    }
+
+    /// The number of bits of the last correction, which some fields use as context for the
+    /// fields that follow
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+
+    pub fn decompress(
+        &mut self,
+        decoder: &mut ArithmeticDecoder,
+        prediction: i32,
+        context: u32,
+    ) -> i32 {
+        let mut real = prediction.wrapping_add(self.read_corrector(decoder, context as usize));
+        if real < 0 {
+            real = real.wrapping_add(self.corr_range as i32);
+        } else if real as u32 >= self.corr_range {
+            real = real.wrapping_sub(self.corr_range as i32);
+        }
+        real
+    }
+
+    fn read_corrector(&mut self, decoder: &mut ArithmeticDecoder, context: usize) -> i32 {
+        self.k = decoder.decode_symbol(&mut self.bits_models[context]);
+
+        if self.k == 0 {
+            return decoder.decode_bit(&mut self.corrector_bit_model) as i32;
+        }
+        if self.k >= 32 {
+            return self.corr_min;
+        }
+
+        let k = self.k;
+        let mut corrector = if k <= self.bits_high {
+            decoder.decode_symbol(&mut self.corrector_models[k as usize])
+        } else {
+            let extra_bits = k - self.bits_high;
+            let high = decoder.decode_symbol(&mut self.corrector_models[k as usize]);
+            let low = decoder.read_bits(extra_bits);
+            (high << extra_bits) | low
+        } as i32;
+
+        // Corrections in [1 << (k - 1), (1 << k) - 1] are positive, the rest negative
+        if corrector >= 1 << (k - 1) {
+            corrector += 1;
+        } else {
+            corrector = corrector.wrapping_sub(((1u32 << k) - 1) as i32);
+        }
+        corrector
+    }
+}
@@ -0,0 +1,794 @@
+//! Decompression of LAZ point data, as written by LASzip. Point data formats 0 to 5 are
+//! compressed point by point (item versions 1 and 2), formats 6 to 10 in layers (item version
+//! 3). Points are decompressed into uncompressed LAS point records
+
+mod arithmetic_decoder;
+mod extra_bytes;
+mod gps_time;
+mod integer_decompressor;
+mod point10;
+mod point14;
+mod rgb;
+mod streaming_median;
+mod wavepacket;
+
+#[cfg(test)]
+pub(crate) mod test_encoder;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
+
+use arithmetic_decoder::{ArithmeticDecoder, ArithmeticModel};
+
+use crate::las::{self, LasHeader};
+
+pub const LASZIP_USER_ID: &str = "laszip encoded";
+pub const LASZIP_RECORD_ID: u16 = 22204;
+
+const POINTWISE_COMPRESSOR: u16 = 1;
+const POINTWISE_CHUNKED_COMPRESSOR: u16 = 2;
+pub const LAYERED_CHUNKED_COMPRESSOR: u16 = 3;
+const ARITHMETIC_CODER: u16 = 0;
+
+/// Chunk size of files where each chunk has its own number of points
+const VARIABLE_CHUNK_SIZE: u32 = u32::MAX;
+/// Point counts are read from the file, so the decompressed size is limited to keep a corrupt
+/// count from exhausting memory
+const MAX_DECOMPRESSED_BYTES: u64 = 1 << 31;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LazItemType {
+    Byte,
+    Point10,
+    GpsTime11,
+    Rgb12,
+    WavePacket13,
+    Point14,
+    Rgb14,
+    RgbNir14,
+    WavePacket14,
+    Byte14,
+}
+
+/// One part of a point record, compressed on its own
+#[derive(Clone, Debug, PartialEq)]
+pub struct LazItem {
+    pub item_type: LazItemType,
+    pub size: u16,
+    pub version: u16,
+}
+
+impl LazItem {
+    fn parse(bytes: &[u8]) -> Result<LazItem, String> {
+        let value_at = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+        let (type_id, size, version) = (value_at(0), value_at(2), value_at(4));
+
+        let (item_type, expected_size, supported_version) = match type_id {
+            0 => (LazItemType::Byte, size, 2),
+            6 => (LazItemType::Point10, point10::POINT10_SIZE as u16, 2),
+            7 => (LazItemType::GpsTime11, gps_time::GPS_TIME_SIZE as u16, 2),
+            8 => (LazItemType::Rgb12, rgb::RGB_SIZE as u16, 2),
+            9 => (
+                LazItemType::WavePacket13,
+                wavepacket::WAVEPACKET_SIZE as u16,
+                1,
+            ),
+            10 => (LazItemType::Point14, point14::POINT14_SIZE as u16, 3),
+            11 => (LazItemType::Rgb14, rgb::RGB_SIZE as u16, 3),
+            12 => (LazItemType::RgbNir14, rgb::RGB_NIR_SIZE as u16, 3),
+            13 => (
+                LazItemType::WavePacket14,
+                wavepacket::WAVEPACKET_SIZE as u16,
+                3,
+            ),
+            14 => (LazItemType::Byte14, size, 3),
+            _ => return Err(format!("Unsupported LAZ item type {}", type_id)),
+        };
+
+        if size != expected_size || size == 0 {
+            return Err(format!(
+                "LAZ item {:?} has size {}, expected {}",
+                item_type, size, expected_size
+            ));
+        }
+        if version != supported_version {
+            return Err(format!(
+                "Version {} of LAZ item {:?} is not supported",
+                version, item_type
+            ));
+        }
+
+        Ok(LazItem {
+            item_type,
+            size,
+            version,
+        })
+    }
+
+    fn is_layered(&self) -> bool {
+        matches!(
+            self.item_type,
+            LazItemType::Point14
+                | LazItemType::Rgb14
+                | LazItemType::RgbNir14
+                | LazItemType::WavePacket14
+                | LazItemType::Byte14
+        )
+    }
+}
+
+/// The LASzip VLR, which describes how the point data is compressed
+#[derive(Clone, Debug, PartialEq)]
+pub struct LazVlr {
+    pub compressor: u16,
+    pub chunk_size: u32,
+    pub items: Vec<LazItem>,
+}
+
+impl LazVlr {
+    pub fn parse(data: &[u8]) -> Result<LazVlr, String> {
+        if data.len() < 34 {
+            return Err(format!(
+                "LASzip VLR must be at least 34 bytes, got {}",
+                data.len()
+            ));
+        }
+
+        let compressor = u16::from_le_bytes([data[0], data[1]]);
+        let coder = u16::from_le_bytes([data[2], data[3]]);
+        let chunk_size = u32::from_le_bytes(data[12..16].try_into().unwrap());
+        let num_items = u16::from_le_bytes([data[32], data[33]]) as usize;
+
+        if !matches!(
+            compressor,
+            POINTWISE_COMPRESSOR | POINTWISE_CHUNKED_COMPRESSOR | LAYERED_CHUNKED_COMPRESSOR
+        ) {
+            return Err(format!("Unsupported LAZ compressor {}", compressor));
+        }
+        if coder != ARITHMETIC_CODER {
+            return Err(format!("Unsupported LAZ coder {}", coder));
+        }
+
+        let items = data[34..]
+            .chunks_exact(6)
+            .take(num_items)
+            .map(LazItem::parse)
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if items.len() != num_items {
+            return Err(format!("LASzip VLR is too short for {} items", num_items));
+        }
+
+        let is_layered = compressor == LAYERED_CHUNKED_COMPRESSOR;
+        let first_item = if is_layered {
+            LazItemType::Point14
+        } else {
+            LazItemType::Point10
+        };
+        if items.first().map(|item| item.item_type) != Some(first_item)
+            || items.iter().any(|item| item.is_layered() != is_layered)
+        {
+            return Err(format!(
+                "LAZ items {:?} can not be used with compressor {}",
+                items.iter().map(|item| item.item_type).collect::<Vec<_>>(),
+                compressor
+            ));
+        }
+
+        Ok(LazVlr {
+            compressor,
+            chunk_size,
+            items,
+        })
+    }
+
+    /// Finds and parses the LASzip VLR of a LAS file
+    pub fn find(bytes: &[u8], header: &LasHeader) -> Result<LazVlr, String> {
+        las::read_vlrs(bytes, header)?
+            .into_iter()
+            .find(|vlr| vlr.user_id == LASZIP_USER_ID && vlr.record_id == LASZIP_RECORD_ID)
+            .ok_or_else(|| "LAZ file has no LASzip VLR".to_string())
+            .and_then(|vlr| LazVlr::parse(vlr.data))
+    }
+
+    /// The size of an uncompressed point record
+    pub fn point_size(&self) -> usize {
+        self.items.iter().map(|item| item.size as usize).sum()
+    }
+}
+
+/// Reads bytes from the start of a slice, one part after another
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> ByteReader<'a> {
+        ByteReader { bytes, position: 0 }
+    }
+
+    pub fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position.saturating_add(length))
+            .ok_or_else(|| {
+                format!(
+                    "LAZ data ended early, expected {} bytes at {}",
+                    length, self.position
+                )
+            })?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, String> {
+        self.take(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn rest(&self) -> &'a [u8] {
+        &self.bytes[self.position.min(self.bytes.len())..]
+    }
+}
+
+/// One layer of a layered item. Layers are left out of chunks where their fields never change,
+/// and then have no decoder
+#[derive(Default)]
+pub struct LayerReader<'a> {
+    size: usize,
+    decoder: Option<ArithmeticDecoder<'a>>,
+}
+
+impl<'a> LayerReader<'a> {
+    fn read(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String> {
+        let bytes = reader.take(self.size)?;
+        self.decoder = (self.size > 0).then(|| ArithmeticDecoder::new(bytes));
+        Ok(())
+    }
+}
+
+/// Item of formats 0 to 5. The first point of each chunk is stored uncompressed, and the other
+/// points are decompressed with one decoder shared by all items
+trait PointwiseItemDecompressor {
+    fn init(&mut self, first: &[u8]);
+    fn decompress(&mut self, decoder: &mut ArithmeticDecoder, item: &mut [u8]);
+}
+
+/// Item of formats 6 to 10. After the uncompressed first point, a chunk has the sizes of all
+/// layers of all items followed by the layers themselves. `context` is the scanner channel of
+/// the point, which the POINT14 item decodes before the other items
+trait LayeredItemDecompressor<'a> {
+    fn read_layer_sizes(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String>;
+    fn read_layers(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String>;
+    fn init(&mut self, first: &[u8], context: &mut usize);
+    fn decompress(&mut self, item: &mut [u8], context: &mut usize);
+}
+
+/// Models that are only created once a context is used
+fn model_at(
+    models: &mut [Option<ArithmeticModel>],
+    index: usize,
+    symbols: u32,
+) -> &mut ArithmeticModel {
+    models[index].get_or_insert_with(|| ArithmeticModel::new(symbols))
+}
+
+fn create_pointwise_decompressor(item: &LazItem) -> Box<dyn PointwiseItemDecompressor> {
+    match item.item_type {
+        LazItemType::Point10 => Box::new(point10::Point10Decompressor::new()),
+        LazItemType::GpsTime11 => Box::new(gps_time::GpsTime11Decompressor::new()),
+        LazItemType::Rgb12 => Box::new(rgb::Rgb12Decompressor::new()),
+        LazItemType::WavePacket13 => Box::new(wavepacket::WavePacket13Decompressor::new()),
+        _ => Box::new(extra_bytes::ExtraBytesDecompressor::new(item.size as usize)),
+    }
+}
+
+fn create_layered_decompressor<'a>(item: &LazItem) -> Box<dyn LayeredItemDecompressor<'a> + 'a> {
+    match item.item_type {
+        LazItemType::Point14 => Box::new(point14::Point14Decompressor::new()),
+        LazItemType::Rgb14 => Box::new(rgb::RgbNir14Decompressor::new(false)),
+        LazItemType::RgbNir14 => Box::new(rgb::RgbNir14Decompressor::new(true)),
+        LazItemType::WavePacket14 => Box::new(wavepacket::WavePacket14Decompressor::new()),
+        _ => Box::new(extra_bytes::ExtraBytes14Decompressor::new(
+            item.size as usize,
+        )),
+    }
+}
+
+fn item_ranges(vlr: &LazVlr) -> Vec<std::ops::Range<usize>> {
+    let mut start = 0;
+    vlr.items
+        .iter()
+        .map(|item| {
+            start += item.size as usize;
+            start - item.size as usize..start
+        })
+        .collect()
+}
+
+/// Size of `num_points` uncompressed points, if it is within `MAX_DECOMPRESSED_BYTES`
+fn get_decompressed_size(num_points: u64, point_size: usize) -> Result<usize, String> {
+    num_points
+        .checked_mul(point_size as u64)
+        .filter(|&size| size <= MAX_DECOMPRESSED_BYTES)
+        .and_then(|size| usize::try_from(size).ok())
+        .ok_or_else(|| {
+            format!(
+                "{} LAZ points of {} bytes are too many to decompress",
+                num_points, point_size
+            )
+        })
+}
+
+/// Reserves without aborting when memory runs out, as the size comes from the file
+fn reserve_points(points: &mut Vec<u8>, additional: usize) -> Result<(), String> {
+    points
+        .try_reserve(additional)
+        .map_err(|_| format!("Could not allocate {} bytes for LAZ points", additional))
+}
+
+/// Decompresses one chunk holding `num_points` points into uncompressed point records
+pub fn decompress_chunk(chunk: &[u8], vlr: &LazVlr, num_points: usize) -> Result<Vec<u8>, String> {
+    let point_size = vlr.point_size();
+    let size = get_decompressed_size(num_points as u64, point_size)?;
+    let mut points = Vec::new();
+    reserve_points(&mut points, size)?;
+    points.resize(size, 0);
+    if num_points == 0 {
+        return Ok(points);
+    }
+
+    let ranges = item_ranges(vlr);
+    let mut reader = ByteReader::new(chunk);
+    let (first, rest) = points.split_at_mut(point_size);
+    first.copy_from_slice(reader.take(point_size)?);
+
+    if vlr.compressor == LAYERED_CHUNKED_COMPRESSOR {
+        let mut decompressors: Vec<_> = vlr.items.iter().map(create_layered_decompressor).collect();
+
+        if num_points > 1 {
+            // The number of points in the chunk, which is also known from the chunk table
+            reader.read_u32()?;
+            for decompressor in &mut decompressors {
+                decompressor.read_layer_sizes(&mut reader)?;
+            }
+            for decompressor in &mut decompressors {
+                decompressor.read_layers(&mut reader)?;
+            }
+        }
+
+        let mut context = 0;
+        for (decompressor, range) in decompressors.iter_mut().zip(&ranges) {
+            decompressor.init(&first[range.clone()], &mut context);
+        }
+        for point in rest.chunks_exact_mut(point_size) {
+            for (decompressor, range) in decompressors.iter_mut().zip(&ranges) {
+                decompressor.decompress(&mut point[range.clone()], &mut context);
+            }
+        }
+    } else {
+        let mut decompressors: Vec<_> = vlr
+            .items
+            .iter()
+            .map(create_pointwise_decompressor)
+            .collect();
+
+        for (decompressor, range) in decompressors.iter_mut().zip(&ranges) {
+            decompressor.init(&first[range.clone()]);
+        }
+        let mut decoder = ArithmeticDecoder::new(reader.rest());
+        for point in rest.chunks_exact_mut(point_size) {
+            for (decompressor, range) in decompressors.iter_mut().zip(&ranges) {
+                decompressor.decompress(&mut decoder, &mut point[range.clone()]);
+            }
+        }
+    }
+
+    Ok(points)
+}
+
+/// Position and number of points of a chunk within the file
+#[derive(Clone, Debug, PartialEq)]
+pub struct LazChunk {
+    pub offset: usize,
+    pub byte_size: usize,
+    pub num_points: usize,
+}
+
+fn read_i64(bytes: &[u8], offset: usize) -> Result<i64, String> {
+    bytes
+        .get(offset..offset + 8)
+        .map(|slice| i64::from_le_bytes(slice.try_into().unwrap()))
+        .ok_or_else(|| format!("LAZ data ended early, expected 8 bytes at {}", offset))
+}
+
+/// Reads the chunk table of a chunked LAZ file. It is stored after the point data, and the
+/// point data starts with its offset
+pub fn read_chunk_table(
+    bytes: &[u8],
+    header: &LasHeader,
+    vlr: &LazVlr,
+) -> Result<Vec<LazChunk>, String> {
+    let point_data_start = header.offset_to_point_data as usize;
+    let mut table_offset = read_i64(bytes, point_data_start)?;
+    // Writers that can not seek back put the offset at the end of the file instead
+    if table_offset == -1 {
+        table_offset = read_i64(bytes, bytes.len().saturating_sub(8))?;
+    }
+
+    let table_offset = usize::try_from(table_offset)
+        .ok()
+        .filter(|&offset| offset <= bytes.len())
+        .ok_or_else(|| format!("Invalid LAZ chunk table offset {}", table_offset))?;
+    let mut reader = ByteReader::new(&bytes[table_offset..]);
+    let version = reader.read_u32()?;
+    let number_of_chunks = reader.read_u32()? as usize;
+
+    if version != 0 {
+        return Err(format!("Unsupported LAZ chunk table version {}", version));
+    }
+
+    let mut decoder = ArithmeticDecoder::new(reader.rest());
+    let mut decompressor = integer_decompressor::IntegerDecompressor::new(32, 2);
+
+    let mut chunks: Vec<LazChunk> = Vec::with_capacity(number_of_chunks.min(bytes.len()));
+    let mut offset = point_data_start + 8;
+    let mut remaining_points = usize::try_from(header.number_of_points).unwrap_or(usize::MAX);
+
+    for _ in 0..number_of_chunks {
+        let last = chunks.last();
+        let num_points = if vlr.chunk_size == VARIABLE_CHUNK_SIZE {
+            let last_num_points = last.map_or(0, |chunk| chunk.num_points as i32);
+            decompressor.decompress(&mut decoder, last_num_points, 0) as u32 as usize
+        } else {
+            remaining_points.min(vlr.chunk_size as usize)
+        };
+        let last_byte_size = last.map_or(0, |chunk| chunk.byte_size as i32);
+        let byte_size = decompressor.decompress(&mut decoder, last_byte_size, 1) as u32 as usize;
+
+        if offset.saturating_add(byte_size) > table_offset {
+            return Err("LAZ chunk table points past the point data".to_string());
+        }
+
+        chunks.push(LazChunk {
+            offset,
+            byte_size,
+            num_points,
+        });
+        offset += byte_size;
+        remaining_points = remaining_points.saturating_sub(num_points);
+    }
+
+    Ok(chunks)
+}
+
+/// Decompresses all points of a LAZ file into uncompressed point records
+pub fn decompress_points(
+    bytes: &[u8],
+    header: &LasHeader,
+    vlr: &LazVlr,
+) -> Result<Vec<u8>, String> {
+    if vlr.point_size() != header.point_record_length as usize {
+        return Err(format!(
+            "LAZ items make {} byte points, but the header has {} byte points",
+            vlr.point_size(),
+            header.point_record_length
+        ));
+    }
+
+    let size = get_decompressed_size(header.number_of_points, vlr.point_size())?;
+    let point_data_start = header.offset_to_point_data as usize;
+    let chunks_hold_wrong_count = |num_points: usize| {
+        format!(
+            "LAZ chunks hold {} points, but the header has {}",
+            num_points, header.number_of_points
+        )
+    };
+
+    let points = if vlr.compressor == POINTWISE_COMPRESSOR {
+        let chunk = bytes
+            .get(point_data_start..)
+            .ok_or("LAZ file ended before the point data")?;
+        decompress_chunk(chunk, vlr, size / vlr.point_size())?
+    } else {
+        let mut points = Vec::new();
+        for chunk in read_chunk_table(bytes, header, vlr)? {
+            // Checked before decompressing, so that a corrupt chunk can not allocate more than the
+            // header allows
+            let remaining_points = (size - points.len()) / vlr.point_size();
+            if chunk.num_points > remaining_points {
+                return Err(chunks_hold_wrong_count(
+                    points.len() / vlr.point_size() + chunk.num_points,
+                ));
+            }
+
+            let chunk_bytes = &bytes[chunk.offset..chunk.offset + chunk.byte_size];
+            let chunk_points = decompress_chunk(chunk_bytes, vlr, chunk.num_points)?;
+            reserve_points(&mut points, chunk_points.len())?;
+            points.extend(chunk_points);
+        }
+        points
+    };
+
+    if points.len() != size {
+        return Err(chunks_hold_wrong_count(points.len() / vlr.point_size()));
+    }
+
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::point10::Point10;
+    use super::point14::Point14;
+    use super::test_encoder::{create_laz_file, items_for_format};
+    use super::test_fixtures::{assert_points_match_reference, read_fixture};
+    use super::wavepacket::WavePacket;
+    use super::{LazItemType, LazVlr};
+    use crate::las;
+
+    /// Point records of a format, with fields changing like they do along scan lines and
+    /// occasional jumps
+    fn create_points(format: u8, extra_bytes: u16, count: usize, seed: u64) -> Vec<u8> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let items = items_for_format(format, extra_bytes);
+        let mut point14 = Point14::default();
+        let mut gps_time = 1.5e8f64;
+        let mut rgb_nir = [1000u16, 2000, 3000, 4000];
+        let mut wave_packet = WavePacket {
+            descriptor_index: 1,
+            packet_size: 256,
+            ..WavePacket::default()
+        };
+        let mut extra = vec![0u8; extra_bytes as usize];
+
+        let mut points = vec![];
+        for _ in 0..count {
+            let step = |rng: &mut ChaCha8Rng, value: i32| {
+                if rng.gen_ratio(1, 50) {
+                    rng.gen()
+                } else {
+                    value.wrapping_add(rng.gen_range(-50..50))
+                }
+            };
+            let x = step(&mut rng, point14.x);
+            let y = step(&mut rng, point14.y);
+            let z = step(&mut rng, point14.z);
+            let returns = rng.gen_range(1..=4u8);
+            let return_number = rng.gen_range(0..=returns);
+
+            point14 = Point14 {
+                x,
+                y,
+                z,
+                intensity: if rng.gen_bool(0.8) {
+                    rng.gen_range(0..1000)
+                } else {
+                    point14.intensity
+                },
+                return_number,
+                number_of_returns: returns,
+                scanner_channel: if rng.gen_ratio(1, 20) {
+                    rng.gen_range(0..4)
+                } else {
+                    point14.scanner_channel
+                },
+                classification: if rng.gen_ratio(1, 10) {
+                    rng.gen()
+                } else {
+                    point14.classification
+                },
+                classification_flags: rng.gen_range(0..16) * rng.gen_ratio(1, 20) as u8,
+                scan_direction_flag: rng.gen_range(0..2),
+                edge_of_flight_line: rng.gen_ratio(1, 30) as u8,
+                user_data: point14.user_data.wrapping_add(rng.gen_ratio(1, 30) as u8),
+                scan_angle: if rng.gen_ratio(1, 5) {
+                    rng.gen()
+                } else {
+                    point14.scan_angle
+                },
+                point_source_id: point14.point_source_id + rng.gen_ratio(1, 100) as u16,
+                gps_time: 0,
+            };
+            let point10 = Point10 {
+                x,
+                y,
+                z,
+                intensity: point14.intensity,
+                bit_fields: (return_number & 7)
+                    | (returns & 7) << 3
+                    | point14.scan_direction_flag << 6
+                    | point14.edge_of_flight_line << 7,
+                classification: point14.classification,
+                scan_angle_rank: point14.scan_angle as u8,
+                user_data: point14.user_data,
+                point_source_id: point14.point_source_id,
+            };
+
+            gps_time = match rng.gen_range(0..100) {
+                0..=29 => gps_time,
+                30..=89 => gps_time + rng.gen_range(1..4) as f64 * 1e-5,
+                90..=94 => gps_time - 1e-5 * rng.gen_range(1..100) as f64,
+                _ => rng.gen_range(0.0..2e8),
+            };
+            point14.gps_time = gps_time.to_bits() as i64;
+
+            if rng.gen_ratio(1, 3) {
+                let gray = rng.gen();
+                rgb_nir = if rng.gen_bool(0.5) {
+                    [gray, gray, gray, rgb_nir[3]]
+                } else {
+                    rgb_nir.map(|value| value.wrapping_add(rng.gen_range(0..300)))
+                };
+            }
+
+            wave_packet.offset = match rng.gen_range(0..10) {
+                0 => wave_packet.offset,
+                1 => rng.gen(),
+                2 => wave_packet.offset.wrapping_add(rng.gen_range(0..1000)),
+                _ => wave_packet.offset + wave_packet.packet_size as u64,
+            };
+            if rng.gen_ratio(1, 10) {
+                wave_packet.packet_size = rng.gen_range(0..1000);
+                wave_packet.return_point = rng.gen();
+                wave_packet.xyz = rng.gen();
+            }
+
+            for byte in &mut extra {
+                if rng.gen_ratio(1, 4) {
+                    *byte = rng.gen();
+                }
+            }
+
+            for item in &items {
+                let mut bytes = vec![0u8; item.size as usize];
+                match item.item_type {
+                    LazItemType::Point10 => point10.write(&mut bytes),
+                    LazItemType::Point14 => point14.write(&mut bytes),
+                    LazItemType::GpsTime11 => bytes.copy_from_slice(&gps_time.to_le_bytes()),
+                    LazItemType::Rgb12 | LazItemType::Rgb14 | LazItemType::RgbNir14 => {
+                        for (i, pair) in bytes.chunks_exact_mut(2).enumerate() {
+                            pair.copy_from_slice(&rgb_nir[i].to_le_bytes());
+                        }
+                    }
+                    LazItemType::WavePacket13 | LazItemType::WavePacket14 => {
+                        wave_packet.write(&mut bytes)
+                    }
+                    LazItemType::Byte | LazItemType::Byte14 => bytes.copy_from_slice(&extra),
+                }
+                points.extend(bytes);
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn all_point_data_formats_are_decompressed() {
+        for format in 0..=10 {
+            for extra_bytes in [0, 3] {
+                let points = create_points(format, extra_bytes, 1000, format as u64);
+                let bytes = create_laz_file(format, extra_bytes, &points, &[400, 400, 400]);
+
+                let (header, records) = las::read_point_records(&bytes).unwrap();
+
+                assert_eq!(header.number_of_points, 1000);
+                assert!(
+                    records[..] == points[..],
+                    "format {} with {} extra bytes",
+                    format,
+                    extra_bytes
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs the fixtures written by testdata/create_fixtures.sh"]
+    fn files_written_by_laszip_are_decompressed() {
+        let names = (0..=10)
+            .map(|format| format!("format-{}", format))
+            .chain(["format-7-extra-bytes".to_string()]);
+        for name in names {
+            let bytes = read_fixture(&format!("{}.laz", name));
+
+            let (header, records) = las::read_point_records(&bytes).unwrap();
+
+            assert_points_match_reference(&header, &records, &format!("{}.csv", name), true);
+        }
+    }
+
+    #[test]
+    fn chunks_with_their_own_number_of_points_are_decompressed() {
+        for format in [3, 8] {
+            let points = create_points(format, 0, 500, 1);
+            let bytes = create_laz_file(format, 0, &points, &[1, 200, 2, 297]);
+
+            let (_, records) = las::read_point_records(&bytes).unwrap();
+
+            assert!(records[..] == points[..], "format {}", format);
+        }
+    }
+
+    #[test]
+    fn layers_of_unchanged_fields_are_left_out() {
+        let first = create_points(10, 2, 1, 2);
+        let points = first.repeat(100);
+        let bytes = create_laz_file(10, 2, &points, &[100]);
+
+        let (_, records) = las::read_point_records(&bytes).unwrap();
+
+        assert!(records[..] == points[..]);
+        // Only the uncompressed first point and the x and y layer hold data
+        assert!(bytes.len() < 1000);
+    }
+
+    #[test]
+    fn positions_are_read_from_laz_files() {
+        let points = create_points(6, 0, 10, 3);
+        let bytes = create_laz_file(6, 0, &points, &[10]);
+
+        let (_, positions) = las::read_positions(&bytes).unwrap();
+
+        for (position, record) in positions.iter().zip(points.chunks_exact(30)) {
+            let point = Point14::read(record);
+            assert_eq!(position.x, point.x as f64 * 0.01);
+            assert_eq!(position.z, point.z as f64 * 0.01);
+        }
+    }
+
+    #[test]
+    fn unsupported_compression_is_rejected() {
+        let points = create_points(1, 0, 10, 4);
+        let bytes = create_laz_file(1, 0, &points, &[10]);
+        let header = las::LasHeader::parse(&bytes).unwrap();
+        let vlr = las::read_vlrs(&bytes, &header).unwrap().remove(0);
+        assert!(LazVlr::parse(vlr.data).is_ok());
+
+        let with = |offset: usize, value: u16| {
+            let mut data = vlr.data.to_vec();
+            data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+            LazVlr::parse(&data)
+        };
+        // Compressor, coder, item type and item version
+        assert!(with(0, 0).is_err());
+        assert!(with(0, 3).is_err());
+        assert!(with(2, 1).is_err());
+        assert!(with(34, 10).is_err());
+        assert!(with(38, 1).is_err());
+        assert!(LazVlr::parse(&vlr.data[..36]).is_err());
+
+        let mut truncated = bytes.clone();
+        truncated.truncate(bytes.len() - 20);
+        assert!(las::read_point_records(&truncated).is_err());
+
+        let mut wrong_length = bytes;
+        wrong_length[105] += 1;
+        assert!(las::read_point_records(&wrong_length).is_err());
+    }
+
+    #[test]
+    fn corrupt_point_counts_are_rejected() {
+        let points = create_points(7, 0, 10, 5);
+        let bytes = create_laz_file(7, 0, &points, &[10]);
+        let header = las::LasHeader::parse(&bytes).unwrap();
+        let vlr = LazVlr::find(&bytes, &header).unwrap();
+
+        assert!(super::decompress_chunk(&bytes, &vlr, usize::MAX).is_err());
+        assert!(super::decompress_chunk(&bytes, &vlr, 1 << 40).is_err());
+
+        for number_of_points in [u64::MAX, 1 << 40, 11] {
+            let mut wrong_count = bytes.clone();
+            wrong_count[247..255].copy_from_slice(&number_of_points.to_le_bytes());
+            assert!(las::read_point_records(&wrong_count).is_err());
+        }
+
+        // The second chunk holds more points than the header has left
+        let mut chunk_past_header = create_laz_file(7, 0, &points, &[3, 7]);
+        chunk_past_header[247..255].copy_from_slice(&5u64.to_le_bytes());
+        let error = las::read_point_records(&chunk_past_header).unwrap_err();
+        assert!(error.contains("hold 10 points"), "{}", error);
+    }
+}
@@ -0,0 +1,209 @@
+//! The core fields of point data formats 0 to 5, as compressed by version 2 of the POINT10 item
+
+use super::arithmetic_decoder::{ArithmeticDecoder, ArithmeticModel};
+use super::integer_decompressor::IntegerDecompressor;
+use super::streaming_median::StreamingMedian5;
+use super::{model_at, PointwiseItemDecompressor};
+
+pub const POINT10_SIZE: usize = 20;
+
+/// Context for the x and y differences, indexed by number of returns and return number
+pub const NUMBER_RETURN_MAP: [[u8; 8]; 8] = [
+    [15, 14, 13, 12, 11, 10, 9, 8],
+    [14, 0, 1, 3, 6, 10, 10, 9],
+    [13, 1, 2, 4, 7, 11, 11, 10],
+    [12, 3, 4, 5, 8, 12, 12, 11],
+    [11, 6, 7, 8, 9, 13, 13, 12],
+    [10, 10, 11, 12, 13, 14, 14, 13],
+    [9, 10, 11, 12, 13, 14, 15, 14],
+    [8, 9, 10, 11, 12, 13, 14, 15],
+];
+
+/// Context for the height, indexed by number of returns and return number
+pub const NUMBER_RETURN_LEVEL: [[u8; 8]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7],
+    [1, 0, 1, 2, 3, 4, 5, 6],
+    [2, 1, 0, 1, 2, 3, 4, 5],
+    [3, 2, 1, 0, 1, 2, 3, 4],
+    [4, 3, 2, 1, 0, 1, 2, 3],
+    [5, 4, 3, 2, 1, 0, 1, 2],
+    [6, 5, 4, 3, 2, 1, 0, 1],
+    [7, 6, 5, 4, 3, 2, 1, 0],
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point10 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub intensity: u16,
+    /// Return number, number of returns, scan direction and edge of flight line
+    pub bit_fields: u8,
+    pub classification: u8,
+    pub scan_angle_rank: u8,
+    pub user_data: u8,
+    pub point_source_id: u16,
+}
+
+impl Point10 {
+    pub fn read(bytes: &[u8]) -> Point10 {
+        let i32_at =
+            |offset: usize| i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        Point10 {
+            x: i32_at(0),
+            y: i32_at(4),
+            z: i32_at(8),
+            intensity: u16::from_le_bytes([bytes[12], bytes[13]]),
+            bit_fields: bytes[14],
+            classification: bytes[15],
+            scan_angle_rank: bytes[16],
+            user_data: bytes[17],
+            point_source_id: u16::from_le_bytes([bytes[18], bytes[19]]),
+        }
+    }
+
+    pub fn write(&self, bytes: &mut [u8]) {
+        bytes[0..4].copy_from_slice(&self.x.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.y.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.z.to_le_bytes());
+        bytes[12..14].copy_from_slice(&self.intensity.to_le_bytes());
+        bytes[14] = self.bit_fields;
+        bytes[15] = self.classification;
+        bytes[16] = self.scan_angle_rank;
+        bytes[17] = self.user_data;
+        bytes[18..20].copy_from_slice(&self.point_source_id.to_le_bytes());
+    }
+
+    pub fn return_number(&self) -> usize {
+        (self.bit_fields & 0x7) as usize
+    }
+
+    pub fn number_of_returns(&self) -> usize {
+        ((self.bit_fields >> 3) & 0x7) as usize
+    }
+
+    pub fn scan_direction_flag(&self) -> usize {
+        ((self.bit_fields >> 6) & 0x1) as usize
+    }
+}
+
+pub struct Point10Decompressor {
+    last: Point10,
+    last_intensity: [u16; 16],
+    last_x_diff_median5: [StreamingMedian5; 16],
+    last_y_diff_median5: [StreamingMedian5; 16],
+    last_height: [i32; 8],
+    changed_values_model: ArithmeticModel,
+    scan_angle_rank_models: [ArithmeticModel; 2],
+    bit_byte_models: Vec<Option<ArithmeticModel>>,
+    classification_models: Vec<Option<ArithmeticModel>>,
+    user_data_models: Vec<Option<ArithmeticModel>>,
+    ic_intensity: IntegerDecompressor,
+    ic_point_source_id: IntegerDecompressor,
+    ic_dx: IntegerDecompressor,
+    ic_dy: IntegerDecompressor,
+    ic_z: IntegerDecompressor,
+}
+
+impl Point10Decompressor {
+    pub fn new() -> Point10Decompressor {
+        Point10Decompressor {
+            last: Point10::default(),
+            last_intensity: [0; 16],
+            last_x_diff_median5: [StreamingMedian5::new(); 16],
+            last_y_diff_median5: [StreamingMedian5::new(); 16],
+            last_height: [0; 8],
+            changed_values_model: ArithmeticModel::new(64),
+            scan_angle_rank_models: [ArithmeticModel::new(256), ArithmeticModel::new(256)],
+            bit_byte_models: vec![None; 256],
+            classification_models: vec![None; 256],
+            user_data_models: vec![None; 256],
+            ic_intensity: IntegerDecompressor::new(16, 4),
+            ic_point_source_id: IntegerDecompressor::new(16, 1),
+            ic_dx: IntegerDecompressor::new(32, 2),
+            ic_dy: IntegerDecompressor::new(32, 22),
+            ic_z: IntegerDecompressor::new(32, 20),
+        }
+    }
+}
+
+impl PointwiseItemDecompressor for Point10Decompressor {
+    fn init(&mut self, first: &[u8]) {
+        self.last = Point10::read(first);
+        // The intensity of the first point is not used as prediction
+        self.last.intensity = 0;
+    }
+
+    fn decompress(&mut self, decoder: &mut ArithmeticDecoder, item: &mut [u8]) {
+        let last = &mut self.last;
+        let changed_values = decoder.decode_symbol(&mut self.changed_values_model);
+
+        if changed_values & (1 << 5) != 0 {
+            let model = model_at(&mut self.bit_byte_models, last.bit_fields as usize, 256);
+            last.bit_fields = decoder.decode_symbol(model) as u8;
+        }
+
+        let r = last.return_number();
+        let n = last.number_of_returns();
+        let m = NUMBER_RETURN_MAP[n][r] as usize;
+        let l = NUMBER_RETURN_LEVEL[n][r] as usize;
+
+        if changed_values & (1 << 4) != 0 {
+            last.intensity = self.ic_intensity.decompress(
+                decoder,
+                self.last_intensity[m] as i32,
+                m.min(3) as u32,
+            ) as u16;
+            self.last_intensity[m] = last.intensity;
+        } else {
+            last.intensity = self.last_intensity[m];
+        }
+
+        if changed_values & (1 << 3) != 0 {
+            let model = model_at(
+                &mut self.classification_models,
+                last.classification as usize,
+                256,
+            );
+            last.classification = decoder.decode_symbol(model) as u8;
+        }
+
+        if changed_values & (1 << 2) != 0 {
+            let model = &mut self.scan_angle_rank_models[last.scan_direction_flag()];
+            let difference = decoder.decode_symbol(model) as u8;
+            last.scan_angle_rank = last.scan_angle_rank.wrapping_add(difference);
+        }
+
+        if changed_values & (1 << 1) != 0 {
+            let model = model_at(&mut self.user_data_models, last.user_data as usize, 256);
+            last.user_data = decoder.decode_symbol(model) as u8;
+        }
+
+        if changed_values & 1 != 0 {
+            last.point_source_id =
+                self.ic_point_source_id
+                    .decompress(decoder, last.point_source_id as i32, 0) as u16;
+        }
+
+        let single_return = (n == 1) as u32;
+
+        let median = self.last_x_diff_median5[m].get();
+        let diff = self.ic_dx.decompress(decoder, median, single_return);
+        last.x = last.x.wrapping_add(diff);
+        self.last_x_diff_median5[m].add(diff);
+
+        let median = self.last_y_diff_median5[m].get();
+        let k_bits = self.ic_dx.k();
+        let context = single_return + if k_bits < 20 { k_bits & !1 } else { 20 };
+        let diff = self.ic_dy.decompress(decoder, median, context);
+        last.y = last.y.wrapping_add(diff);
+        self.last_y_diff_median5[m].add(diff);
+
+        let k_bits = (self.ic_dx.k() + self.ic_dy.k()) / 2;
+        let context = single_return + if k_bits < 18 { k_bits & !1 } else { 18 };
+        last.z = self.ic_z.decompress(decoder, self.last_height[l], context);
+        self.last_height[l] = last.z;
+
+        last.write(item);
+    }
+}
@@ -0,0 +1,371 @@
+//! The core fields of point data formats 6 to 10, as compressed by version 3 of the POINT14
+//! item. The fields are split into layers that are coded separately, and the state is kept
+//! separately for each of the four scanner channels
+
+use super::arithmetic_decoder::{ArithmeticDecoder, ArithmeticModel};
+use super::gps_time::GpsTimeDecompressor;
+use super::integer_decompressor::IntegerDecompressor;
+use super::streaming_median::StreamingMedian5;
+use super::{model_at, ByteReader, LayerReader, LayeredItemDecompressor};
+
+pub const POINT14_SIZE: usize = 30;
+
+/// Context for the x and y differences, indexed by number of returns and return number. Only
+/// six cases are told apart: single return, first and last of two, and first, intermediate
+/// and last of more
+pub const NUMBER_RETURN_MAP_6CTX: [[u8; 16]; 16] = [
+    [0, 1, 2, 3, 4, 5, 3, 4, 4, 5, 5, 5, 5, 5, 5, 5],
+    [1, 0, 1, 3, 4, 5, 3, 4, 4, 5, 5, 5, 5, 5, 5, 5],
+    [2, 1, 2, 4, 5, 3, 4, 4, 5, 5, 5, 5, 5, 5, 5, 5],
+    [3, 3, 4, 5, 4, 5, 4, 4, 4, 5, 5, 5, 5, 5, 5, 5],
+    [4, 3, 4, 4, 5, 4, 4, 5, 5, 4, 5, 5, 5, 5, 5, 5],
+    [5, 3, 4, 4, 4, 5, 4, 4, 5, 5, 4, 5, 5, 5, 5, 5],
+    [3, 3, 4, 4, 4, 4, 5, 4, 4, 5, 5, 4, 5, 5, 5, 5],
+    [4, 3, 4, 4, 4, 4, 4, 5, 4, 4, 5, 5, 4, 5, 5, 5],
+    [4, 3, 4, 4, 4, 4, 4, 4, 5, 4, 4, 5, 5, 4, 5, 5],
+    [5, 3, 4, 4, 4, 4, 4, 4, 4, 5, 4, 4, 5, 5, 4, 5],
+    [5, 3, 4, 4, 4, 4, 4, 4, 4, 4, 5, 4, 4, 5, 5, 4],
+    [5, 3, 4, 4, 4, 4, 4, 4, 4, 4, 4, 5, 5, 4, 5, 5],
+    [5, 3, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 5, 5, 4, 5],
+    [5, 3, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 5, 5, 4],
+    [5, 3, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 5, 5],
+    [5, 3, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 5],
+];
+
+/// Context for the height, the distance between return number and number of returns
+pub fn number_return_level_8ctx(n: usize, r: usize) -> usize {
+    n.abs_diff(r).min(7)
+}
+
+const CHANNEL_RETURNS_XY_LAYER: usize = 0;
+const Z_LAYER: usize = 1;
+const CLASSIFICATION_LAYER: usize = 2;
+const FLAGS_LAYER: usize = 3;
+const INTENSITY_LAYER: usize = 4;
+const SCAN_ANGLE_LAYER: usize = 5;
+const USER_DATA_LAYER: usize = 6;
+const POINT_SOURCE_LAYER: usize = 7;
+const GPS_TIME_LAYER: usize = 8;
+const LAYER_COUNT: usize = 9;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point14 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub intensity: u16,
+    pub return_number: u8,
+    pub number_of_returns: u8,
+    pub classification_flags: u8,
+    pub scanner_channel: u8,
+    pub scan_direction_flag: u8,
+    pub edge_of_flight_line: u8,
+    pub classification: u8,
+    pub user_data: u8,
+    pub scan_angle: i16,
+    pub point_source_id: u16,
+    /// The bits of the GPS time
+    pub gps_time: i64,
+}
+
+impl Point14 {
+    pub fn read(bytes: &[u8]) -> Point14 {
+        let i32_at =
+            |offset: usize| i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        Point14 {
+            x: i32_at(0),
+            y: i32_at(4),
+            z: i32_at(8),
+            intensity: u16::from_le_bytes([bytes[12], bytes[13]]),
+            return_number: bytes[14] & 0xF,
+            number_of_returns: bytes[14] >> 4,
+            classification_flags: bytes[15] & 0xF,
+            scanner_channel: (bytes[15] >> 4) & 0x3,
+            scan_direction_flag: (bytes[15] >> 6) & 0x1,
+            edge_of_flight_line: bytes[15] >> 7,
+            classification: bytes[16],
+            user_data: bytes[17],
+            scan_angle: i16::from_le_bytes([bytes[18], bytes[19]]),
+            point_source_id: u16::from_le_bytes([bytes[20], bytes[21]]),
+            gps_time: i64::from_le_bytes(bytes[22..30].try_into().unwrap()),
+        }
+    }
+
+    pub fn write(&self, bytes: &mut [u8]) {
+        bytes[0..4].copy_from_slice(&self.x.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.y.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.z.to_le_bytes());
+        bytes[12..14].copy_from_slice(&self.intensity.to_le_bytes());
+        bytes[14] = self.return_number | (self.number_of_returns << 4);
+        bytes[15] = self.classification_flags
+            | (self.scanner_channel << 4)
+            | (self.scan_direction_flag << 6)
+            | (self.edge_of_flight_line << 7);
+        bytes[16] = self.classification;
+        bytes[17] = self.user_data;
+        bytes[18..20].copy_from_slice(&self.scan_angle.to_le_bytes());
+        bytes[20..22].copy_from_slice(&self.point_source_id.to_le_bytes());
+        bytes[22..30].copy_from_slice(&self.gps_time.to_le_bytes());
+    }
+}
+
+struct Point14Context {
+    last: Point14,
+    last_gps_time_change: bool,
+    last_intensity: [u16; 8],
+    last_x_diff_median5: [StreamingMedian5; 12],
+    last_y_diff_median5: [StreamingMedian5; 12],
+    last_z: [i32; 8],
+    changed_values_models: Vec<ArithmeticModel>,
+    scanner_channel_model: ArithmeticModel,
+    number_of_returns_models: Vec<Option<ArithmeticModel>>,
+    return_number_gps_same_model: ArithmeticModel,
+    return_number_models: Vec<Option<ArithmeticModel>>,
+    classification_models: Vec<Option<ArithmeticModel>>,
+    flags_models: Vec<Option<ArithmeticModel>>,
+    user_data_models: Vec<Option<ArithmeticModel>>,
+    ic_dx: IntegerDecompressor,
+    ic_dy: IntegerDecompressor,
+    ic_z: IntegerDecompressor,
+    ic_intensity: IntegerDecompressor,
+    ic_scan_angle: IntegerDecompressor,
+    ic_point_source_id: IntegerDecompressor,
+    gps_time: GpsTimeDecompressor,
+}
+
+impl Point14Context {
+    fn new(last: &Point14) -> Point14Context {
+        Point14Context {
+            last: *last,
+            last_gps_time_change: false,
+            last_intensity: [last.intensity; 8],
+            last_x_diff_median5: [StreamingMedian5::new(); 12],
+            last_y_diff_median5: [StreamingMedian5::new(); 12],
+            last_z: [last.z; 8],
+            changed_values_models: vec![ArithmeticModel::new(128); 8],
+            scanner_channel_model: ArithmeticModel::new(3),
+            number_of_returns_models: vec![None; 16],
+            return_number_gps_same_model: ArithmeticModel::new(13),
+            return_number_models: vec![None; 16],
+            classification_models: vec![None; 64],
+            flags_models: vec![None; 64],
+            user_data_models: vec![None; 64],
+            ic_dx: IntegerDecompressor::new(32, 2),
+            ic_dy: IntegerDecompressor::new(32, 22),
+            ic_z: IntegerDecompressor::new(32, 20),
+            ic_intensity: IntegerDecompressor::new(16, 4),
+            ic_scan_angle: IntegerDecompressor::new(16, 2),
+            ic_point_source_id: IntegerDecompressor::new(16, 1),
+            gps_time: GpsTimeDecompressor::new(last.gps_time, true),
+        }
+    }
+}
+
+/// Version 3 of the POINT14 item. The scanner channel of each point is the context for all
+/// other items of the point
+pub struct Point14Decompressor<'a> {
+    layers: [LayerReader<'a>; LAYER_COUNT],
+    contexts: [Option<Box<Point14Context>>; 4],
+    current_context: usize,
+}
+
+impl<'a> Point14Decompressor<'a> {
+    pub fn new() -> Point14Decompressor<'a> {
+        Point14Decompressor {
+            layers: Default::default(),
+            contexts: Default::default(),
+            current_context: 0,
+        }
+    }
+
+    /// Decodes which fields changed and switches to the scanner channel of the point
+    fn decompress_changed_values(&mut self, xy_decoder: &mut ArithmeticDecoder) -> u32 {
+        let state = self.contexts[self.current_context].as_mut().unwrap();
+
+        // Whether the last point was a first and/or last return, and had a new GPS time
+        let last = &state.last;
+        let lpr = (last.return_number == 1) as usize
+            + 2 * (last.return_number >= last.number_of_returns) as usize
+            + 4 * state.last_gps_time_change as usize;
+
+        let changed_values = xy_decoder.decode_symbol(&mut state.changed_values_models[lpr]);
+
+        if changed_values & (1 << 6) != 0 {
+            let diff = xy_decoder.decode_symbol(&mut state.scanner_channel_model) as usize;
+            let scanner_channel = (self.current_context + diff + 1) % 4;
+            let last = state.last;
+
+            // New channels start from the last point of the current channel
+            if self.contexts[scanner_channel].is_none() {
+                self.contexts[scanner_channel] = Some(Box::new(Point14Context::new(&last)));
+            }
+            self.current_context = scanner_channel;
+            self.contexts[scanner_channel]
+                .as_mut()
+                .unwrap()
+                .last
+                .scanner_channel = scanner_channel as u8;
+        }
+
+        changed_values
+    }
+}
+
+impl<'a> LayeredItemDecompressor<'a> for Point14Decompressor<'a> {
+    fn read_layer_sizes(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String> {
+        for layer in &mut self.layers {
+            layer.size = reader.read_u32()? as usize;
+        }
+        Ok(())
+    }
+
+    fn read_layers(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String> {
+        self.layers
+            .iter_mut()
+            .try_for_each(|layer| layer.read(reader))
+    }
+
+    fn init(&mut self, first: &[u8], context: &mut usize) {
+        let point = Point14::read(first);
+        self.current_context = point.scanner_channel as usize;
+        self.contexts = Default::default();
+        self.contexts[self.current_context] = Some(Box::new(Point14Context::new(&point)));
+        *context = self.current_context;
+    }
+
+    fn decompress(&mut self, item: &mut [u8], context: &mut usize) {
+        let mut layers = std::mem::take(&mut self.layers);
+        // The x and y layer is only left out of chunks with a single point
+        let xy_decoder = layers[CHANNEL_RETURNS_XY_LAYER]
+            .decoder
+            .get_or_insert_with(|| ArithmeticDecoder::new(&[]));
+
+        let changed_values = self.decompress_changed_values(xy_decoder);
+        *context = self.current_context;
+        let state = self.contexts[self.current_context].as_mut().unwrap();
+        let last = &mut state.last;
+
+        let point_source_change = changed_values & (1 << 5) != 0;
+        let gps_time_change = changed_values & (1 << 4) != 0;
+        let scan_angle_change = changed_values & (1 << 3) != 0;
+
+        let last_n = last.number_of_returns as usize;
+        let last_r = last.return_number as usize;
+
+        let n = if changed_values & (1 << 2) != 0 {
+            let model = model_at(&mut state.number_of_returns_models, last_n, 16);
+            xy_decoder.decode_symbol(model) as usize
+        } else {
+            last_n
+        };
+
+        let r = match changed_values & 3 {
+            0 => last_r,
+            1 => (last_r + 1) % 16,
+            2 => (last_r + 15) % 16,
+            _ if gps_time_change => {
+                let model = model_at(&mut state.return_number_models, last_r, 16);
+                xy_decoder.decode_symbol(model) as usize
+            }
+            _ => {
+                let symbol = xy_decoder.decode_symbol(&mut state.return_number_gps_same_model);
+                (last_r + symbol as usize + 2) % 16
+            }
+        };
+
+        last.number_of_returns = n as u8;
+        last.return_number = r as u8;
+
+        let m = NUMBER_RETURN_MAP_6CTX[n][r] as usize;
+        let l = number_return_level_8ctx(n, r);
+        // Whether the point is a first (2) and/or last (1) return
+        let cpr = if r == 1 { 2 } else { 0 } + if r >= n { 1 } else { 0 };
+        let single_return = (n == 1) as u32;
+
+        let median_index = (m << 1) | gps_time_change as usize;
+        let median = state.last_x_diff_median5[median_index].get();
+        let diff = state.ic_dx.decompress(xy_decoder, median, single_return);
+        last.x = last.x.wrapping_add(diff);
+        state.last_x_diff_median5[median_index].add(diff);
+
+        let median = state.last_y_diff_median5[median_index].get();
+        let k_bits = state.ic_dx.k();
+        let y_context = single_return + if k_bits < 20 { k_bits & !1 } else { 20 };
+        let diff = state.ic_dy.decompress(xy_decoder, median, y_context);
+        last.y = last.y.wrapping_add(diff);
+        state.last_y_diff_median5[median_index].add(diff);
+
+        if let Some(decoder) = &mut layers[Z_LAYER].decoder {
+            let k_bits = (state.ic_dx.k() + state.ic_dy.k()) / 2;
+            let z_context = single_return + if k_bits < 18 { k_bits & !1 } else { 18 };
+            last.z = state.ic_z.decompress(decoder, state.last_z[l], z_context);
+            state.last_z[l] = last.z;
+        }
+
+        if let Some(decoder) = &mut layers[CLASSIFICATION_LAYER].decoder {
+            let index = (((last.classification & 0x1F) as usize) << 1) + (cpr == 3) as usize;
+            let model = model_at(&mut state.classification_models, index, 256);
+            last.classification = decoder.decode_symbol(model) as u8;
+        }
+
+        if let Some(decoder) = &mut layers[FLAGS_LAYER].decoder {
+            let last_flags = (last.edge_of_flight_line << 5)
+                | (last.scan_direction_flag << 4)
+                | last.classification_flags;
+            let model = model_at(&mut state.flags_models, last_flags as usize, 64);
+            let flags = decoder.decode_symbol(model) as u8;
+            last.edge_of_flight_line = (flags >> 5) & 1;
+            last.scan_direction_flag = (flags >> 4) & 1;
+            last.classification_flags = flags & 0xF;
+        }
+
+        if let Some(decoder) = &mut layers[INTENSITY_LAYER].decoder {
+            let index = (cpr << 1) | gps_time_change as usize;
+            last.intensity = state.ic_intensity.decompress(
+                decoder,
+                state.last_intensity[index] as i32,
+                cpr as u32,
+            ) as u16;
+            state.last_intensity[index] = last.intensity;
+        }
+
+        if let Some(decoder) = &mut layers[SCAN_ANGLE_LAYER].decoder {
+            if scan_angle_change {
+                last.scan_angle = state.ic_scan_angle.decompress(
+                    decoder,
+                    last.scan_angle as i32,
+                    gps_time_change as u32,
+                ) as i16;
+            }
+        }
+
+        if let Some(decoder) = &mut layers[USER_DATA_LAYER].decoder {
+            let model = model_at(
+                &mut state.user_data_models,
+                last.user_data as usize / 4,
+                256,
+            );
+            last.user_data = decoder.decode_symbol(model) as u8;
+        }
+
+        if let Some(decoder) = &mut layers[POINT_SOURCE_LAYER].decoder {
+            if point_source_change {
+                last.point_source_id =
+                    state
+                        .ic_point_source_id
+                        .decompress(decoder, last.point_source_id as i32, 0)
+                        as u16;
+            }
+        }
+
+        if let Some(decoder) = &mut layers[GPS_TIME_LAYER].decoder {
+            if gps_time_change {
+                last.gps_time = state.gps_time.decompress(decoder);
+            }
+        }
+
+        last.write(item);
+        state.last_gps_time_change = gps_time_change;
+        self.layers = layers;
+    }
+}
@@ -0,0 +1,253 @@
+//! Colors of the RGB12 version 2 item and of the RGB14 and RGBNIR14 version 3 items. Each
+//! color byte is stored as a difference to the last color, and green and blue are predicted
+//! from how much red changed
+
+use super::arithmetic_decoder::{ArithmeticDecoder, ArithmeticModel};
+use super::{ByteReader, LayerReader, LayeredItemDecompressor, PointwiseItemDecompressor};
+
+pub const RGB_SIZE: usize = 6;
+pub const RGB_NIR_SIZE: usize = 8;
+
+fn clamp_u8(value: i32) -> i32 {
+    value.clamp(0, 255)
+}
+
+fn read_u16s<const N: usize>(bytes: &[u8]) -> [u16; N] {
+    std::array::from_fn(|i| u16::from_le_bytes([bytes[2 * i], bytes[2 * i + 1]]))
+}
+
+fn write_u16s(values: &[u16], bytes: &mut [u8]) {
+    for (i, value) in values.iter().enumerate() {
+        bytes[2 * i..2 * i + 2].copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+#[derive(Clone)]
+pub struct RgbModels {
+    byte_used: ArithmeticModel,
+    diff: [ArithmeticModel; 6],
+}
+
+impl RgbModels {
+    pub fn new() -> RgbModels {
+        RgbModels {
+            byte_used: ArithmeticModel::new(128),
+            diff: std::array::from_fn(|_| ArithmeticModel::new(256)),
+        }
+    }
+
+    pub fn decompress(&mut self, decoder: &mut ArithmeticDecoder, last: &[u16; 3]) -> [u16; 3] {
+        let byte_used = decoder.decode_symbol(&mut self.byte_used);
+        let mut decode_byte = |index: usize, last_byte: i32| -> u16 {
+            let corrector = decoder.decode_symbol(&mut self.diff[index]) as i32;
+            (corrector + last_byte) as u8 as u16
+        };
+        let is_used = |bit: u32| byte_used & (1 << bit) != 0;
+        let low = |value: u16| (value & 0xFF) as i32;
+        let high = |value: u16| (value >> 8) as i32;
+
+        let mut item = [0u16; 3];
+
+        item[0] = if is_used(0) {
+            decode_byte(0, low(last[0]))
+        } else {
+            last[0] & 0xFF
+        };
+        item[0] |= if is_used(1) {
+            decode_byte(1, high(last[0])) << 8
+        } else {
+            last[0] & 0xFF00
+        };
+
+        if is_used(6) {
+            let mut diff = low(item[0]) - low(last[0]);
+            item[1] = if is_used(2) {
+                decode_byte(2, clamp_u8(diff + low(last[1])))
+            } else {
+                last[1] & 0xFF
+            };
+            item[2] = if is_used(4) {
+                diff = (diff + low(item[1]) - low(last[1])) / 2;
+                decode_byte(4, clamp_u8(diff + low(last[2])))
+            } else {
+                last[2] & 0xFF
+            };
+
+            let mut diff = high(item[0]) - high(last[0]);
+            item[1] |= if is_used(3) {
+                decode_byte(3, clamp_u8(diff + high(last[1]))) << 8
+            } else {
+                last[1] & 0xFF00
+            };
+            item[2] |= if is_used(5) {
+                diff = (diff + high(item[1]) - high(last[1])) / 2;
+                decode_byte(5, clamp_u8(diff + high(last[2]))) << 8
+            } else {
+                last[2] & 0xFF00
+            };
+        } else {
+            item[1] = item[0];
+            item[2] = item[0];
+        }
+
+        item
+    }
+}
+
+/// Version 2 of the RGB12 item
+pub struct Rgb12Decompressor {
+    last: [u16; 3],
+    models: RgbModels,
+}
+
+impl Rgb12Decompressor {
+    pub fn new() -> Rgb12Decompressor {
+        Rgb12Decompressor {
+            last: [0; 3],
+            models: RgbModels::new(),
+        }
+    }
+}
+
+impl PointwiseItemDecompressor for Rgb12Decompressor {
+    fn init(&mut self, first: &[u8]) {
+        self.last = read_u16s(first);
+    }
+
+    fn decompress(&mut self, decoder: &mut ArithmeticDecoder, item: &mut [u8]) {
+        self.last = self.models.decompress(decoder, &self.last);
+        write_u16s(&self.last, item);
+    }
+}
+
+#[derive(Clone)]
+struct NirModels {
+    bytes_used: ArithmeticModel,
+    diff: [ArithmeticModel; 2],
+}
+
+impl NirModels {
+    fn new() -> NirModels {
+        NirModels {
+            bytes_used: ArithmeticModel::new(4),
+            diff: [ArithmeticModel::new(256), ArithmeticModel::new(256)],
+        }
+    }
+
+    fn decompress(&mut self, decoder: &mut ArithmeticDecoder, last: u16) -> u16 {
+        let bytes_used = decoder.decode_symbol(&mut self.bytes_used);
+
+        let low = if bytes_used & 1 != 0 {
+            let corrector = decoder.decode_symbol(&mut self.diff[0]);
+            (corrector + (last & 0xFF) as u32) as u8 as u16
+        } else {
+            last & 0xFF
+        };
+        let high = if bytes_used & 2 != 0 {
+            let corrector = decoder.decode_symbol(&mut self.diff[1]);
+            ((corrector + (last >> 8) as u32) as u8 as u16) << 8
+        } else {
+            last & 0xFF00
+        };
+
+        low | high
+    }
+}
+
+#[derive(Clone)]
+struct RgbNirContext {
+    /// Red, green, blue and near infrared
+    last: [u16; 4],
+    rgb_models: RgbModels,
+    nir_models: NirModels,
+}
+
+/// Version 3 of the RGB14 and RGBNIR14 items, which have one layer for the colors and one for
+/// near infrared. Like all layered items, it keeps separate state for each scanner channel
+pub struct RgbNir14Decompressor<'a> {
+    has_nir: bool,
+    rgb_layer: LayerReader<'a>,
+    nir_layer: LayerReader<'a>,
+    contexts: [Option<RgbNirContext>; 4],
+    current_context: usize,
+}
+
+impl<'a> RgbNir14Decompressor<'a> {
+    pub fn new(has_nir: bool) -> RgbNir14Decompressor<'a> {
+        RgbNir14Decompressor {
+            has_nir,
+            rgb_layer: LayerReader::default(),
+            nir_layer: LayerReader::default(),
+            contexts: Default::default(),
+            current_context: 0,
+        }
+    }
+
+    fn size(&self) -> usize {
+        if self.has_nir {
+            RGB_NIR_SIZE
+        } else {
+            RGB_SIZE
+        }
+    }
+
+    fn create_context(last: &[u8]) -> RgbNirContext {
+        let mut values = [0u8; RGB_NIR_SIZE];
+        values[..last.len()].copy_from_slice(last);
+        RgbNirContext {
+            last: read_u16s(&values),
+            rgb_models: RgbModels::new(),
+            nir_models: NirModels::new(),
+        }
+    }
+}
+
+impl<'a> LayeredItemDecompressor<'a> for RgbNir14Decompressor<'a> {
+    fn read_layer_sizes(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String> {
+        self.rgb_layer.size = reader.read_u32()? as usize;
+        if self.has_nir {
+            self.nir_layer.size = reader.read_u32()? as usize;
+        }
+        Ok(())
+    }
+
+    fn read_layers(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String> {
+        self.rgb_layer.read(reader)?;
+        if self.has_nir {
+            self.nir_layer.read(reader)?;
+        }
+        Ok(())
+    }
+
+    fn init(&mut self, first: &[u8], context: &mut usize) {
+        let context = *context;
+        self.contexts = Default::default();
+        self.contexts[context] = Some(Self::create_context(&first[..self.size()]));
+        self.current_context = context;
+    }
+
+    fn decompress(&mut self, item: &mut [u8], context: &mut usize) {
+        let context = *context;
+        if self.contexts[context].is_none() {
+            let mut last = [0u8; RGB_NIR_SIZE];
+            if let Some(current) = &self.contexts[self.current_context] {
+                write_u16s(&current.last, &mut last);
+            }
+            self.contexts[context] = Some(Self::create_context(&last));
+        }
+        self.current_context = context;
+        let size = self.size();
+        let state = self.contexts[context].as_mut().unwrap();
+
+        if let Some(decoder) = &mut self.rgb_layer.decoder {
+            let last_rgb = [state.last[0], state.last[1], state.last[2]];
+            let rgb = state.rgb_models.decompress(decoder, &last_rgb);
+            state.last[..3].copy_from_slice(&rgb);
+        }
+        if let Some(decoder) = &mut self.nir_layer.decoder {
+            state.last[3] = state.nir_models.decompress(decoder, state.last[3]);
+        }
+
+        write_u16s(&state.last[..size / 2], &mut item[..size]);
+    }
+}
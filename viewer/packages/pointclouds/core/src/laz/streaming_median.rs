@@ -0,0 +1,68 @@
+/// Approximate median of the last few values added, used to predict coordinate differences
+#[derive(Clone, Copy, Debug)]
+pub struct StreamingMedian5 {
+    values: [i32; 5],
+    high: bool,
+}
+
+impl StreamingMedian5 {
+    pub fn new() -> StreamingMedian5 {
+        StreamingMedian5 {
+            values: [0; 5],
+            high: true,
+        }
+    }
+
+    pub fn add(&mut self, v: i32) {
+        let values = &mut self.values;
+        if self.high {
+            if v < values[2] {
+                values[4] = values[3];
+                values[3] = values[2];
+                if v < values[0] {
+                    values[2] = values[1];
+                    values[1] = values[0];
+                    values[0] = v;
+                } else if v < values[1] {
+                    values[2] = values[1];
+                    values[1] = v;
+                } else {
+                    values[2] = v;
+                }
+            } else {
+                if v < values[3] {
+                    values[4] = values[3];
+                    values[3] = v;
+                } else {
+                    values[4] = v;
+                }
+                self.high = false;
+            }
+        } else if values[2] < v {
+            values[0] = values[1];
+            values[1] = values[2];
+            if values[4] < v {
+                values[2] = values[3];
+                values[3] = values[4];
+                values[4] = v;
+            } else if values[3] < v {
+                values[2] = values[3];
+                values[3] = v;
+            } else {
+                values[2] = v;
+            }
+        } else {
+            if values[1] < v {
+                values[0] = values[1];
+                values[1] = v;
+            } else {
+                values[0] = v;
+            }
+            self.high = true;
+        }
+    }
+
+    pub fn get(&self) -> i32 {
+        self.values[2]
+    }
+}
@@ -0,0 +1,1411 @@
+//! Compressors mirroring the decompressors, for creating LAZ data with known contents in tests
+
+use super::arithmetic_decoder::{
+    ArithmeticBitModel, ArithmeticModel, AC_MAX_LENGTH, AC_MIN_LENGTH, BM_LENGTH_SHIFT,
+    DM_LENGTH_SHIFT,
+};
+use super::point10::{Point10, NUMBER_RETURN_LEVEL, NUMBER_RETURN_MAP};
+use super::point14::{number_return_level_8ctx, Point14, NUMBER_RETURN_MAP_6CTX};
+use super::streaming_median::StreamingMedian5;
+use super::wavepacket::WavePacket;
+use super::{model_at, LazItem, LazItemType, LASZIP_RECORD_ID, LASZIP_USER_ID};
+
+pub struct ArithmeticEncoder {
+    base: u32,
+    length: u32,
+    bytes: Vec<u8>,
+}
+
+impl ArithmeticEncoder {
+    pub fn new() -> ArithmeticEncoder {
+        ArithmeticEncoder {
+            base: 0,
+            length: AC_MAX_LENGTH,
+            bytes: vec![],
+        }
+    }
+
+    fn propagate_carry(&mut self) {
+        for byte in self.bytes.iter_mut().rev() {
+            if *byte == 0xFF {
+                *byte = 0;
+            } else {
+                *byte += 1;
+                break;
+            }
+        }
+    }
+
+    fn add_to_base(&mut self, value: u32) {
+        let (base, carry) = self.base.overflowing_add(value);
+        self.base = base;
+        if carry {
+            self.propagate_carry();
+        }
+    }
+
+    fn renormalize(&mut self) {
+        while self.length < AC_MIN_LENGTH {
+            self.bytes.push((self.base >> 24) as u8);
+            self.base <<= 8;
+            self.length <<= 8;
+        }
+    }
+
+    pub fn encode_bit(&mut self, model: &mut ArithmeticBitModel, bit: u32) {
+        let x = model.bit_0_prob * (self.length >> BM_LENGTH_SHIFT);
+        if bit == 0 {
+            self.length = x;
+            model.bit_0_count += 1;
+        } else {
+            self.add_to_base(x);
+            self.length -= x;
+        }
+        self.renormalize();
+
+        model.bits_until_update -= 1;
+        if model.bits_until_update == 0 {
+            model.update();
+        }
+    }
+
+    pub fn encode_symbol(&mut self, model: &mut ArithmeticModel, symbol: u32) {
+        assert!(symbol < model.symbols);
+
+        // The last symbol takes the rest of the interval, which avoids a multiplication
+        if symbol == model.last_symbol {
+            let x = model.distribution[symbol as usize] * (self.length >> DM_LENGTH_SHIFT);
+            self.add_to_base(x);
+            self.length -= x;
+        } else {
+            self.length >>= DM_LENGTH_SHIFT;
+            let x = model.distribution[symbol as usize] * self.length;
+            self.add_to_base(x);
+            self.length = model.distribution[symbol as usize + 1] * self.length - x;
+        }
+        self.renormalize();
+
+        model.symbol_count[symbol as usize] += 1;
+        model.symbols_until_update -= 1;
+        if model.symbols_until_update == 0 {
+            model.update();
+        }
+    }
+
+    pub fn write_bits(&mut self, mut bits: u32, mut value: u32) {
+        if bits > 19 {
+            self.write_short(value as u16);
+            value >>= 16;
+            bits -= 16;
+        }
+        self.length >>= bits;
+        self.add_to_base(value * self.length);
+        self.renormalize();
+    }
+
+    pub fn write_short(&mut self, value: u16) {
+        self.length >>= 16;
+        self.add_to_base(value as u32 * self.length);
+        self.renormalize();
+    }
+
+    pub fn write_int(&mut self, value: u32) {
+        self.write_short(value as u16);
+        self.write_short((value >> 16) as u16);
+    }
+
+    pub fn write_int64(&mut self, value: u64) {
+        self.write_int(value as u32);
+        self.write_int((value >> 32) as u32);
+    }
+
+    pub fn done(mut self) -> Vec<u8> {
+        let another_byte = self.length > 2 * AC_MIN_LENGTH;
+        if another_byte {
+            self.add_to_base(AC_MIN_LENGTH);
+            self.length = AC_MIN_LENGTH >> 1;
+        } else {
+            self.add_to_base(AC_MIN_LENGTH >> 1);
+            self.length = AC_MIN_LENGTH >> 9;
+        }
+        self.renormalize();
+
+        // The decoder reads a few bytes ahead
+        self.bytes.extend([0, 0]);
+        if another_byte {
+            self.bytes.push(0);
+        }
+        self.bytes
+    }
+}
+
+#[derive(Clone)]
+pub struct IntegerCompressor {
+    k: u32,
+    bits_high: u32,
+    corr_range: u32,
+    corr_min: i32,
+    corr_max: i32,
+    bits_models: Vec<ArithmeticModel>,
+    corrector_bit_model: ArithmeticBitModel,
+    corrector_models: Vec<ArithmeticModel>,
+}
+
+impl IntegerCompressor {
+    pub fn new(bits: u32, contexts: u32) -> IntegerCompressor {
+        let bits_high = 8;
+        let (corr_bits, corr_range, corr_min) = if bits > 0 && bits < 32 {
+            (bits, 1u32 << bits, -((1u32 << (bits - 1)) as i32))
+        } else {
+            (32, 0, i32::MIN)
+        };
+
+        IntegerCompressor {
+            k: 0,
+            bits_high,
+            corr_range,
+            corr_min,
+            corr_max: corr_min.wrapping_add(corr_range.wrapping_sub(1) as i32),
+            bits_models: (0..contexts)
+                .map(|_| ArithmeticModel::new(corr_bits + 1))
+                .collect(),
+            corrector_bit_model: ArithmeticBitModel::new(),
+            corrector_models: (0..=corr_bits)
+                .map(|i| ArithmeticModel::new(1 << i.clamp(1, bits_high)))
+                .collect(),
+        }
+    }
+
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+
+    pub fn compress(
+        &mut self,
+        encoder: &mut ArithmeticEncoder,
+        prediction: i32,
+        real: i32,
+        context: u32,
+    ) {
+        let mut corrector = real.wrapping_sub(prediction);
+        if self.corr_range != 0 {
+            if corrector < self.corr_min {
+                corrector += self.corr_range as i32;
+            } else if corrector > self.corr_max {
+                corrector -= self.corr_range as i32;
+            }
+        }
+
+        let magnitude = if corrector <= 0 {
+            corrector.wrapping_neg() as u32
+        } else {
+            (corrector - 1) as u32
+        };
+        self.k = 32 - magnitude.leading_zeros();
+        encoder.encode_symbol(&mut self.bits_models[context as usize], self.k);
+
+        let k = self.k;
+        if k == 0 {
+            encoder.encode_bit(&mut self.corrector_bit_model, corrector as u32);
+        } else if k < 32 {
+            let value = if corrector < 0 {
+                corrector.wrapping_add(((1u32 << k) - 1) as i32)
+            } else {
+                corrector - 1
+            } as u32;
+            if k <= self.bits_high {
+                encoder.encode_symbol(&mut self.corrector_models[k as usize], value);
+            } else {
+                let extra_bits = k - self.bits_high;
+                encoder.encode_symbol(&mut self.corrector_models[k as usize], value >> extra_bits);
+                encoder.write_bits(extra_bits, value & ((1 << extra_bits) - 1));
+            }
+        }
+    }
+}
+
+/// Layers from a version 3 item, `None` for layers left out of the chunk
+type Layers = Vec<Option<Vec<u8>>>;
+
+trait ItemCompressor {
+    fn init(&mut self, first: &[u8], context: &mut usize);
+    /// `encoder` is used by pointwise items
+    fn compress(&mut self, encoder: &mut ArithmeticEncoder, item: &[u8], context: &mut usize);
+    fn done(self: Box<Self>) -> Layers {
+        vec![]
+    }
+}
+
+struct Point10Compressor {
+    last: Point10,
+    last_intensity: [u16; 16],
+    last_x_diff_median5: [StreamingMedian5; 16],
+    last_y_diff_median5: [StreamingMedian5; 16],
+    last_height: [i32; 8],
+    changed_values_model: ArithmeticModel,
+    scan_angle_rank_models: [ArithmeticModel; 2],
+    bit_byte_models: Vec<Option<ArithmeticModel>>,
+    classification_models: Vec<Option<ArithmeticModel>>,
+    user_data_models: Vec<Option<ArithmeticModel>>,
+    ic_intensity: IntegerCompressor,
+    ic_point_source_id: IntegerCompressor,
+    ic_dx: IntegerCompressor,
+    ic_dy: IntegerCompressor,
+    ic_z: IntegerCompressor,
+}
+
+impl Point10Compressor {
+    fn new() -> Point10Compressor {
+        Point10Compressor {
+            last: Point10::default(),
+            last_intensity: [0; 16],
+            last_x_diff_median5: [StreamingMedian5::new(); 16],
+            last_y_diff_median5: [StreamingMedian5::new(); 16],
+            last_height: [0; 8],
+            changed_values_model: ArithmeticModel::new(64),
+            scan_angle_rank_models: [ArithmeticModel::new(256), ArithmeticModel::new(256)],
+            bit_byte_models: vec![None; 256],
+            classification_models: vec![None; 256],
+            user_data_models: vec![None; 256],
+            ic_intensity: IntegerCompressor::new(16, 4),
+            ic_point_source_id: IntegerCompressor::new(16, 1),
+            ic_dx: IntegerCompressor::new(32, 2),
+            ic_dy: IntegerCompressor::new(32, 22),
+            ic_z: IntegerCompressor::new(32, 20),
+        }
+    }
+}
+
+impl ItemCompressor for Point10Compressor {
+    fn init(&mut self, first: &[u8], _: &mut usize) {
+        self.last = Point10::read(first);
+        self.last.intensity = 0;
+    }
+
+    fn compress(&mut self, encoder: &mut ArithmeticEncoder, item: &[u8], _: &mut usize) {
+        let point = Point10::read(item);
+        let last = self.last;
+        let r = point.return_number();
+        let n = point.number_of_returns();
+        let m = NUMBER_RETURN_MAP[n][r] as usize;
+        let l = NUMBER_RETURN_LEVEL[n][r] as usize;
+
+        let changed_values = ((last.bit_fields != point.bit_fields) as u32) << 5
+            | ((self.last_intensity[m] != point.intensity) as u32) << 4
+            | ((last.classification != point.classification) as u32) << 3
+            | ((last.scan_angle_rank != point.scan_angle_rank) as u32) << 2
+            | ((last.user_data != point.user_data) as u32) << 1
+            | (last.point_source_id != point.point_source_id) as u32;
+        encoder.encode_symbol(&mut self.changed_values_model, changed_values);
+
+        if changed_values & (1 << 5) != 0 {
+            let model = model_at(&mut self.bit_byte_models, last.bit_fields as usize, 256);
+            encoder.encode_symbol(model, point.bit_fields as u32);
+        }
+        if changed_values & (1 << 4) != 0 {
+            self.ic_intensity.compress(
+                encoder,
+                self.last_intensity[m] as i32,
+                point.intensity as i32,
+                m.min(3) as u32,
+            );
+            self.last_intensity[m] = point.intensity;
+        }
+        if changed_values & (1 << 3) != 0 {
+            let model = model_at(
+                &mut self.classification_models,
+                last.classification as usize,
+                256,
+            );
+            encoder.encode_symbol(model, point.classification as u32);
+        }
+        if changed_values & (1 << 2) != 0 {
+            let model = &mut self.scan_angle_rank_models[point.scan_direction_flag()];
+            encoder.encode_symbol(
+                model,
+                point.scan_angle_rank.wrapping_sub(last.scan_angle_rank) as u32,
+            );
+        }
+        if changed_values & (1 << 1) != 0 {
+            let model = model_at(&mut self.user_data_models, last.user_data as usize, 256);
+            encoder.encode_symbol(model, point.user_data as u32);
+        }
+        if changed_values & 1 != 0 {
+            self.ic_point_source_id.compress(
+                encoder,
+                last.point_source_id as i32,
+                point.point_source_id as i32,
+                0,
+            );
+        }
+
+        let single_return = (n == 1) as u32;
+
+        let diff = point.x.wrapping_sub(last.x);
+        let median = self.last_x_diff_median5[m].get();
+        self.ic_dx.compress(encoder, median, diff, single_return);
+        self.last_x_diff_median5[m].add(diff);
+
+        let diff = point.y.wrapping_sub(last.y);
+        let median = self.last_y_diff_median5[m].get();
+        let k_bits = self.ic_dx.k();
+        let context = single_return + if k_bits < 20 { k_bits & !1 } else { 20 };
+        self.ic_dy.compress(encoder, median, diff, context);
+        self.last_y_diff_median5[m].add(diff);
+
+        let k_bits = (self.ic_dx.k() + self.ic_dy.k()) / 2;
+        let context = single_return + if k_bits < 18 { k_bits & !1 } else { 18 };
+        self.ic_z
+            .compress(encoder, self.last_height[l], point.z, context);
+        self.last_height[l] = point.z;
+
+        self.last = point;
+    }
+}
+
+const GPS_TIME_MULTI: i32 = 500;
+const GPS_TIME_MULTI_MINUS: i32 = -10;
+
+/// Compresses GPS times like LASzip, except that it never switches between sequences of
+/// times
+struct GpsTimeCompressor {
+    layered: bool,
+    last: usize,
+    next: usize,
+    last_gps_time: [i64; 4],
+    last_gps_time_diff: [i32; 4],
+    multi_extreme_counter: [i32; 4],
+    multi_model: ArithmeticModel,
+    zero_diff_model: ArithmeticModel,
+    ic_gps_time: IntegerCompressor,
+}
+
+impl GpsTimeCompressor {
+    fn new(first_gps_time: i64, layered: bool) -> GpsTimeCompressor {
+        GpsTimeCompressor {
+            layered,
+            last: 0,
+            next: 0,
+            last_gps_time: [first_gps_time, 0, 0, 0],
+            last_gps_time_diff: [0; 4],
+            multi_extreme_counter: [0; 4],
+            multi_model: ArithmeticModel::new(if layered { 515 } else { 516 }),
+            zero_diff_model: ArithmeticModel::new(if layered { 5 } else { 6 }),
+            ic_gps_time: IntegerCompressor::new(32, 9),
+        }
+    }
+
+    fn count_extreme(&mut self, diff: i32) {
+        self.multi_extreme_counter[self.last] += 1;
+        if self.multi_extreme_counter[self.last] > 3 {
+            self.last_gps_time_diff[self.last] = diff;
+            self.multi_extreme_counter[self.last] = 0;
+        }
+    }
+
+    fn compress(&mut self, encoder: &mut ArithmeticEncoder, gps_time: i64) {
+        let last = self.last;
+        let diff_64 = gps_time.wrapping_sub(self.last_gps_time[last]);
+        let diff = i32::try_from(diff_64).ok();
+        let layered = self.layered as u32;
+
+        if self.last_gps_time_diff[last] == 0 {
+            if diff_64 == 0 && !self.layered {
+                encoder.encode_symbol(&mut self.zero_diff_model, 0);
+            } else if let Some(diff) = diff {
+                encoder.encode_symbol(&mut self.zero_diff_model, 1 - layered);
+                self.ic_gps_time.compress(encoder, 0, diff, 0);
+                self.last_gps_time_diff[last] = diff;
+                self.multi_extreme_counter[last] = 0;
+                self.last_gps_time[last] = gps_time;
+            } else {
+                encoder.encode_symbol(&mut self.zero_diff_model, 2 - layered);
+                self.compress_full_time(encoder, gps_time);
+            }
+            return;
+        }
+
+        let Some(diff) = diff else {
+            encoder.encode_symbol(&mut self.multi_model, 512 - layered);
+            self.compress_full_time(encoder, gps_time);
+            return;
+        };
+
+        let last_diff = self.last_gps_time_diff[last];
+        let multi = (diff as f64 / last_diff as f64)
+            .round()
+            .clamp(i32::MIN as f64, i32::MAX as f64) as i32;
+
+        if multi == 1 {
+            encoder.encode_symbol(&mut self.multi_model, 1);
+            self.ic_gps_time.compress(encoder, last_diff, diff, 1);
+            self.multi_extreme_counter[last] = 0;
+        } else if multi > 0 {
+            if multi < GPS_TIME_MULTI {
+                encoder.encode_symbol(&mut self.multi_model, multi as u32);
+                let context = if multi < 10 { 2 } else { 3 };
+                self.ic_gps_time
+                    .compress(encoder, multi.wrapping_mul(last_diff), diff, context);
+            } else {
+                encoder.encode_symbol(&mut self.multi_model, GPS_TIME_MULTI as u32);
+                self.ic_gps_time
+                    .compress(encoder, GPS_TIME_MULTI.wrapping_mul(last_diff), diff, 4);
+                self.count_extreme(diff);
+            }
+        } else if multi < 0 {
+            if multi > GPS_TIME_MULTI_MINUS {
+                encoder.encode_symbol(&mut self.multi_model, (GPS_TIME_MULTI - multi) as u32);
+                self.ic_gps_time
+                    .compress(encoder, multi.wrapping_mul(last_diff), diff, 5);
+            } else {
+                encoder.encode_symbol(
+                    &mut self.multi_model,
+                    (GPS_TIME_MULTI - GPS_TIME_MULTI_MINUS) as u32,
+                );
+                self.ic_gps_time.compress(
+                    encoder,
+                    GPS_TIME_MULTI_MINUS.wrapping_mul(last_diff),
+                    diff,
+                    6,
+                );
+                self.count_extreme(diff);
+            }
+        } else {
+            encoder.encode_symbol(&mut self.multi_model, 0);
+            self.ic_gps_time.compress(encoder, 0, diff, 7);
+            self.count_extreme(diff);
+        }
+        self.last_gps_time[last] = gps_time;
+    }
+
+    fn compress_full_time(&mut self, encoder: &mut ArithmeticEncoder, gps_time: i64) {
+        self.next = (self.next + 1) & 3;
+        self.ic_gps_time.compress(
+            encoder,
+            (self.last_gps_time[self.last] >> 32) as i32,
+            (gps_time >> 32) as i32,
+            8,
+        );
+        encoder.write_int(gps_time as u32);
+        self.last = self.next;
+        self.last_gps_time[self.last] = gps_time;
+        self.last_gps_time_diff[self.last] = 0;
+        self.multi_extreme_counter[self.last] = 0;
+    }
+}
+
+struct GpsTime11Compressor(GpsTimeCompressor);
+
+impl ItemCompressor for GpsTime11Compressor {
+    fn init(&mut self, first: &[u8], _: &mut usize) {
+        self.0 = GpsTimeCompressor::new(i64::from_le_bytes(first.try_into().unwrap()), false);
+    }
+
+    fn compress(&mut self, encoder: &mut ArithmeticEncoder, item: &[u8], _: &mut usize) {
+        self.0
+            .compress(encoder, i64::from_le_bytes(item.try_into().unwrap()));
+    }
+}
+
+#[derive(Clone)]
+struct RgbModels {
+    byte_used: ArithmeticModel,
+    diff: [ArithmeticModel; 6],
+}
+
+impl RgbModels {
+    fn new() -> RgbModels {
+        RgbModels {
+            byte_used: ArithmeticModel::new(128),
+            diff: std::array::from_fn(|_| ArithmeticModel::new(256)),
+        }
+    }
+
+    fn compress(&mut self, encoder: &mut ArithmeticEncoder, last: &[u16], item: &[u16]) {
+        let low = |value: u16| (value & 0xFF) as i32;
+        let high = |value: u16| (value >> 8) as i32;
+
+        let mut symbol =
+            (low(last[0]) != low(item[0])) as u32 | ((high(last[0]) != high(item[0])) as u32) << 1;
+        let is_gray = item[0] == item[1] && item[0] == item[2];
+        if !is_gray {
+            symbol |= 1 << 6
+                | ((low(last[1]) != low(item[1])) as u32) << 2
+                | ((high(last[1]) != high(item[1])) as u32) << 3
+                | ((low(last[2]) != low(item[2])) as u32) << 4
+                | ((high(last[2]) != high(item[2])) as u32) << 5;
+        }
+        encoder.encode_symbol(&mut self.byte_used, symbol);
+
+        let mut encode = |index: usize, value: i32| {
+            encoder.encode_symbol(&mut self.diff[index], value as u8 as u32);
+        };
+        let clamp = |value: i32| value.clamp(0, 255);
+
+        if symbol & 1 != 0 {
+            encode(0, low(item[0]) - low(last[0]));
+        }
+        if symbol & 2 != 0 {
+            encode(1, high(item[0]) - high(last[0]));
+        }
+        if symbol & (1 << 6) != 0 {
+            let mut diff = low(item[0]) - low(last[0]);
+            if symbol & (1 << 2) != 0 {
+                encode(2, low(item[1]) - clamp(diff + low(last[1])));
+            }
+            if symbol & (1 << 4) != 0 {
+                diff = (diff + low(item[1]) - low(last[1])) / 2;
+                encode(4, low(item[2]) - clamp(diff + low(last[2])));
+            }
+            let mut diff = high(item[0]) - high(last[0]);
+            if symbol & (1 << 3) != 0 {
+                encode(3, high(item[1]) - clamp(diff + high(last[1])));
+            }
+            if symbol & (1 << 5) != 0 {
+                diff = (diff + high(item[1]) - high(last[1])) / 2;
+                encode(5, high(item[2]) - clamp(diff + high(last[2])));
+            }
+        }
+    }
+}
+
+fn read_u16s(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect()
+}
+
+struct Rgb12Compressor {
+    last: Vec<u16>,
+    models: RgbModels,
+}
+
+impl ItemCompressor for Rgb12Compressor {
+    fn init(&mut self, first: &[u8], _: &mut usize) {
+        self.last = read_u16s(first);
+    }
+
+    fn compress(&mut self, encoder: &mut ArithmeticEncoder, item: &[u8], _: &mut usize) {
+        let item = read_u16s(item);
+        self.models.compress(encoder, &self.last, &item);
+        self.last = item;
+    }
+}
+
+struct ExtraBytesCompressor {
+    last: Vec<u8>,
+    models: Vec<ArithmeticModel>,
+}
+
+impl ItemCompressor for ExtraBytesCompressor {
+    fn init(&mut self, first: &[u8], _: &mut usize) {
+        self.last = first.to_vec();
+    }
+
+    fn compress(&mut self, encoder: &mut ArithmeticEncoder, item: &[u8], _: &mut usize) {
+        for ((last, model), value) in self.last.iter_mut().zip(&mut self.models).zip(item) {
+            encoder.encode_symbol(model, value.wrapping_sub(*last) as u32);
+            *last = *value;
+        }
+    }
+}
+
+#[derive(Clone)]
+struct WavePacketModels {
+    last_diff_32: i32,
+    last_offset_diff_symbol: usize,
+    packet_index: ArithmeticModel,
+    offset_diff: [ArithmeticModel; 4],
+    ic_offset_diff: IntegerCompressor,
+    ic_packet_size: IntegerCompressor,
+    ic_return_point: IntegerCompressor,
+    ic_xyz: IntegerCompressor,
+}
+
+impl WavePacketModels {
+    fn new() -> WavePacketModels {
+        WavePacketModels {
+            last_diff_32: 0,
+            last_offset_diff_symbol: 0,
+            packet_index: ArithmeticModel::new(256),
+            offset_diff: std::array::from_fn(|_| ArithmeticModel::new(4)),
+            ic_offset_diff: IntegerCompressor::new(32, 1),
+            ic_packet_size: IntegerCompressor::new(32, 1),
+            ic_return_point: IntegerCompressor::new(32, 1),
+            ic_xyz: IntegerCompressor::new(32, 3),
+        }
+    }
+
+    fn compress(&mut self, encoder: &mut ArithmeticEncoder, last: &WavePacket, item: &WavePacket) {
+        encoder.encode_symbol(&mut self.packet_index, item.descriptor_index as u32);
+
+        let diff_64 = item.offset.wrapping_sub(last.offset) as i64;
+        let diff_32 = i32::try_from(diff_64).ok();
+        let symbol = if diff_64 == 0 {
+            0
+        } else if diff_64 == last.packet_size as i64 {
+            1
+        } else if diff_32.is_some() {
+            2
+        } else {
+            3
+        };
+        encoder.encode_symbol(&mut self.offset_diff[self.last_offset_diff_symbol], symbol);
+        self.last_offset_diff_symbol = symbol as usize;
+
+        if let (2, Some(diff_32)) = (symbol, diff_32) {
+            self.ic_offset_diff
+                .compress(encoder, self.last_diff_32, diff_32, 0);
+            self.last_diff_32 = diff_32;
+        } else if symbol == 3 {
+            encoder.write_int64(item.offset);
+        }
+
+        self.ic_packet_size
+            .compress(encoder, last.packet_size as i32, item.packet_size as i32, 0);
+        self.ic_return_point
+            .compress(encoder, last.return_point, item.return_point, 0);
+        for i in 0..3 {
+            self.ic_xyz
+                .compress(encoder, last.xyz[i], item.xyz[i], i as u32);
+        }
+    }
+}
+
+struct WavePacket13Compressor {
+    last: WavePacket,
+    models: WavePacketModels,
+}
+
+impl ItemCompressor for WavePacket13Compressor {
+    fn init(&mut self, first: &[u8], _: &mut usize) {
+        self.last = WavePacket::read(first);
+    }
+
+    fn compress(&mut self, encoder: &mut ArithmeticEncoder, item: &[u8], _: &mut usize) {
+        let item = WavePacket::read(item);
+        self.models.compress(encoder, &self.last, &item);
+        self.last = item;
+    }
+}
+
+/// One layer of a version 3 item, which is only kept if its fields change within the chunk
+struct LayerWriter {
+    encoder: ArithmeticEncoder,
+    changed: bool,
+}
+
+impl LayerWriter {
+    fn new() -> LayerWriter {
+        LayerWriter {
+            encoder: ArithmeticEncoder::new(),
+            changed: false,
+        }
+    }
+
+    fn done(self, always_keep: bool) -> Option<Vec<u8>> {
+        (self.changed || always_keep).then(|| self.encoder.done())
+    }
+}
+
+struct Point14CompressorContext {
+    last: Point14,
+    last_gps_time_change: bool,
+    last_intensity: [u16; 8],
+    last_x_diff_median5: [StreamingMedian5; 12],
+    last_y_diff_median5: [StreamingMedian5; 12],
+    last_z: [i32; 8],
+    changed_values_models: Vec<ArithmeticModel>,
+    scanner_channel_model: ArithmeticModel,
+    number_of_returns_models: Vec<Option<ArithmeticModel>>,
+    return_number_gps_same_model: ArithmeticModel,
+    return_number_models: Vec<Option<ArithmeticModel>>,
+    classification_models: Vec<Option<ArithmeticModel>>,
+    flags_models: Vec<Option<ArithmeticModel>>,
+    user_data_models: Vec<Option<ArithmeticModel>>,
+    ic_dx: IntegerCompressor,
+    ic_dy: IntegerCompressor,
+    ic_z: IntegerCompressor,
+    ic_intensity: IntegerCompressor,
+    ic_scan_angle: IntegerCompressor,
+    ic_point_source_id: IntegerCompressor,
+    gps_time: GpsTimeCompressor,
+}
+
+impl Point14CompressorContext {
+    fn new(last: &Point14) -> Point14CompressorContext {
+        Point14CompressorContext {
+            last: *last,
+            last_gps_time_change: false,
+            last_intensity: [last.intensity; 8],
+            last_x_diff_median5: [StreamingMedian5::new(); 12],
+            last_y_diff_median5: [StreamingMedian5::new(); 12],
+            last_z: [last.z; 8],
+            changed_values_models: vec![ArithmeticModel::new(128); 8],
+            scanner_channel_model: ArithmeticModel::new(3),
+            number_of_returns_models: vec![None; 16],
+            return_number_gps_same_model: ArithmeticModel::new(13),
+            return_number_models: vec![None; 16],
+            classification_models: vec![None; 64],
+            flags_models: vec![None; 64],
+            user_data_models: vec![None; 64],
+            ic_dx: IntegerCompressor::new(32, 2),
+            ic_dy: IntegerCompressor::new(32, 22),
+            ic_z: IntegerCompressor::new(32, 20),
+            ic_intensity: IntegerCompressor::new(16, 4),
+            ic_scan_angle: IntegerCompressor::new(16, 2),
+            ic_point_source_id: IntegerCompressor::new(16, 1),
+            gps_time: GpsTimeCompressor::new(last.gps_time, true),
+        }
+    }
+}
+
+struct Point14Compressor {
+    /// Channel, returns and xy, z, classification, flags, intensity, scan angle, user data,
+    /// point source and GPS time
+    layers: Vec<LayerWriter>,
+    contexts: [Option<Box<Point14CompressorContext>>; 4],
+    current_context: usize,
+}
+
+impl ItemCompressor for Point14Compressor {
+    fn init(&mut self, first: &[u8], context: &mut usize) {
+        let point = Point14::read(first);
+        self.current_context = point.scanner_channel as usize;
+        self.contexts[self.current_context] = Some(Box::new(Point14CompressorContext::new(&point)));
+        *context = self.current_context;
+    }
+
+    fn compress(&mut self, _: &mut ArithmeticEncoder, item: &[u8], context: &mut usize) {
+        let point = Point14::read(item);
+        let channel = point.scanner_channel as usize;
+        let current = self.contexts[self.current_context].as_ref().unwrap();
+
+        let last = current.last;
+        let lpr = (last.return_number == 1) as usize
+            + 2 * (last.return_number >= last.number_of_returns) as usize
+            + 4 * current.last_gps_time_change as usize;
+
+        // Changes are relative to the last point of the new channel, if it has one
+        let last = match &self.contexts[channel] {
+            Some(state) if channel != self.current_context => state.last,
+            _ => last,
+        };
+        let point_source_change = last.point_source_id != point.point_source_id;
+        let gps_time_change = last.gps_time != point.gps_time;
+        let scan_angle_change = last.scan_angle != point.scan_angle;
+        let (last_n, last_r) = (last.number_of_returns as usize, last.return_number as usize);
+        let (n, r) = (
+            point.number_of_returns as usize,
+            point.return_number as usize,
+        );
+
+        let mut changed_values = ((channel != self.current_context) as u32) << 6
+            | (point_source_change as u32) << 5
+            | (gps_time_change as u32) << 4
+            | (scan_angle_change as u32) << 3
+            | ((n != last_n) as u32) << 2;
+        if r != last_r {
+            changed_values |= if r == (last_r + 1) % 16 {
+                1
+            } else if r == (last_r + 15) % 16 {
+                2
+            } else {
+                3
+            };
+        }
+
+        let [xy, z_layer, classification_layer, flags_layer, intensity_layer, scan_angle_layer, user_data_layer, point_source_layer, gps_time_layer] =
+            &mut self.layers[..]
+        else {
+            unreachable!()
+        };
+        xy.changed = true;
+        let xy = &mut xy.encoder;
+
+        let current = self.contexts[self.current_context].as_mut().unwrap();
+        xy.encode_symbol(&mut current.changed_values_models[lpr], changed_values);
+
+        if channel != self.current_context {
+            let symbol = (channel + 3 - self.current_context) % 4;
+            xy.encode_symbol(&mut current.scanner_channel_model, symbol as u32);
+            let last = current.last;
+            if self.contexts[channel].is_none() {
+                self.contexts[channel] = Some(Box::new(Point14CompressorContext::new(&last)));
+            }
+            self.current_context = channel;
+        }
+        *context = self.current_context;
+        let state = self.contexts[self.current_context].as_mut().unwrap();
+        state.last.scanner_channel = channel as u8;
+        let last = state.last;
+
+        if n != last_n {
+            let model = model_at(&mut state.number_of_returns_models, last_n, 16);
+            xy.encode_symbol(model, n as u32);
+        }
+        if changed_values & 3 == 3 {
+            if gps_time_change {
+                let model = model_at(&mut state.return_number_models, last_r, 16);
+                xy.encode_symbol(model, r as u32);
+            } else {
+                let symbol = (r + 16 - last_r - 2) % 16;
+                xy.encode_symbol(&mut state.return_number_gps_same_model, symbol as u32);
+            }
+        }
+
+        let m = NUMBER_RETURN_MAP_6CTX[n][r] as usize;
+        let l = number_return_level_8ctx(n, r);
+        let cpr = if r == 1 { 2 } else { 0 } + if r >= n { 1 } else { 0 };
+        let single_return = (n == 1) as u32;
+        let median_index = (m << 1) | gps_time_change as usize;
+
+        let diff = point.x.wrapping_sub(last.x);
+        let median = state.last_x_diff_median5[median_index].get();
+        state.ic_dx.compress(xy, median, diff, single_return);
+        state.last_x_diff_median5[median_index].add(diff);
+
+        let diff = point.y.wrapping_sub(last.y);
+        let median = state.last_y_diff_median5[median_index].get();
+        let k_bits = state.ic_dx.k();
+        let y_context = single_return + if k_bits < 20 { k_bits & !1 } else { 20 };
+        state.ic_dy.compress(xy, median, diff, y_context);
+        state.last_y_diff_median5[median_index].add(diff);
+
+        let k_bits = (state.ic_dx.k() + state.ic_dy.k()) / 2;
+        let z_context = single_return + if k_bits < 18 { k_bits & !1 } else { 18 };
+        state
+            .ic_z
+            .compress(&mut z_layer.encoder, state.last_z[l], point.z, z_context);
+        state.last_z[l] = point.z;
+        z_layer.changed |= last.z != point.z;
+
+        let index = (((last.classification & 0x1F) as usize) << 1) + (cpr == 3) as usize;
+        let model = model_at(&mut state.classification_models, index, 256);
+        classification_layer
+            .encoder
+            .encode_symbol(model, point.classification as u32);
+        classification_layer.changed |= last.classification != point.classification;
+
+        let flags = |point: &Point14| {
+            (point.edge_of_flight_line << 5)
+                | (point.scan_direction_flag << 4)
+                | point.classification_flags
+        };
+        let model = model_at(&mut state.flags_models, flags(&last) as usize, 64);
+        flags_layer
+            .encoder
+            .encode_symbol(model, flags(&point) as u32);
+        flags_layer.changed |= flags(&last) != flags(&point);
+
+        let index = (cpr << 1) | gps_time_change as usize;
+        state.ic_intensity.compress(
+            &mut intensity_layer.encoder,
+            state.last_intensity[index] as i32,
+            point.intensity as i32,
+            cpr as u32,
+        );
+        state.last_intensity[index] = point.intensity;
+        intensity_layer.changed |= last.intensity != point.intensity;
+
+        if scan_angle_change {
+            state.ic_scan_angle.compress(
+                &mut scan_angle_layer.encoder,
+                last.scan_angle as i32,
+                point.scan_angle as i32,
+                gps_time_change as u32,
+            );
+            scan_angle_layer.changed = true;
+        }
+
+        let model = model_at(
+            &mut state.user_data_models,
+            last.user_data as usize / 4,
+            256,
+        );
+        user_data_layer
+            .encoder
+            .encode_symbol(model, point.user_data as u32);
+        user_data_layer.changed |= last.user_data != point.user_data;
+
+        if point_source_change {
+            state.ic_point_source_id.compress(
+                &mut point_source_layer.encoder,
+                last.point_source_id as i32,
+                point.point_source_id as i32,
+                0,
+            );
+            point_source_layer.changed = true;
+        }
+
+        if gps_time_change {
+            state
+                .gps_time
+                .compress(&mut gps_time_layer.encoder, point.gps_time);
+            gps_time_layer.changed = true;
+        }
+
+        state.last = point;
+        state.last_gps_time_change = gps_time_change;
+    }
+
+    fn done(self: Box<Self>) -> Layers {
+        self.layers
+            .into_iter()
+            .enumerate()
+            .map(|(i, layer)| layer.done(i == 0))
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+struct RgbNirCompressorContext {
+    last: Vec<u16>,
+    rgb_models: RgbModels,
+    nir_models: [ArithmeticModel; 3],
+}
+
+impl RgbNirCompressorContext {
+    fn new(last: &[u16]) -> RgbNirCompressorContext {
+        RgbNirCompressorContext {
+            last: last.to_vec(),
+            rgb_models: RgbModels::new(),
+            nir_models: [
+                ArithmeticModel::new(4),
+                ArithmeticModel::new(256),
+                ArithmeticModel::new(256),
+            ],
+        }
+    }
+}
+
+struct RgbNir14Compressor {
+    has_nir: bool,
+    rgb_layer: LayerWriter,
+    nir_layer: LayerWriter,
+    contexts: [Option<RgbNirCompressorContext>; 4],
+    current_context: usize,
+}
+
+impl ItemCompressor for RgbNir14Compressor {
+    fn init(&mut self, first: &[u8], context: &mut usize) {
+        self.contexts[*context] = Some(RgbNirCompressorContext::new(&read_u16s(first)));
+        self.current_context = *context;
+    }
+
+    fn compress(&mut self, _: &mut ArithmeticEncoder, item: &[u8], context: &mut usize) {
+        if self.contexts[*context].is_none() {
+            let last = self.contexts[self.current_context]
+                .as_ref()
+                .unwrap()
+                .last
+                .clone();
+            self.contexts[*context] = Some(RgbNirCompressorContext::new(&last));
+        }
+        self.current_context = *context;
+        let state = self.contexts[*context].as_mut().unwrap();
+        let item = read_u16s(item);
+
+        state
+            .rgb_models
+            .compress(&mut self.rgb_layer.encoder, &state.last, &item);
+        self.rgb_layer.changed |= state.last[..3] != item[..3];
+
+        if self.has_nir {
+            let (last, nir) = (state.last[3], item[3]);
+            let symbol =
+                ((last & 0xFF) != (nir & 0xFF)) as u32 | (((last >> 8) != (nir >> 8)) as u32) << 1;
+            let encoder = &mut self.nir_layer.encoder;
+            encoder.encode_symbol(&mut state.nir_models[0], symbol);
+            if symbol & 1 != 0 {
+                let diff = (nir as u8).wrapping_sub(last as u8);
+                encoder.encode_symbol(&mut state.nir_models[1], diff as u32);
+            }
+            if symbol & 2 != 0 {
+                let diff = ((nir >> 8) as u8).wrapping_sub((last >> 8) as u8);
+                encoder.encode_symbol(&mut state.nir_models[2], diff as u32);
+            }
+            self.nir_layer.changed |= last != nir;
+        }
+
+        state.last = item;
+    }
+
+    fn done(self: Box<Self>) -> Layers {
+        let mut layers = vec![self.rgb_layer.done(false)];
+        if self.has_nir {
+            layers.push(self.nir_layer.done(false));
+        }
+        layers
+    }
+}
+
+#[derive(Clone)]
+struct ExtraBytesCompressorContext {
+    last: Vec<u8>,
+    models: Vec<ArithmeticModel>,
+}
+
+struct ExtraBytes14Compressor {
+    layers: Vec<LayerWriter>,
+    contexts: [Option<ExtraBytesCompressorContext>; 4],
+    current_context: usize,
+}
+
+impl ItemCompressor for ExtraBytes14Compressor {
+    fn init(&mut self, first: &[u8], context: &mut usize) {
+        self.contexts[*context] = Some(ExtraBytesCompressorContext {
+            last: first.to_vec(),
+            models: vec![ArithmeticModel::new(256); first.len()],
+        });
+        self.current_context = *context;
+    }
+
+    fn compress(&mut self, _: &mut ArithmeticEncoder, item: &[u8], context: &mut usize) {
+        if self.contexts[*context].is_none() {
+            let last = self.contexts[self.current_context]
+                .as_ref()
+                .unwrap()
+                .last
+                .clone();
+            self.contexts[*context] = Some(ExtraBytesCompressorContext {
+                models: vec![ArithmeticModel::new(256); last.len()],
+                last,
+            });
+        }
+        self.current_context = *context;
+        let state = self.contexts[*context].as_mut().unwrap();
+
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            let diff = item[i].wrapping_sub(state.last[i]);
+            layer
+                .encoder
+                .encode_symbol(&mut state.models[i], diff as u32);
+            layer.changed |= diff != 0;
+            state.last[i] = item[i];
+        }
+    }
+
+    fn done(self: Box<Self>) -> Layers {
+        self.layers
+            .into_iter()
+            .map(|layer| layer.done(false))
+            .collect()
+    }
+}
+
+struct WavePacket14Compressor {
+    layer: LayerWriter,
+    contexts: [Option<(WavePacket, WavePacketModels)>; 4],
+    current_context: usize,
+}
+
+impl ItemCompressor for WavePacket14Compressor {
+    fn init(&mut self, first: &[u8], context: &mut usize) {
+        self.contexts[*context] = Some((WavePacket::read(first), WavePacketModels::new()));
+        self.current_context = *context;
+    }
+
+    fn compress(&mut self, _: &mut ArithmeticEncoder, item: &[u8], context: &mut usize) {
+        if self.contexts[*context].is_none() {
+            let last = self.contexts[self.current_context].as_ref().unwrap().0;
+            self.contexts[*context] = Some((last, WavePacketModels::new()));
+        }
+        self.current_context = *context;
+        let (last, models) = self.contexts[*context].as_mut().unwrap();
+        let item = WavePacket::read(item);
+
+        models.compress(&mut self.layer.encoder, last, &item);
+        self.layer.changed |= *last != item;
+        *last = item;
+    }
+
+    fn done(self: Box<Self>) -> Layers {
+        vec![self.layer.done(false)]
+    }
+}
+
+fn create_compressor(item: &LazItem) -> Box<dyn ItemCompressor> {
+    let size = item.size as usize;
+    match item.item_type {
+        LazItemType::Point10 => Box::new(Point10Compressor::new()),
+        LazItemType::GpsTime11 => Box::new(GpsTime11Compressor(GpsTimeCompressor::new(0, false))),
+        LazItemType::Rgb12 => Box::new(Rgb12Compressor {
+            last: vec![],
+            models: RgbModels::new(),
+        }),
+        LazItemType::WavePacket13 => Box::new(WavePacket13Compressor {
+            last: WavePacket::default(),
+            models: WavePacketModels::new(),
+        }),
+        LazItemType::Byte => Box::new(ExtraBytesCompressor {
+            last: vec![],
+            models: vec![ArithmeticModel::new(256); size],
+        }),
+        LazItemType::Point14 => Box::new(Point14Compressor {
+            layers: (0..9).map(|_| LayerWriter::new()).collect(),
+            contexts: Default::default(),
+            current_context: 0,
+        }),
+        LazItemType::Rgb14 | LazItemType::RgbNir14 => Box::new(RgbNir14Compressor {
+            has_nir: item.item_type == LazItemType::RgbNir14,
+            rgb_layer: LayerWriter::new(),
+            nir_layer: LayerWriter::new(),
+            contexts: Default::default(),
+            current_context: 0,
+        }),
+        LazItemType::WavePacket14 => Box::new(WavePacket14Compressor {
+            layer: LayerWriter::new(),
+            contexts: Default::default(),
+            current_context: 0,
+        }),
+        LazItemType::Byte14 => Box::new(ExtraBytes14Compressor {
+            layers: (0..size).map(|_| LayerWriter::new()).collect(),
+            contexts: Default::default(),
+            current_context: 0,
+        }),
+    }
+}
+
+/// The items LASzip uses for a point data format, with `extra_bytes` after the standard fields
+pub fn items_for_format(format: u8, extra_bytes: u16) -> Vec<LazItem> {
+    let item = |item_type, size: usize, version| LazItem {
+        item_type,
+        size: size as u16,
+        version,
+    };
+
+    if format <= 5 {
+        let mut items = vec![item(LazItemType::Point10, 20, 2)];
+        if format != 0 && format != 2 {
+            items.push(item(LazItemType::GpsTime11, 8, 2));
+        }
+        if [2, 3, 5].contains(&format) {
+            items.push(item(LazItemType::Rgb12, 6, 2));
+        }
+        if [4, 5].contains(&format) {
+            items.push(item(LazItemType::WavePacket13, 29, 1));
+        }
+        if extra_bytes > 0 {
+            items.push(item(LazItemType::Byte, extra_bytes as usize, 2));
+        }
+        items
+    } else {
+        let mut items = vec![item(LazItemType::Point14, 30, 3)];
+        match format {
+            7 => items.push(item(LazItemType::Rgb14, 6, 3)),
+            8 | 10 => items.push(item(LazItemType::RgbNir14, 8, 3)),
+            _ => {}
+        }
+        if [9, 10].contains(&format) {
+            items.push(item(LazItemType::WavePacket14, 29, 3));
+        }
+        if extra_bytes > 0 {
+            items.push(item(LazItemType::Byte14, extra_bytes as usize, 3));
+        }
+        items
+    }
+}
+
+/// Compresses the point records of one chunk
+pub fn compress_chunk(points: &[u8], items: &[LazItem]) -> Vec<u8> {
+    let point_size: usize = items.iter().map(|item| item.size as usize).sum();
+    let is_layered = items[0].item_type == LazItemType::Point14;
+    let mut compressors: Vec<_> = items.iter().map(create_compressor).collect();
+
+    let mut ranges = vec![];
+    let mut start = 0;
+    for item in items {
+        ranges.push(start..start + item.size as usize);
+        start += item.size as usize;
+    }
+
+    let mut records = points.chunks_exact(point_size);
+    let Some(first) = records.next() else {
+        return vec![];
+    };
+
+    let mut context = 0;
+    for (compressor, range) in compressors.iter_mut().zip(&ranges) {
+        compressor.init(&first[range.clone()], &mut context);
+    }
+
+    let mut encoder = ArithmeticEncoder::new();
+    for record in records {
+        for (compressor, range) in compressors.iter_mut().zip(&ranges) {
+            compressor.compress(&mut encoder, &record[range.clone()], &mut context);
+        }
+    }
+
+    let mut chunk = first.to_vec();
+    if is_layered {
+        let layers: Vec<_> = compressors
+            .into_iter()
+            .flat_map(|compressor| compressor.done())
+            .collect();
+        chunk.extend(((points.len() / point_size) as u32).to_le_bytes());
+        for layer in &layers {
+            let size = layer.as_ref().map_or(0, |layer| layer.len());
+            chunk.extend((size as u32).to_le_bytes());
+        }
+        chunk.extend(layers.into_iter().flatten().flatten());
+    } else {
+        chunk.extend(encoder.done());
+    }
+    chunk
+}
+
+/// A variable length record with its header
+pub fn create_vlr(user_id: &str, record_id: u16, data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0, 0];
+    let mut padded_user_id = [0u8; 16];
+    padded_user_id[..user_id.len()].copy_from_slice(user_id.as_bytes());
+    bytes.extend(padded_user_id);
+    bytes.extend(record_id.to_le_bytes());
+    bytes.extend((data.len() as u16).to_le_bytes());
+    bytes.extend([0u8; 32]);
+    bytes.extend(data);
+    bytes
+}
+
+/// The data of the LASzip VLR for `items`
+pub fn create_laszip_vlr_data(items: &[LazItem], chunk_size: u32) -> Vec<u8> {
+    let is_layered = items[0].item_type == LazItemType::Point14;
+    let mut data = vec![];
+    data.extend((if is_layered { 3u16 } else { 2u16 }).to_le_bytes());
+    data.extend(0u16.to_le_bytes());
+    data.extend([3, 4, 0, 0]);
+    data.extend(0u32.to_le_bytes());
+    data.extend(chunk_size.to_le_bytes());
+    data.extend((-1i64).to_le_bytes());
+    data.extend((-1i64).to_le_bytes());
+    data.extend((items.len() as u16).to_le_bytes());
+    for item in items {
+        let type_id: u16 = match item.item_type {
+            LazItemType::Byte => 0,
+            LazItemType::Point10 => 6,
+            LazItemType::GpsTime11 => 7,
+            LazItemType::Rgb12 => 8,
+            LazItemType::WavePacket13 => 9,
+            LazItemType::Point14 => 10,
+            LazItemType::Rgb14 => 11,
+            LazItemType::RgbNir14 => 12,
+            LazItemType::WavePacket14 => 13,
+            LazItemType::Byte14 => 14,
+        };
+        data.extend(type_id.to_le_bytes());
+        data.extend(item.size.to_le_bytes());
+        data.extend(item.version.to_le_bytes());
+    }
+    data
+}
+
+/// A LAS 1.4 header for compressed points, with a scale of 0.01 and no offset
+pub fn create_las_header(
+    format: u8,
+    point_size: usize,
+    num_points: usize,
+    vlrs: &[Vec<u8>],
+) -> Vec<u8> {
+    let header_size = 375;
+    let offset_to_point_data = header_size + vlrs.iter().map(|vlr| vlr.len()).sum::<usize>();
+    let mut bytes = vec![0u8; header_size];
+    bytes[0..4].copy_from_slice(b"LASF");
+    bytes[24] = 1;
+    bytes[25] = 4;
+    bytes[94..96].copy_from_slice(&(header_size as u16).to_le_bytes());
+    bytes[96..100].copy_from_slice(&(offset_to_point_data as u32).to_le_bytes());
+    bytes[100..104].copy_from_slice(&(vlrs.len() as u32).to_le_bytes());
+    bytes[104] = format | 0x80;
+    bytes[105..107].copy_from_slice(&(point_size as u16).to_le_bytes());
+    bytes[247..255].copy_from_slice(&(num_points as u64).to_le_bytes());
+    for (i, scale) in [0.01f64, 0.01, 0.01].iter().enumerate() {
+        bytes[131 + 8 * i..139 + 8 * i].copy_from_slice(&scale.to_le_bytes());
+    }
+    bytes.extend(vlrs.concat());
+    bytes
+}
+
+/// The chunk table, with the number of points of each chunk if `is_variable`
+pub fn create_chunk_table(
+    chunk_sizes: &[usize],
+    chunk_byte_sizes: &[usize],
+    is_variable: bool,
+) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.extend(0u32.to_le_bytes());
+    bytes.extend((chunk_sizes.len() as u32).to_le_bytes());
+
+    let mut encoder = ArithmeticEncoder::new();
+    let mut compressor = IntegerCompressor::new(32, 2);
+    for i in 0..chunk_sizes.len() {
+        let previous = |values: &[usize]| if i > 0 { values[i - 1] as i32 } else { 0 };
+        if is_variable {
+            compressor.compress(
+                &mut encoder,
+                previous(chunk_sizes),
+                chunk_sizes[i] as i32,
+                0,
+            );
+        }
+        compressor.compress(
+            &mut encoder,
+            previous(chunk_byte_sizes),
+            chunk_byte_sizes[i] as i32,
+            1,
+        );
+    }
+    bytes.extend(encoder.done());
+    bytes
+}
+
+/// Creates a LAS 1.4 file with LAZ compressed points. `chunk_sizes` are the number of points
+/// in each chunk, and a variable chunk size is used when they differ
+pub fn create_laz_file(
+    format: u8,
+    extra_bytes: u16,
+    points: &[u8],
+    chunk_sizes: &[usize],
+) -> Vec<u8> {
+    let items = items_for_format(format, extra_bytes);
+    let point_size: usize = items.iter().map(|item| item.size as usize).sum();
+    let num_points = points.len() / point_size;
+    let is_variable = chunk_sizes.windows(2).any(|pair| pair[0] != pair[1]);
+    let chunk_size = if is_variable {
+        u32::MAX
+    } else {
+        chunk_sizes[0] as u32
+    };
+
+    let vlr = create_vlr(
+        LASZIP_USER_ID,
+        LASZIP_RECORD_ID,
+        &create_laszip_vlr_data(&items, chunk_size),
+    );
+    let mut bytes = create_las_header(format, point_size, num_points, &[vlr]);
+
+    let chunk_table_offset_position = bytes.len();
+    bytes.extend(0i64.to_le_bytes());
+
+    let mut chunk_byte_sizes = vec![];
+    let mut start = 0;
+    for &size in chunk_sizes {
+        let end = (start + size).min(num_points);
+        let chunk = compress_chunk(&points[start * point_size..end * point_size], &items);
+        chunk_byte_sizes.push(chunk.len());
+        bytes.extend(chunk);
+        start = end;
+    }
+
+    let chunk_table_offset = bytes.len() as i64;
+    bytes[chunk_table_offset_position..chunk_table_offset_position + 8]
+        .copy_from_slice(&chunk_table_offset.to_le_bytes());
+    bytes.extend(create_chunk_table(
+        chunk_sizes,
+        &chunk_byte_sizes,
+        is_variable,
+    ));
+
+    bytes
+}
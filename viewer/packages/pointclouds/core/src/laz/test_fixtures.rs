@@ -0,0 +1,190 @@
+//! Comparison of decompressed points against fixtures written by LASzip, whose points were dumped
+//! by PDAL. The fixtures and their dumps are created by `testdata/create_fixtures.sh`
+
+use std::cmp::Ordering;
+
+use crate::las::LasHeader;
+
+/// Record lengths of point data formats 0 to 10, without extra bytes
+const STANDARD_RECORD_LENGTHS: [usize; 11] = [20, 28, 26, 34, 57, 63, 30, 36, 38, 59, 67];
+
+pub fn read_fixture(name: &str) -> Vec<u8> {
+    let path = format!("{}/testdata/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read(&path).unwrap_or_else(|error| panic!("Could not read {}: {}", path, error))
+}
+
+/// Column names and rows of a CSV file written by PDAL's text writer
+fn read_reference_points(name: &str) -> (Vec<String>, Vec<Vec<f64>>) {
+    let text = String::from_utf8(read_fixture(name)).unwrap();
+    let mut lines = text.lines();
+
+    let columns = lines
+        .next()
+        .unwrap()
+        .split(',')
+        .map(|column| column.trim().trim_matches('"').to_string())
+        .collect();
+    let rows = lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split(',')
+                .map(|value| value.trim().parse().unwrap())
+                .collect()
+        })
+        .collect();
+
+    (columns, rows)
+}
+
+fn read_u16(record: &[u8], offset: usize) -> f64 {
+    u16::from_le_bytes(record[offset..offset + 2].try_into().unwrap()) as f64
+}
+
+/// Fields of a point record by their PDAL dimension names, with scale and offset applied to the
+/// position. The extra bytes are read as a single `u32` named `Extra`
+fn get_point_fields(header: &LasHeader, record: &[u8]) -> Vec<(&'static str, f64)> {
+    let format = header.format_id();
+    let is_legacy = format < 6;
+    let coordinate = |axis: usize| {
+        let value = i32::from_le_bytes(record[4 * axis..4 * axis + 4].try_into().unwrap());
+        value as f64 * header.scale[axis] + header.offset[axis]
+    };
+
+    let mut fields = vec![
+        ("X", coordinate(0)),
+        ("Y", coordinate(1)),
+        ("Z", coordinate(2)),
+        ("Intensity", read_u16(record, 12)),
+    ];
+    if is_legacy {
+        fields.extend([
+            ("ReturnNumber", (record[14] & 0x07) as f64),
+            ("NumberOfReturns", ((record[14] >> 3) & 0x07) as f64),
+            ("Classification", (record[15] & 0x1F) as f64),
+            ("PointSourceId", read_u16(record, 18)),
+        ]);
+    } else {
+        fields.extend([
+            ("ReturnNumber", (record[14] & 0x0F) as f64),
+            ("NumberOfReturns", (record[14] >> 4) as f64),
+            ("Classification", record[16] as f64),
+            ("PointSourceId", read_u16(record, 20)),
+        ]);
+    }
+
+    let gps_time_offset = match format {
+        1 | 3 | 4 | 5 => Some(20),
+        6..=10 => Some(22),
+        _ => None,
+    };
+    if let Some(offset) = gps_time_offset {
+        let gps_time = f64::from_le_bytes(record[offset..offset + 8].try_into().unwrap());
+        fields.push(("GpsTime", gps_time));
+    }
+
+    let rgb_offset = match format {
+        2 => Some(20),
+        3 | 5 => Some(28),
+        7 | 8 | 10 => Some(30),
+        _ => None,
+    };
+    if let Some(offset) = rgb_offset {
+        fields.extend([
+            ("Red", read_u16(record, offset)),
+            ("Green", read_u16(record, offset + 2)),
+            ("Blue", read_u16(record, offset + 4)),
+        ]);
+    }
+    if format == 8 || format == 10 {
+        fields.push(("Infrared", read_u16(record, 36)));
+    }
+
+    let standard_length = STANDARD_RECORD_LENGTHS[format as usize];
+    if record.len() >= standard_length + 4 {
+        let extra = &record[standard_length..standard_length + 4];
+        fields.push((
+            "Extra",
+            u32::from_le_bytes(extra.try_into().unwrap()) as f64,
+        ));
+    }
+
+    fields
+}
+
+fn compare_rows(a: &[f64], b: &[f64]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| a.total_cmp(b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Checks the decompressed records against the points PDAL dumped to the CSV file `name`, on the
+/// fields found in both. Records of formats without a field PDAL dumps leave that field out.
+/// Points are compared in file order, or sorted when `is_ordered` is false, for files like COPC
+/// where the point order depends on how the nodes are read
+pub fn assert_points_match_reference(
+    header: &LasHeader,
+    records: &[u8],
+    name: &str,
+    is_ordered: bool,
+) {
+    let (columns, reference_rows) = read_reference_points(name);
+    let point_fields: Vec<_> = records
+        .chunks_exact(header.point_record_length as usize)
+        .map(|record| get_point_fields(header, record))
+        .collect();
+    assert_eq!(point_fields.len(), reference_rows.len(), "{}", name);
+
+    let compared_fields: Vec<(&str, usize)> = point_fields[0]
+        .iter()
+        .filter_map(|(field, _)| {
+            let column = columns.iter().position(|column| column == field)?;
+            Some((*field, column))
+        })
+        .collect();
+    assert!(
+        compared_fields.len() >= 8,
+        "{} has too few known columns",
+        name
+    );
+
+    let mut rows: Vec<Vec<f64>> = point_fields
+        .iter()
+        .map(|fields| {
+            compared_fields
+                .iter()
+                .map(|(field, _)| fields.iter().find(|(name, _)| name == field).unwrap().1)
+                .collect()
+        })
+        .collect();
+    let mut expected_rows: Vec<Vec<f64>> = reference_rows
+        .iter()
+        .map(|row| {
+            compared_fields
+                .iter()
+                .map(|&(_, column)| row[column])
+                .collect()
+        })
+        .collect();
+    if !is_ordered {
+        rows.sort_by(|a, b| compare_rows(a, b));
+        expected_rows.sort_by(|a, b| compare_rows(a, b));
+    }
+
+    for (index, (row, expected_row)) in rows.iter().zip(&expected_rows).enumerate() {
+        for ((field, _), (value, expected)) in
+            compared_fields.iter().zip(row.iter().zip(expected_row))
+        {
+            assert!(
+                (value - expected).abs() <= 1e-6 * expected.abs().max(1.0),
+                "{}: point {} has {} {}, PDAL dumped {}",
+                name,
+                index,
+                field,
+                value,
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Wave packet descriptors of point data formats 4, 5, 9 and 10
+
+use super::arithmetic_decoder::{ArithmeticDecoder, ArithmeticModel};
+use super::integer_decompressor::IntegerDecompressor;
+use super::{ByteReader, LayerReader, LayeredItemDecompressor, PointwiseItemDecompressor};
+
+pub const WAVEPACKET_SIZE: usize = 29;
+
+/// Descriptor index, byte offset to the waveform data, packet size, return point location and
+/// the x, y and z parameters of the waveform. The floats are kept as their bits
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WavePacket {
+    pub descriptor_index: u8,
+    pub offset: u64,
+    pub packet_size: u32,
+    pub return_point: i32,
+    pub xyz: [i32; 3],
+}
+
+impl WavePacket {
+    pub fn read(bytes: &[u8]) -> WavePacket {
+        let i32_at =
+            |offset: usize| i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        WavePacket {
+            descriptor_index: bytes[0],
+            offset: u64::from_le_bytes(bytes[1..9].try_into().unwrap()),
+            packet_size: i32_at(9) as u32,
+            return_point: i32_at(13),
+            xyz: [i32_at(17), i32_at(21), i32_at(25)],
+        }
+    }
+
+    pub fn write(&self, bytes: &mut [u8]) {
+        bytes[0] = self.descriptor_index;
+        bytes[1..9].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[9..13].copy_from_slice(&self.packet_size.to_le_bytes());
+        bytes[13..17].copy_from_slice(&self.return_point.to_le_bytes());
+        for (i, value) in self.xyz.iter().enumerate() {
+            bytes[17 + 4 * i..21 + 4 * i].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+#[derive(Clone)]
+struct WavePacketModels {
+    last_diff_32: i32,
+    last_offset_diff_symbol: usize,
+    packet_index: ArithmeticModel,
+    offset_diff: [ArithmeticModel; 4],
+    ic_offset_diff: IntegerDecompressor,
+    ic_packet_size: IntegerDecompressor,
+    ic_return_point: IntegerDecompressor,
+    ic_xyz: IntegerDecompressor,
+}
+
+impl WavePacketModels {
+    fn new() -> WavePacketModels {
+        WavePacketModels {
+            last_diff_32: 0,
+            last_offset_diff_symbol: 0,
+            packet_index: ArithmeticModel::new(256),
+            offset_diff: std::array::from_fn(|_| ArithmeticModel::new(4)),
+            ic_offset_diff: IntegerDecompressor::new(32, 1),
+            ic_packet_size: IntegerDecompressor::new(32, 1),
+            ic_return_point: IntegerDecompressor::new(32, 1),
+            ic_xyz: IntegerDecompressor::new(32, 3),
+        }
+    }
+
+    fn decompress(&mut self, decoder: &mut ArithmeticDecoder, last: &WavePacket) -> WavePacket {
+        let descriptor_index = decoder.decode_symbol(&mut self.packet_index) as u8;
+
+        self.last_offset_diff_symbol =
+            decoder.decode_symbol(&mut self.offset_diff[self.last_offset_diff_symbol]) as usize;
+        let offset = match self.last_offset_diff_symbol {
+            0 => last.offset,
+            1 => last.offset.wrapping_add(last.packet_size as u64),
+            2 => {
+                self.last_diff_32 = self
+                    .ic_offset_diff
+                    .decompress(decoder, self.last_diff_32, 0);
+                last.offset.wrapping_add(self.last_diff_32 as i64 as u64)
+            }
+            _ => decoder.read_int64(),
+        };
+
+        let packet_size = self
+            .ic_packet_size
+            .decompress(decoder, last.packet_size as i32, 0) as u32;
+        let return_point = self
+            .ic_return_point
+            .decompress(decoder, last.return_point, 0);
+        let xyz = std::array::from_fn(|i| self.ic_xyz.decompress(decoder, last.xyz[i], i as u32));
+
+        WavePacket {
+            descriptor_index,
+            offset,
+            packet_size,
+            return_point,
+            xyz,
+        }
+    }
+}
+
+/// Version 1 of the WAVEPACKET13 item
+pub struct WavePacket13Decompressor {
+    last: WavePacket,
+    models: WavePacketModels,
+}
+
+impl WavePacket13Decompressor {
+    pub fn new() -> WavePacket13Decompressor {
+        WavePacket13Decompressor {
+            last: WavePacket::default(),
+            models: WavePacketModels::new(),
+        }
+    }
+}
+
+impl PointwiseItemDecompressor for WavePacket13Decompressor {
+    fn init(&mut self, first: &[u8]) {
+        self.last = WavePacket::read(first);
+    }
+
+    fn decompress(&mut self, decoder: &mut ArithmeticDecoder, item: &mut [u8]) {
+        self.last = self.models.decompress(decoder, &self.last);
+        self.last.write(item);
+    }
+}
+
+#[derive(Clone)]
+struct WavePacketContext {
+    last: WavePacket,
+    models: WavePacketModels,
+}
+
+/// Version 3 of the WAVEPACKET14 item
+pub struct WavePacket14Decompressor<'a> {
+    layer: LayerReader<'a>,
+    contexts: [Option<WavePacketContext>; 4],
+    current_context: usize,
+}
+
+impl<'a> WavePacket14Decompressor<'a> {
+    pub fn new() -> WavePacket14Decompressor<'a> {
+        WavePacket14Decompressor {
+            layer: LayerReader::default(),
+            contexts: Default::default(),
+            current_context: 0,
+        }
+    }
+}
+
+impl<'a> LayeredItemDecompressor<'a> for WavePacket14Decompressor<'a> {
+    fn read_layer_sizes(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String> {
+        self.layer.size = reader.read_u32()? as usize;
+        Ok(())
+    }
+
+    fn read_layers(&mut self, reader: &mut ByteReader<'a>) -> Result<(), String> {
+        self.layer.read(reader)
+    }
+
+    fn init(&mut self, first: &[u8], context: &mut usize) {
+        let context = *context;
+        self.contexts = Default::default();
+        self.contexts[context] = Some(WavePacketContext {
+            last: WavePacket::read(first),
+            models: WavePacketModels::new(),
+        });
+        self.current_context = context;
+    }
+
+    fn decompress(&mut self, item: &mut [u8], context: &mut usize) {
+        let context = *context;
+        if self.contexts[context].is_none() {
+            let last = self.contexts[self.current_context]
+                .as_ref()
+                .map_or_else(WavePacket::default, |current| current.last);
+            self.contexts[context] = Some(WavePacketContext {
+                last,
+                models: WavePacketModels::new(),
+            });
+        }
+        self.current_context = context;
+        let state = self.contexts[context].as_mut().unwrap();
+
+        if let Some(decoder) = &mut self.layer.decoder {
+            state.last = state.models.decompress(decoder, &state.last);
+        }
+        state.last.write(item);
+    }
+}
@@ -0,0 +1,23 @@
+/*!
+ * Copyright 2022 Cognite AS
+ */
+
+//! Geometry, point octree and point assignment code for point clouds, without any dependency on
+//! a particular platform. `pointclouds-wasm` exposes it to JavaScript
+
+pub mod assignment;
+pub mod bvh;
+pub mod clip_volume;
+pub mod copc;
+pub mod ept;
+pub mod inflation;
+pub mod inputs;
+pub mod las;
+pub mod las_export;
+pub mod laz;
+pub mod linalg;
+pub mod object_id_width;
+pub mod overlap_policy;
+pub mod point_memberships;
+pub mod point_octree;
+pub mod shapes;
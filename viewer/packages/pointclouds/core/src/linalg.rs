@@ -0,0 +1,208 @@
+use nalgebra_glm::{dot, mat4_to_mat3, max2, min2, vec3, vec4, vec4_to_vec3, DMat4, DVec3, DVec4};
+
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingBox {
+    pub min: DVec3,
+    pub max: DVec3,
+}
+
+/// The plane of points `p` with `dot(normal, p) == offset`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: DVec3,
+    pub offset: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vec3WithIndex {
+    pub vec: DVec3,
+    pub index: usize,
+}
+
+impl BoundingBox {
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.min.x < other.max.x
+            && self.max.x > other.min.x
+            && self.min.y < other.max.y
+            && self.max.y > other.min.y
+            && self.min.z < other.max.z
+            && self.max.z > other.min.z
+    }
+
+    pub fn add_point(&mut self, point: &DVec3) {
+        self.min = min2(&self.min, point);
+        self.max = max2(&self.max, point);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        BoundingBox {
+            min: min2(&self.min, &other.min),
+            max: max2(&self.max, &other.max),
+        }
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        BoundingBox {
+            min: max2(&self.min, &other.min),
+            max: min2(&self.max, &other.max),
+        }
+    }
+
+    pub fn center(&self) -> DVec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn extent(&self) -> DVec3 {
+        self.max - self.min
+    }
+
+    pub fn contains_point(&self, point: &DVec3) -> bool {
+        (0..3).all(|axis| self.min[axis] <= point[axis] && point[axis] <= self.max[axis])
+    }
+
+    /// Zero for points inside the box
+    pub fn distance_to_point(&self, point: &DVec3) -> f64 {
+        let below = self.min - point;
+        let above = point - self.max;
+        max2(&max2(&below, &above), &DVec3::zeros()).magnitude()
+    }
+
+    /// Slab test for the ray `origin + t * direction` with `t >= 0`
+    pub fn intersects_ray(&self, origin: &DVec3, direction: &DVec3) -> bool {
+        let mut t_min: f64 = 0.0;
+        let mut t_max = f64::INFINITY;
+
+        for axis in 0..3 {
+            let inv_direction = 1.0 / direction[axis];
+            let t_0 = (self.min[axis] - origin[axis]) * inv_direction;
+            let t_1 = (self.max[axis] - origin[axis]) * inv_direction;
+
+            t_min = t_min.max(t_0.min(t_1));
+            t_max = t_max.min(t_0.max(t_1));
+        }
+
+        t_min <= t_max
+    }
+
+    pub fn get_base_cube_corner(corner_index: u32) -> DVec4 {
+        vec4(
+            if (corner_index & 1) == 0 { -1.0 } else { 1.0 },
+            if (corner_index & 2) == 0 { -1.0 } else { 1.0 },
+            if (corner_index & 4) == 0 { -1.0 } else { 1.0 },
+            1.0,
+        )
+    }
+
+    pub fn get_transformed_base_cube(matrix: &DMat4) -> Self {
+        (0..8)
+            .map(|i: u32| {
+                let unit_corner = BoundingBox::get_base_cube_corner(i);
+                let transformed_corner = matrix * unit_corner;
+                vec4_to_vec3(&transformed_corner)
+            })
+            .collect()
+    }
+
+    pub fn get_base_cube_bounding_box() -> Self {
+        let points = (0..8).map(|i| vec4_to_vec3(&BoundingBox::get_base_cube_corner(i)));
+        let min_point: DVec3 = points.clone().reduce(|v0, v1| min2(&v0, &v1)).unwrap();
+        let max_point: DVec3 = points.reduce(|v0, v1| max2(&v0, &v1)).unwrap();
+        BoundingBox {
+            min: min_point,
+            max: max_point,
+        }
+    }
+
+    pub fn infinite() -> Self {
+        BoundingBox {
+            min: vec3(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            max: vec3(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        }
+    }
+}
+
+impl Plane {
+    pub fn new(normal: DVec3, offset: f64) -> Self {
+        let length = normal.magnitude();
+        Plane {
+            normal: normal / length,
+            offset: offset / length,
+        }
+    }
+
+    pub fn from_points(a: &DVec3, b: &DVec3, c: &DVec3) -> Self {
+        let normal = (b - a).cross(&(c - a)).normalize();
+        Plane {
+            normal,
+            offset: dot(&normal, a),
+        }
+    }
+
+    /// Positive on the side the normal points to
+    pub fn signed_distance(&self, point: &DVec3) -> f64 {
+        dot(&self.normal, point) - self.offset
+    }
+}
+
+pub fn closest_point_on_segment(point: &DVec3, start: &DVec3, end: &DVec3) -> DVec3 {
+    let segment = end - start;
+    let length_squared = segment.magnitude_squared();
+
+    if length_squared == 0.0 {
+        return *start;
+    }
+
+    let t = (dot(&(point - start), &segment) / length_squared).clamp(0.0, 1.0);
+    start + segment * t
+}
+
+/// Lengths of the axes of the instance whose inverse is `inv_instance_matrix`, which equals the
+/// half extents of the transformed base cube when the axes are orthogonal
+pub fn get_instance_half_extents(inv_instance_matrix: &DMat4) -> DVec3 {
+    let inv_matrix = mat4_to_mat3(inv_instance_matrix);
+    vec3(
+        1.0 / inv_matrix.row(0).magnitude(),
+        1.0 / inv_matrix.row(1).magnitude(),
+        1.0 / inv_matrix.row(2).magnitude(),
+    )
+}
+
+/// Signed distance to a box centered at the origin with the given half extents, negative inside
+pub fn signed_distance_to_box(point: &DVec3, half_extents: &DVec3) -> f64 {
+    let q = point.abs() - half_extents;
+    max2(&q, &DVec3::zeros()).magnitude() + q.max().min(0.0)
+}
+
+/// Signed distance to a shape extruded along an axis, given the signed distance to its cross
+/// section and the signed distance along the axis to the range it is extruded over
+pub fn signed_distance_to_extrusion(cross_section_distance: f64, axial_distance: f64) -> f64 {
+    let outside_cross_section = cross_section_distance.max(0.0);
+    let outside_axial = axial_distance.max(0.0);
+
+    cross_section_distance.max(axial_distance).min(0.0)
+        + (outside_cross_section * outside_cross_section + outside_axial * outside_axial).sqrt()
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        BoundingBox {
+            min: vec3(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: vec3(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+}
+
+impl FromIterator<DVec3> for BoundingBox {
+    fn from_iter<T>(point_iter: T) -> Self
+    where
+        T: IntoIterator<Item = DVec3>,
+    {
+        let mut bounding_box: BoundingBox = Default::default();
+
+        point_iter
+            .into_iter()
+            .for_each(|p| bounding_box.add_point(&p));
+
+        bounding_box
+    }
+}
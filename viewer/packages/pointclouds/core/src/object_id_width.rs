@@ -0,0 +1,47 @@
+use serde::Deserialize;
+
+/// Element type of the object id arrays returned to JavaScript. Object ids are `u32` internally
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectIdWidth {
+    /// Object ids are returned in a `Uint16Array`
+    #[default]
+    U16,
+    /// Object ids are returned in a `Uint32Array`
+    U32,
+}
+
+impl ObjectIdWidth {
+    pub fn max_object_id(self) -> u32 {
+        match self {
+            ObjectIdWidth::U16 => u16::MAX as u32,
+            ObjectIdWidth::U32 => u32::MAX,
+        }
+    }
+
+    pub fn check_object_id(self, object_id: u32) -> Result<(), String> {
+        if object_id > self.max_object_id() {
+            return Err(format!(
+                "Object id {} does not fit in the chosen object id width {:?}, the maximum is {}",
+                object_id,
+                self,
+                self.max_object_id()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ObjectIdWidth;
+
+    #[test]
+    fn ids_above_16_bits_are_rejected_for_u16_only() {
+        assert!(ObjectIdWidth::U16.check_object_id(65_535).is_ok());
+        assert!(ObjectIdWidth::U16.check_object_id(65_536).is_err());
+        assert!(ObjectIdWidth::U32.check_object_id(65_536).is_ok());
+        assert!(ObjectIdWidth::U32.check_object_id(u32::MAX).is_ok());
+    }
+}
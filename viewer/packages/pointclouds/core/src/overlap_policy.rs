@@ -0,0 +1,228 @@
+use serde::Deserialize;
+
+use crate::inputs::ParsedObject;
+use crate::linalg::Vec3WithIndex;
+
+/// Decides which object a point is assigned to when it is inside several shapes
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// The object that comes first in the input wins
+    FirstWins,
+    /// The object that comes last in the input wins
+    #[default]
+    LastWins,
+    /// The object with the highest `priority` wins
+    Priority,
+    /// The object with the smallest shape volume wins
+    SmallestVolume,
+    /// The object the point is deepest inside wins, measured from the center line for swept
+    /// shapes like pipes. See `Shape::get_distance_to_core`
+    NearestCenter,
+}
+
+/// Collects every (point, object) containment and keeps the winner for each point. Candidates are
+/// ranked by a score where lower is better, with ties going to the object that comes first in the
+/// input, so the result does not depend on the order candidates are offered in
+pub struct OverlapResolver<'a> {
+    policy: OverlapPolicy,
+    objects: &'a [ParsedObject],
+    object_scores: Vec<f64>,
+    winners: Vec<Option<(f64, usize)>>,
+}
+
+impl<'a> OverlapResolver<'a> {
+    pub fn new(policy: OverlapPolicy, objects: &'a [ParsedObject], num_points: usize) -> Self {
+        let object_scores = objects
+            .iter()
+            .enumerate()
+            .map(|(index, object)| match policy {
+                OverlapPolicy::FirstWins => index as f64,
+                OverlapPolicy::LastWins => -(index as f64),
+                OverlapPolicy::Priority => -(object.priority as f64),
+                OverlapPolicy::SmallestVolume => object.shape.get_volume(),
+                OverlapPolicy::NearestCenter => 0.0,
+            })
+            .collect();
+
+        OverlapResolver {
+            policy,
+            objects,
+            object_scores,
+            winners: vec![None; num_points],
+        }
+    }
+
+    /// Registers that `point` is inside the shape of `objects[object_index]`
+    pub fn offer(&mut self, point: &Vec3WithIndex, object_index: usize) {
+        let score = match self.policy {
+            OverlapPolicy::NearestCenter => self.objects[object_index]
+                .shape
+                .get_distance_to_core(&point.vec),
+            _ => self.object_scores[object_index],
+        };
+        // NaN compares false with everything, which would let the first object offered win
+        let score = if score.is_nan() { f64::INFINITY } else { score };
+
+        let candidate = (score, object_index);
+        let winner = &mut self.winners[point.index];
+
+        let is_better = match winner {
+            Some(current) => {
+                candidate.0 < current.0 || (candidate.0 == current.0 && candidate.1 < current.1)
+            }
+            None => true,
+        };
+
+        if is_better {
+            *winner = Some(candidate);
+        }
+    }
+
+    pub fn get_winning_object_index(&self, point_index: usize) -> Option<usize> {
+        self.winners[point_index].map(|(_, object_index)| object_index)
+    }
+
+    /// The object id of the winner for each point, or zero for points outside all shapes
+    pub fn get_object_ids(&self) -> Vec<u32> {
+        self.winners
+            .iter()
+            .map(|winner| match winner {
+                Some((_, object_index)) => self.objects[*object_index].shape.get_object_id(),
+                None => 0,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{vec3, DMat4};
+
+    use super::{OverlapPolicy, OverlapResolver};
+    use crate::inputs::ParsedObject;
+    use crate::linalg::Vec3WithIndex;
+    use crate::shapes::{Cylinder, HalfSpace, OrientedBox, Sphere};
+
+    fn create_overlapping_objects() -> Vec<ParsedObject> {
+        vec![
+            ParsedObject {
+                shape: Box::new(Sphere::new(vec3(0.0, 0.0, 0.0), 2.0, 1)),
+                priority: 5,
+                uninflated_shape: None,
+            },
+            ParsedObject {
+                shape: Box::new(Sphere::new(vec3(1.0, 0.0, 0.0), 1.0, 2)),
+                priority: 0,
+                uninflated_shape: None,
+            },
+            ParsedObject {
+                shape: Box::new(Sphere::new(vec3(0.5, 0.0, 0.0), 1.5, 3)),
+                priority: 5,
+                uninflated_shape: None,
+            },
+        ]
+    }
+
+    fn resolve_in_order(policy: OverlapPolicy, order: &[usize]) -> Vec<u32> {
+        let objects = create_overlapping_objects();
+        let point = Vec3WithIndex {
+            vec: vec3(0.9, 0.0, 0.0),
+            index: 0,
+        };
+
+        let mut resolver = OverlapResolver::new(policy, &objects, 1);
+        for object_index in order {
+            resolver.offer(&point, *object_index);
+        }
+
+        resolver.get_object_ids()
+    }
+
+    fn assert_winner_is_independent_of_order(policy: OverlapPolicy, expected_object_id: u32) {
+        for order in [[0, 1, 2], [2, 1, 0], [1, 0, 2]] {
+            assert_eq!(resolve_in_order(policy, &order), vec![expected_object_id]);
+        }
+    }
+
+    #[test]
+    fn first_and_last_wins_refer_to_input_order() {
+        assert_winner_is_independent_of_order(OverlapPolicy::FirstWins, 1);
+        assert_winner_is_independent_of_order(OverlapPolicy::LastWins, 3);
+    }
+
+    #[test]
+    fn priority_ties_go_to_first_object() {
+        assert_winner_is_independent_of_order(OverlapPolicy::Priority, 1);
+    }
+
+    #[test]
+    fn smallest_volume_wins() {
+        assert_winner_is_independent_of_order(OverlapPolicy::SmallestVolume, 2);
+    }
+
+    #[test]
+    fn nearest_center_ties_go_to_first_object() {
+        // The point is 1.1 deep inside the first and third spheres, and 0.9 inside the second
+        assert_winner_is_independent_of_order(OverlapPolicy::NearestCenter, 1);
+    }
+
+    #[test]
+    fn nearest_center_is_independent_of_order_with_half_space() {
+        let objects = vec![
+            ParsedObject {
+                shape: Box::new(HalfSpace::new(vec3(0.0, 0.0, 1.0), 0.0, 1)),
+                priority: 0,
+                uninflated_shape: None,
+            },
+            ParsedObject {
+                shape: Box::new(Sphere::new(vec3(0.0, 0.0, -0.5), 1.0, 2)),
+                priority: 0,
+                uninflated_shape: None,
+            },
+            ParsedObject {
+                shape: Box::new(OrientedBox::new(DMat4::identity(), 3)),
+                priority: 0,
+                uninflated_shape: None,
+            },
+            ParsedObject {
+                shape: Box::new(Cylinder::new(
+                    vec3(-10.0, 0.0, -0.2),
+                    vec3(0.05, 0.0, -0.2),
+                    0.3,
+                    4,
+                )),
+                priority: 0,
+                uninflated_shape: None,
+            },
+        ];
+        let point = Vec3WithIndex {
+            vec: vec3(0.0, 0.0, -0.2),
+            index: 0,
+        };
+
+        // Depths: 0.2 below the half-space, 0.7 inside the sphere, 0.8 inside the box and 0.3
+        // from the cylinder axis, although the point is only 0.05 from its end cap
+        for order in [[0, 1, 2, 3], [3, 2, 1, 0], [1, 3, 0, 2], [2, 0, 3, 1]] {
+            let mut resolver = OverlapResolver::new(OverlapPolicy::NearestCenter, &objects, 1);
+            for object_index in order {
+                resolver.offer(&point, object_index);
+            }
+
+            assert_eq!(resolver.get_object_ids(), vec![3]);
+        }
+
+        let mut resolver = OverlapResolver::new(OverlapPolicy::NearestCenter, &objects[..2], 1);
+        resolver.offer(&point, 0);
+        resolver.offer(&point, 1);
+        assert_eq!(resolver.get_object_ids(), vec![2]);
+    }
+
+    #[test]
+    fn points_without_candidates_get_zero() {
+        let objects = create_overlapping_objects();
+        let resolver = OverlapResolver::new(OverlapPolicy::FirstWins, &objects, 3);
+
+        assert_eq!(resolver.get_object_ids(), vec![0, 0, 0]);
+    }
+}
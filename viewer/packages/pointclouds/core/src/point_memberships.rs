@@ -0,0 +1,82 @@
+/// Every object each point belongs to, in compressed sparse row layout. The object ids of point
+/// `i` are `object_ids[offsets[i]..offsets[i + 1]]`, ordered as the objects were given
+pub struct PointMemberships {
+    pub offsets: Vec<u32>,
+    pub object_ids: Vec<u32>,
+}
+
+impl PointMemberships {
+    /// Builds the memberships from `(point_index, object_id)` pairs. Pairs must be ordered by
+    /// object, and repeated object ids for the same point are only reported once
+    pub fn from_pairs(num_points: usize, pairs: &[(usize, u32)]) -> Self {
+        let mut counts = vec![0u32; num_points];
+        for (point_index, _) in pairs {
+            counts[*point_index] += 1;
+        }
+
+        let mut offsets = Vec::with_capacity(num_points + 1);
+        offsets.push(0);
+        for count in counts {
+            offsets.push(offsets.last().unwrap() + count);
+        }
+
+        // Stable counting sort keeps the object order within each point
+        let mut slots = vec![0u32; pairs.len()];
+        let mut lengths = vec![0u32; num_points];
+        for (point_index, object_id) in pairs {
+            let start = offsets[*point_index] as usize;
+            let length = lengths[*point_index] as usize;
+
+            if !slots[start..start + length].contains(object_id) {
+                slots[start + length] = *object_id;
+                lengths[*point_index] += 1;
+            }
+        }
+
+        let mut compacted_offsets = Vec::with_capacity(num_points + 1);
+        let mut object_ids = Vec::with_capacity(pairs.len());
+        compacted_offsets.push(0);
+        for (point_index, length) in lengths.iter().enumerate() {
+            let start = offsets[point_index] as usize;
+            object_ids.extend_from_slice(&slots[start..start + *length as usize]);
+            compacted_offsets.push(object_ids.len() as u32);
+        }
+
+        PointMemberships {
+            offsets: compacted_offsets,
+            object_ids,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::PointMemberships;
+
+    #[test]
+    fn memberships_are_grouped_by_point_in_object_order() {
+        let pairs = [(2, 7), (0, 7), (2, 3), (0, 1), (3, 1)];
+        let memberships = PointMemberships::from_pairs(4, &pairs);
+
+        assert_eq!(memberships.offsets, vec![0, 2, 2, 4, 5]);
+        assert_eq!(memberships.object_ids, vec![7, 1, 7, 3, 1]);
+    }
+
+    #[test]
+    fn repeated_object_ids_are_reported_once() {
+        let pairs = [(1, 5), (0, 2), (1, 5), (1, 6)];
+        let memberships = PointMemberships::from_pairs(2, &pairs);
+
+        assert_eq!(memberships.offsets, vec![0, 1, 3]);
+        assert_eq!(memberships.object_ids, vec![2, 5, 6]);
+    }
+
+    #[test]
+    fn no_pairs_gives_empty_rows() {
+        let memberships = PointMemberships::from_pairs(3, &[]);
+
+        assert_eq!(memberships.offsets, vec![0, 0, 0, 0]);
+        assert!(memberships.object_ids.is_empty());
+    }
+}
@@ -0,0 +1,9 @@
+mod octree_node;
+pub mod point_neighbour;
+#[allow(clippy::module_inception)]
+pub mod point_octree;
+pub mod ray_pick;
+
+pub use point_neighbour::*;
+pub use point_octree::*;
+pub use ray_pick::*;
@@ -0,0 +1,534 @@
+use crate::linalg::{BoundingBox, Vec3WithIndex};
+
+pub const MAX_POINTS_PER_NODE: usize = 1_000;
+pub const MIN_OCTREE_NODE_SIZE: f64 = 0.0625;
+
+use std::collections::BinaryHeap;
+use std::ops::Range;
+
+use nalgebra_glm::DVec3;
+
+use crate::bvh::Bvh;
+use crate::clip_volume::{BoxClassification, ClipVolume};
+use crate::shapes::Shape;
+
+use super::{PickRay, PointNeighbour, RayPickHit};
+
+#[derive(Debug)]
+enum OctreeNodeContent {
+    Children(Box<[OctreeNode; 8]>),
+    Points(Range<usize>),
+}
+
+/// Node of an octree over a point array that is owned elsewhere. Leaves refer to their points by
+/// a range into that array, so every query takes the array as an argument
+#[derive(Debug)]
+pub struct OctreeNode {
+    content: OctreeNodeContent,
+    bounding_box: BoundingBox,
+}
+
+impl OctreeNode {
+    /// Reorders `points[range]` so that the points of each leaf are contiguous
+    pub fn new(
+        bounding_box: BoundingBox,
+        points: &mut [Vec3WithIndex],
+        range: Range<usize>,
+    ) -> OctreeNode {
+        if range.len() <= MAX_POINTS_PER_NODE
+            || bounding_box.max.x - bounding_box.min.x < MIN_OCTREE_NODE_SIZE
+        {
+            OctreeNode {
+                content: OctreeNodeContent::Points(range),
+                bounding_box,
+            }
+        } else {
+            let children = split(points, range, bounding_box);
+            OctreeNode {
+                content: OctreeNodeContent::Children(children),
+                bounding_box,
+            }
+        }
+    }
+
+    pub fn assign_object_ids(
+        &self,
+        points: &[Vec3WithIndex],
+        bounding_box: &BoundingBox,
+        shape: &dyn Shape,
+        object_ids: &mut [u32],
+    ) {
+        self.for_each_point_in_shape(points, bounding_box, shape, &mut |point| {
+            object_ids[point.index] = shape.get_object_id();
+        });
+    }
+
+    pub fn for_each_point_in_shape(
+        &self,
+        points: &[Vec3WithIndex],
+        bounding_box: &BoundingBox,
+        shape: &dyn Shape,
+        visitor: &mut impl FnMut(&Vec3WithIndex),
+    ) {
+        self.for_each_point_where(
+            points,
+            bounding_box,
+            &|point| shape.contains_point(point),
+            visitor,
+        );
+    }
+
+    /// Calls `visitor` for the points for which `predicate` holds, only visiting nodes that
+    /// overlap `bounding_box`
+    pub fn for_each_point_where(
+        &self,
+        points: &[Vec3WithIndex],
+        bounding_box: &BoundingBox,
+        predicate: &impl Fn(&DVec3) -> bool,
+        visitor: &mut impl FnMut(&Vec3WithIndex),
+    ) {
+        match &self.content {
+            OctreeNodeContent::Children(children) => children.iter().for_each(|child| {
+                if child.bounding_box.overlaps(bounding_box) {
+                    child.for_each_point_where(points, bounding_box, predicate, visitor);
+                }
+            }),
+            OctreeNodeContent::Points(range) => points[range.clone()].iter().for_each(|point| {
+                if predicate(&point.vec) {
+                    visitor(point);
+                }
+            }),
+        }
+    }
+
+    /// Calls `visitor` with every point and each item of `bvh` that `contains` it. Only the items
+    /// below `frontier` are considered, and the frontier is narrowed down to this node first
+    pub fn for_each_point_in_bvh_items(
+        &self,
+        points: &[Vec3WithIndex],
+        bvh: &Bvh,
+        frontier: &[usize],
+        contains: &impl Fn(usize, &DVec3) -> bool,
+        visitor: &mut impl FnMut(&Vec3WithIndex, usize),
+    ) {
+        let frontier = bvh.refine_frontier(frontier, &self.bounding_box);
+        if frontier.is_empty() {
+            return;
+        }
+
+        match &self.content {
+            OctreeNodeContent::Children(children) => children.iter().for_each(|child| {
+                child.for_each_point_in_bvh_items(points, bvh, &frontier, contains, visitor)
+            }),
+            OctreeNodeContent::Points(range) => {
+                let items = bvh.get_items_overlapping(&frontier, &self.bounding_box);
+
+                // Testing one item at a time against all points keeps the shape tests predictable
+                for item in items {
+                    for point in &points[range.clone()] {
+                        if contains(item, &point.vec) {
+                            visitor(point, item);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn for_each_point(
+        &self,
+        points: &[Vec3WithIndex],
+        visitor: &mut impl FnMut(&Vec3WithIndex),
+    ) {
+        match &self.content {
+            OctreeNodeContent::Children(children) => children
+                .iter()
+                .for_each(|child| child.for_each_point(points, visitor)),
+            OctreeNodeContent::Points(range) => points[range.clone()].iter().for_each(visitor),
+        }
+    }
+
+    /// Calls `visitor` for the points inside `clip_volume`. Children entirely inside or outside the
+    /// volume are accepted or rejected as a whole, without testing their points
+    pub fn for_each_point_in_clip_volume(
+        &self,
+        points: &[Vec3WithIndex],
+        clip_volume: &ClipVolume,
+        visitor: &mut impl FnMut(&Vec3WithIndex),
+    ) {
+        match &self.content {
+            OctreeNodeContent::Children(children) => children.iter().for_each(|child| {
+                match clip_volume.classify_box(&child.bounding_box) {
+                    BoxClassification::Inside => child.for_each_point(points, visitor),
+                    BoxClassification::Outside => (),
+                    BoxClassification::Intersecting => {
+                        child.for_each_point_in_clip_volume(points, clip_volume, visitor)
+                    }
+                }
+            }),
+            OctreeNodeContent::Points(range) => points[range.clone()].iter().for_each(|point| {
+                if clip_volume.contains_point(&point.vec) {
+                    visitor(point);
+                }
+            }),
+        }
+    }
+
+    /// Calls `visitor` with every point at most `radius` away from `center` and its distance,
+    /// skipping nodes whose bounding box is farther away than that
+    pub fn for_each_point_within_radius(
+        &self,
+        points: &[Vec3WithIndex],
+        center: &DVec3,
+        radius: f64,
+        visitor: &mut impl FnMut(&Vec3WithIndex, f64),
+    ) {
+        match &self.content {
+            OctreeNodeContent::Children(children) => children.iter().for_each(|child| {
+                if child.bounding_box.distance_to_point(center) <= radius {
+                    child.for_each_point_within_radius(points, center, radius, visitor);
+                }
+            }),
+            OctreeNodeContent::Points(range) => points[range.clone()].iter().for_each(|point| {
+                let distance = (point.vec - center).magnitude();
+                if distance <= radius {
+                    visitor(point, distance);
+                }
+            }),
+        }
+    }
+
+    /// Keeps the `k` points nearest to `center` in the max-heap `nearest`. Children are visited
+    /// closest first, and skipped once they are farther away than the `k`th nearest point so far
+    pub fn find_nearest_points(
+        &self,
+        points: &[Vec3WithIndex],
+        center: &DVec3,
+        k: usize,
+        nearest: &mut BinaryHeap<PointNeighbour>,
+    ) {
+        match &self.content {
+            OctreeNodeContent::Children(children) => {
+                let mut child_distances: [(f64, usize); 8] = std::array::from_fn(|child_index| {
+                    (
+                        children[child_index].bounding_box.distance_to_point(center),
+                        child_index,
+                    )
+                });
+                child_distances.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+                for (distance, child_index) in child_distances {
+                    if nearest.len() == k
+                        && nearest
+                            .peek()
+                            .map_or(false, |farthest| distance > farthest.distance)
+                    {
+                        break;
+                    }
+
+                    children[child_index].find_nearest_points(points, center, k, nearest);
+                }
+            }
+            OctreeNodeContent::Points(range) => points[range.clone()].iter().for_each(|point| {
+                let neighbour = PointNeighbour {
+                    index: point.index,
+                    distance: (point.vec - center).magnitude(),
+                };
+
+                if nearest.len() < k {
+                    nearest.push(neighbour);
+                } else if nearest
+                    .peek()
+                    .map_or(false, |farthest| neighbour < *farthest)
+                {
+                    nearest.pop();
+                    nearest.push(neighbour);
+                }
+            }),
+        }
+    }
+
+    /// Keeps the hit closest to the ray origin in `closest_hit`. Children are visited in order
+    /// along the ray, and skipped once they start beyond the closest hit so far
+    pub fn find_closest_ray_hit(
+        &self,
+        points: &[Vec3WithIndex],
+        ray: &PickRay,
+        closest_hit: &mut Option<RayPickHit>,
+    ) {
+        match &self.content {
+            OctreeNodeContent::Children(children) => {
+                let mut child_distances: [(f64, usize); 8] = std::array::from_fn(|child_index| {
+                    (
+                        ray.get_distance_range(&children[child_index].bounding_box)
+                            .0,
+                        child_index,
+                    )
+                });
+                child_distances.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+                for (near_distance, child_index) in child_distances {
+                    if closest_hit.map_or(false, |hit| near_distance > hit.distance_along_ray) {
+                        break;
+                    }
+
+                    let child = &children[child_index];
+                    if ray.may_hit_box(&child.bounding_box) {
+                        child.find_closest_ray_hit(points, ray, closest_hit);
+                    }
+                }
+            }
+            OctreeNodeContent::Points(range) => points[range.clone()].iter().for_each(|point| {
+                if let Some(hit) = ray.hit_point(point) {
+                    if closest_hit.map_or(true, |closest| hit.is_closer_than(&closest)) {
+                        *closest_hit = Some(hit);
+                    }
+                }
+            }),
+        }
+    }
+}
+
+fn split(
+    points: &mut [Vec3WithIndex],
+    range: Range<usize>,
+    bounding_box: BoundingBox,
+) -> Box<[OctreeNode; 8]> {
+    let middle = (bounding_box.min + bounding_box.max) / 2.0;
+    let node_points = &mut points[range.clone()];
+    let splits = find_splits(node_points, &middle);
+
+    sort_points_into_sectors(node_points, splits, &middle);
+
+    let boxes = get_child_bounding_boxes(&bounding_box);
+
+    let split_maxes = get_split_ends(node_points, &splits);
+
+    let children: Box<[OctreeNode; 8]> = Box::new(std::array::from_fn(|child_index| {
+        OctreeNode::new(
+            boxes[child_index],
+            points,
+            range.start + splits[child_index]..range.start + split_maxes[child_index],
+        )
+    }));
+
+    children
+}
+
+fn get_split_ends(points: &[Vec3WithIndex], splits: &[usize; 8]) -> [usize; 8] {
+    let mut split_maxes = *splits;
+    split_maxes.rotate_left(1);
+    split_maxes[7] = points.len();
+
+    split_maxes
+}
+
+fn find_splits(points: &mut [Vec3WithIndex], middle: &DVec3) -> [usize; 8] {
+    let mut sector_counts = [0; 8];
+    for point in points.iter() {
+        let index = get_octree_child_index(&point.vec, middle);
+        sector_counts[index] += 1;
+    }
+
+    let mut accum_counts = [0; 8];
+
+    for i in 1..8 {
+        accum_counts[i] = accum_counts[i - 1] + sector_counts[i - 1];
+    }
+
+    accum_counts
+}
+
+fn get_octree_child_index(point: &DVec3, middle: &DVec3) -> usize {
+    (if point[0] < middle.x { 0 } else { 1 })
+        + (if point[1] < middle.y { 0 } else { 2 })
+        + (if point[2] < middle.z { 0 } else { 4 })
+}
+
+/// Moves one point at a time to the element in `partition_first_unsorted_points` corresponding to
+/// its target partition (octree node). It then swaps it with the point at that index, and continues
+/// the process with this new point. Continues until the partition with index `index_of_partition_to_fill`
+/// has been filled
+fn sort_points_into_partitions_until_specific_partition_filled(
+    points: &mut [Vec3WithIndex],
+    partition_first_unsorted_points: &mut [usize; 8],
+    partition_end_points: &[usize; 8],
+    bounding_box_center: &DVec3,
+    index_of_partition_to_fill: usize,
+) {
+    // Start moving the last point in the current partition
+    // Iteration ends when we move something back to this index, i.e. this partition is full
+    let initial_point_index = partition_end_points[index_of_partition_to_fill] - 1;
+
+    let mut current_point_index = initial_point_index;
+    let mut current_point = points[current_point_index];
+
+    loop {
+        let next_partition_index = get_octree_child_index(&current_point.vec, bounding_box_center);
+
+        let next_point_index = partition_first_unsorted_points[next_partition_index];
+        let next_point = points[next_point_index];
+
+        points[next_point_index] = current_point;
+
+        current_point_index = next_point_index;
+        current_point = next_point;
+
+        partition_first_unsorted_points[next_partition_index] += 1;
+
+        if current_point_index == initial_point_index {
+            // We have reached a cycle - break loop
+            break;
+        }
+    }
+}
+
+/// Takes the points slice and a starting index for each of the eight octree node children slices, and groups
+/// the points into their corresponding child's slice. It does not allocate a new vector
+fn sort_points_into_sectors(points: &mut [Vec3WithIndex], splits: [usize; 8], middle: &DVec3) {
+    let mut offsets = splits;
+    let partition_end_points = get_split_ends(points, &splits);
+
+    for current_partition in 0..8 {
+        if offsets[current_partition] >= partition_end_points[current_partition] {
+            // This partition is filled, don't start iterating here
+            continue;
+        }
+
+        sort_points_into_partitions_until_specific_partition_filled(
+            points,
+            &mut offsets,
+            &partition_end_points,
+            middle,
+            current_partition,
+        );
+    }
+}
+
+fn get_child_bounding_boxes(bounding_box: &BoundingBox) -> [BoundingBox; 8] {
+    let middle = (bounding_box.min + bounding_box.max) / 2.0;
+
+    std::array::from_fn(|i| {
+        let mut min: DVec3 = Default::default();
+        let mut max: DVec3 = Default::default();
+
+        (min.x, max.x) = if (i & 1) == 0 {
+            (bounding_box.min.x, middle.x)
+        } else {
+            (middle.x, bounding_box.max.x)
+        };
+
+        (min.y, max.y) = if (i & 2) == 0 {
+            (bounding_box.min.y, middle.y)
+        } else {
+            (middle.y, bounding_box.max.y)
+        };
+
+        (min.z, max.z) = if (i & 4) == 0 {
+            (bounding_box.min.z, middle.z)
+        } else {
+            (middle.z, bounding_box.max.z)
+        };
+
+        BoundingBox { min, max }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::prelude::*;
+    use rand_chacha::ChaCha8Rng;
+
+    use nalgebra_glm::{comp_max, epsilon, vec3, DVec3};
+
+    use super::{
+        find_splits, get_child_bounding_boxes, get_octree_child_index, get_split_ends,
+        sort_points_into_sectors,
+    };
+    use super::{BoundingBox, Vec3WithIndex};
+
+    #[test]
+    fn test_point_in_first_octant_gets_index_0() {
+        let point = vec3(-0.1, -0.4, -0.2);
+        let middle = vec3(0.0, 0.0, 0.0);
+        assert_eq!(get_octree_child_index(&point, &middle), 0);
+    }
+
+    #[test]
+    fn test_point_in_last_octant_gets_index_7() {
+        let point = vec3(0.1, 0.4, 0.2);
+        let middle = vec3(0.0, 0.0, 0.0);
+        assert_eq!(get_octree_child_index(&point, &middle), 7);
+    }
+
+    #[test]
+    fn test_child_box_centers_average_to_parent_center() {
+        let bounding_box = BoundingBox {
+            min: vec3(-2.0, -1.0, 0.0),
+            max: vec3(0.0, 1.0, 2.0),
+        };
+
+        let middle = (bounding_box.min + bounding_box.max) / 2.0;
+
+        let child_boxes = get_child_bounding_boxes(&bounding_box);
+
+        let mut accumulative_middle: DVec3 = Default::default();
+        for child in child_boxes.iter() {
+            accumulative_middle += (child.min + child.max) / 2.0;
+        }
+
+        let average_middle = accumulative_middle / (child_boxes.len() as f64);
+
+        assert!(comp_max(&(average_middle - middle).abs()) < epsilon());
+    }
+
+    #[test]
+    fn test_sector_inplace_sorting() {
+        const NUM_POINTS: usize = 300;
+
+        let mut points: Vec<Vec3WithIndex> = Vec::with_capacity(NUM_POINTS);
+
+        let original_points = points.clone();
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0xbaadf00d);
+
+        for i in 0..NUM_POINTS {
+            let p = vec3(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+            points.push(Vec3WithIndex { vec: p, index: i });
+        }
+
+        let middle: DVec3 = Default::default();
+
+        let splits = find_splits(&mut points[..], &middle);
+        let split_ends = get_split_ends(&points[..], &splits);
+        sort_points_into_sectors(&mut points[..], splits, &middle);
+
+        let mut num_points_checked = 0;
+
+        for sector_index in 0..8 {
+            for point in &points[splits[sector_index]..split_ends[sector_index]] {
+                assert_eq!(get_octree_child_index(&point.vec, &middle), sector_index);
+                num_points_checked += 1;
+            }
+        }
+
+        assert_eq!(num_points_checked, points.len());
+
+        for og_point in original_points {
+            let mut found = false;
+            for new_point in points.iter() {
+                if new_point == &og_point {
+                    found = true;
+                    break;
+                }
+            }
+
+            assert!(found);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use std::cmp::Ordering;
+
+/// A point found near a query position, identified by its original index
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointNeighbour {
+    pub index: usize,
+    pub distance: f64,
+}
+
+impl Eq for PointNeighbour {}
+
+/// Ordered by distance, with ties broken by index so that results do not depend on the octree
+/// order of the points
+impl Ord for PointNeighbour {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for PointNeighbour {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,412 @@
+use crate::bvh::Bvh;
+use crate::clip_volume::ClipVolume;
+use crate::linalg::{BoundingBox, Vec3WithIndex};
+
+use super::octree_node::OctreeNode;
+use super::{PickRay, PointNeighbour, RayPickHit};
+use std::collections::BinaryHeap;
+use std::vec::Vec;
+
+use nalgebra_glm::DVec3;
+
+use crate::shapes::Shape;
+
+/// Octree over a set of points, which it owns. Building it reorders the points, but each point
+/// keeps its original index
+pub struct PointOctree {
+    points: Vec<Vec3WithIndex>,
+    root: OctreeNode,
+}
+
+impl PointOctree {
+    pub fn new(bounding_box: BoundingBox, mut points: Vec<Vec3WithIndex>) -> PointOctree {
+        let num_points = points.len();
+        let root = OctreeNode::new(bounding_box, &mut points, 0..num_points);
+
+        PointOctree { points, root }
+    }
+
+    /// All points in octree order
+    pub fn points(&self) -> &[Vec3WithIndex] {
+        &self.points
+    }
+
+    pub fn assign_object_ids(
+        &self,
+        bounding_box: &BoundingBox,
+        shape: &dyn Shape,
+        object_ids: &mut [u32],
+    ) {
+        self.root
+            .assign_object_ids(&self.points, bounding_box, shape, object_ids);
+    }
+
+    /// Calls `visitor` for every point contained in `shape`, only visiting octree nodes that
+    /// overlap `bounding_box`
+    pub fn for_each_point_in_shape(
+        &self,
+        bounding_box: &BoundingBox,
+        shape: &dyn Shape,
+        mut visitor: impl FnMut(&Vec3WithIndex),
+    ) {
+        self.root
+            .for_each_point_in_shape(&self.points, bounding_box, shape, &mut visitor);
+    }
+
+    /// Calls `visitor` with every point and the index of each item of `bvh` that `contains` it, in
+    /// no particular order. The hierarchy is traversed jointly with the octree, so that items far
+    /// from a node cost nothing there
+    pub fn for_each_point_in_bvh_items(
+        &self,
+        bvh: &Bvh,
+        contains: impl Fn(usize, &DVec3) -> bool,
+        mut visitor: impl FnMut(&Vec3WithIndex, usize),
+    ) {
+        self.root.for_each_point_in_bvh_items(
+            &self.points,
+            bvh,
+            &bvh.get_root_frontier(),
+            &contains,
+            &mut visitor,
+        );
+    }
+
+    pub fn for_each_point_in_box(
+        &self,
+        bounding_box: &BoundingBox,
+        mut visitor: impl FnMut(&Vec3WithIndex),
+    ) {
+        self.root.for_each_point_where(
+            &self.points,
+            bounding_box,
+            &|point| bounding_box.contains_point(point),
+            &mut visitor,
+        );
+    }
+
+    pub fn for_each_point_in_clip_volume(
+        &self,
+        clip_volume: &ClipVolume,
+        mut visitor: impl FnMut(&Vec3WithIndex),
+    ) {
+        self.root
+            .for_each_point_in_clip_volume(&self.points, clip_volume, &mut visitor);
+    }
+
+    /// All points at most `radius` away from `center`, nearest first
+    pub fn find_points_within_radius(&self, center: &DVec3, radius: f64) -> Vec<PointNeighbour> {
+        let mut neighbours = vec![];
+        self.root.for_each_point_within_radius(
+            &self.points,
+            center,
+            radius,
+            &mut |point, distance| {
+                neighbours.push(PointNeighbour {
+                    index: point.index,
+                    distance,
+                })
+            },
+        );
+
+        neighbours.sort_unstable();
+        neighbours
+    }
+
+    /// The `k` points nearest to `center`, nearest first. Returns all points if there are fewer
+    pub fn find_nearest_points(&self, center: &DVec3, k: usize) -> Vec<PointNeighbour> {
+        if k == 0 {
+            return vec![];
+        }
+
+        let mut nearest = BinaryHeap::with_capacity(k + 1);
+        self.root
+            .find_nearest_points(&self.points, center, k, &mut nearest);
+
+        nearest.into_sorted_vec()
+    }
+
+    /// The point hit by `ray` that is closest to its origin, if any
+    pub fn find_closest_ray_hit(&self, ray: &PickRay) -> Option<RayPickHit> {
+        let mut closest_hit = None;
+        self.root
+            .find_closest_ray_hit(&self.points, ray, &mut closest_hit);
+
+        closest_hit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{PickRay, PointNeighbour, PointOctree};
+    use crate::bvh::Bvh;
+    use crate::clip_volume::ClipVolume;
+    use crate::linalg::{BoundingBox, Plane, Vec3WithIndex};
+    use crate::shapes::{OrientedBox, Shape, Sphere};
+
+    use nalgebra_glm::{translate, vec3, DMat4, DVec3};
+
+    use rand::prelude::*;
+    use rand_chacha::ChaCha8Rng;
+
+    fn create_random_points_in_base_box(num_points: u32) -> Vec<Vec3WithIndex> {
+        let mut rng = ChaCha8Rng::seed_from_u64(0xbaadf00d);
+
+        let mut points = Vec::<Vec3WithIndex>::with_capacity(num_points as usize);
+
+        let base_box = BoundingBox::get_base_cube_bounding_box();
+
+        for i in 0..num_points {
+            points.push(Vec3WithIndex {
+                vec: vec3(
+                    rng.gen_range(base_box.min.x..base_box.max.x),
+                    rng.gen_range(base_box.min.y..base_box.max.y),
+                    rng.gen_range(base_box.min.z..base_box.max.z),
+                ),
+                index: i as usize,
+            });
+        }
+
+        points
+    }
+
+    #[test]
+    fn all_points_returned_for_all_enclosing_box_shape() {
+        const NUM_POINTS: u32 = 1_000;
+        const OBJECT_ID: u32 = 42;
+
+        let points = create_random_points_in_base_box(NUM_POINTS);
+
+        let shape: Box<dyn Shape> =
+            Box::<OrientedBox>::new(OrientedBox::new(DMat4::identity(), OBJECT_ID));
+        let bounding_box = BoundingBox::get_transformed_base_cube(&DMat4::identity());
+        let mut array = vec![0; NUM_POINTS as usize];
+
+        let octree = PointOctree::new(bounding_box, points);
+        octree.assign_object_ids(&shape.create_bounding_box(), shape.as_ref(), &mut array);
+
+        for i in 0..NUM_POINTS {
+            let set_object_id = array[i as usize];
+            assert_eq!(set_object_id, OBJECT_ID);
+        }
+    }
+
+    #[test]
+    fn no_points_returned_for_non_overlapping_shape() {
+        const NUM_POINTS: u32 = 1_000;
+        const OBJECT_ID: u32 = 42;
+
+        let points = create_random_points_in_base_box(NUM_POINTS);
+
+        let box_matrix = translate(&DMat4::identity(), &vec3(2.0, 0.0, 0.0));
+        let bounding_box = BoundingBox::get_transformed_base_cube(&box_matrix);
+        let shape: Box<dyn Shape> =
+            Box::<OrientedBox>::new(OrientedBox::new(box_matrix, OBJECT_ID));
+        let mut array = vec![0; NUM_POINTS as usize];
+
+        let octree = PointOctree::new(bounding_box, points);
+        octree.assign_object_ids(&shape.create_bounding_box(), shape.as_ref(), &mut array);
+
+        for i in 0..NUM_POINTS {
+            let set_object_id = array[i as usize];
+            assert_eq!(set_object_id, 0);
+        }
+    }
+
+    #[test]
+    fn box_query_visits_exactly_the_points_inside_box() {
+        const NUM_POINTS: u32 = 5_000;
+
+        let points = create_random_points_in_base_box(NUM_POINTS);
+        let query_box = BoundingBox {
+            min: vec3(-0.3, 0.1, -1.0),
+            max: vec3(0.2, 0.6, 0.0),
+        };
+
+        let mut expected_indices: Vec<usize> = points
+            .iter()
+            .filter(|point| query_box.contains_point(&point.vec))
+            .map(|point| point.index)
+            .collect();
+
+        let octree = PointOctree::new(BoundingBox::get_base_cube_bounding_box(), points);
+
+        let mut found_indices = vec![];
+        octree.for_each_point_in_box(&query_box, |point| found_indices.push(point.index));
+
+        expected_indices.sort_unstable();
+        found_indices.sort_unstable();
+        assert!(!found_indices.is_empty());
+        assert_eq!(found_indices, expected_indices);
+        assert_eq!(octree.points().len(), NUM_POINTS as usize);
+    }
+
+    fn find_neighbours_by_brute_force(
+        points: &[Vec3WithIndex],
+        center: &DVec3,
+    ) -> Vec<PointNeighbour> {
+        let mut neighbours: Vec<PointNeighbour> = points
+            .iter()
+            .map(|point| PointNeighbour {
+                index: point.index,
+                distance: (point.vec - center).magnitude(),
+            })
+            .collect();
+
+        neighbours.sort_unstable();
+        neighbours
+    }
+
+    #[test]
+    fn radius_query_finds_exactly_the_points_within_radius() {
+        const NUM_POINTS: u32 = 5_000;
+        const RADIUS: f64 = 0.3;
+
+        let points = create_random_points_in_base_box(NUM_POINTS);
+        let center = vec3(0.4, -0.2, 0.1);
+
+        let expected: Vec<PointNeighbour> = find_neighbours_by_brute_force(&points, &center)
+            .into_iter()
+            .filter(|neighbour| neighbour.distance <= RADIUS)
+            .collect();
+
+        let octree = PointOctree::new(BoundingBox::get_base_cube_bounding_box(), points);
+        let found = octree.find_points_within_radius(&center, RADIUS);
+
+        assert!(!found.is_empty());
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn nearest_points_query_matches_brute_force() {
+        const NUM_POINTS: u32 = 5_000;
+        const K: usize = 25;
+
+        let points = create_random_points_in_base_box(NUM_POINTS);
+        let center = vec3(-0.9, 0.7, 1.5);
+
+        let mut expected = find_neighbours_by_brute_force(&points, &center);
+        expected.truncate(K);
+
+        let octree = PointOctree::new(BoundingBox::get_base_cube_bounding_box(), points);
+
+        assert_eq!(octree.find_nearest_points(&center, K), expected);
+        assert!(octree.find_nearest_points(&center, 0).is_empty());
+        assert_eq!(
+            octree
+                .find_nearest_points(&center, 2 * NUM_POINTS as usize)
+                .len(),
+            NUM_POINTS as usize
+        );
+    }
+
+    #[test]
+    fn ray_pick_matches_brute_force() {
+        const NUM_POINTS: u32 = 5_000;
+
+        let points = create_random_points_in_base_box(NUM_POINTS);
+        let ray = PickRay::new(vec3(-3.0, 0.2, -0.1), &vec3(1.0, 0.1, 0.05), 0.01, 0.005).unwrap();
+
+        let expected = points
+            .iter()
+            .filter_map(|point| ray.hit_point(point))
+            .reduce(|closest, hit| {
+                if hit.is_closer_than(&closest) {
+                    hit
+                } else {
+                    closest
+                }
+            });
+
+        let octree = PointOctree::new(BoundingBox::get_base_cube_bounding_box(), points);
+
+        assert!(expected.is_some());
+        assert_eq!(octree.find_closest_ray_hit(&ray), expected);
+    }
+
+    #[test]
+    fn ray_pointing_away_from_points_hits_nothing() {
+        let points = create_random_points_in_base_box(1_000);
+        let ray = PickRay::new(vec3(3.0, 0.0, 0.0), &vec3(1.0, 0.0, 0.0), 0.5, 0.1).unwrap();
+
+        let octree = PointOctree::new(BoundingBox::get_base_cube_bounding_box(), points);
+
+        assert_eq!(octree.find_closest_ray_hit(&ray), None);
+    }
+
+    #[test]
+    fn clip_volume_query_matches_per_point_test() {
+        const NUM_POINTS: u32 = 20_000;
+
+        let points = create_random_points_in_base_box(NUM_POINTS);
+        let clip_volume = ClipVolume::new(vec![
+            Plane::new(vec3(1.0, 0.0, 0.0), 0.5),
+            Plane::new(vec3(-1.0, -1.0, 0.0), 0.3),
+            Plane::new(vec3(0.0, 0.2, 1.0), 0.7),
+        ]);
+
+        let mut expected_indices: Vec<usize> = points
+            .iter()
+            .filter(|point| clip_volume.contains_point(&point.vec))
+            .map(|point| point.index)
+            .collect();
+
+        let octree = PointOctree::new(BoundingBox::get_base_cube_bounding_box(), points);
+
+        let mut found_indices = vec![];
+        octree.for_each_point_in_clip_volume(&clip_volume, |point| found_indices.push(point.index));
+
+        expected_indices.sort_unstable();
+        found_indices.sort_unstable();
+        assert!(!found_indices.is_empty());
+        assert_eq!(found_indices, expected_indices);
+    }
+
+    #[test]
+    fn joint_traversal_finds_same_points_as_one_traversal_per_shape() {
+        const NUM_POINTS: u32 = 20_000;
+        const NUM_SPHERES: u32 = 500;
+
+        let points = create_random_points_in_base_box(NUM_POINTS);
+        let octree = PointOctree::new(BoundingBox::get_base_cube_bounding_box(), points);
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0xdeadbeef);
+        let spheres: Vec<Sphere> = (0..NUM_SPHERES)
+            .map(|object_id| {
+                let center = vec3(
+                    rng.gen_range(-1.5..1.5),
+                    rng.gen_range(-1.5..1.5),
+                    rng.gen_range(-1.5..1.5),
+                );
+                Sphere::new(center, rng.gen_range(0.01..0.2), object_id)
+            })
+            .collect();
+        let shapes: Vec<&dyn Shape> = spheres.iter().map(|sphere| sphere as &dyn Shape).collect();
+
+        let mut expected_pairs = vec![];
+        for (shape_index, shape) in shapes.iter().enumerate() {
+            octree.for_each_point_in_shape(&shape.create_bounding_box(), *shape, |point| {
+                expected_pairs.push((point.index, shape_index))
+            });
+        }
+
+        let bvh = Bvh::new(
+            shapes
+                .iter()
+                .map(|shape| shape.create_bounding_box())
+                .collect(),
+        );
+
+        let mut found_pairs = vec![];
+        octree.for_each_point_in_bvh_items(
+            &bvh,
+            |shape_index, point| shapes[shape_index].contains_point(point),
+            |point, shape_index| found_pairs.push((point.index, shape_index)),
+        );
+
+        expected_pairs.sort_unstable();
+        found_pairs.sort_unstable();
+        assert!(!found_pairs.is_empty());
+        assert_eq!(found_pairs, expected_pairs);
+    }
+}
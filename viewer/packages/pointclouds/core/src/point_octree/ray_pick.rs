@@ -0,0 +1,172 @@
+use nalgebra_glm::{dot, DVec3};
+
+use crate::linalg::{BoundingBox, Vec3WithIndex};
+
+/// A pick ray that accepts points within `radius + t * radius_per_distance` of it, where `t` is the
+/// distance along the ray. A positive `radius_per_distance` gives a cone, which matches a constant
+/// radius in screen space for a perspective camera
+#[derive(Clone, Copy, Debug)]
+pub struct PickRay {
+    origin: DVec3,
+    direction: DVec3,
+    radius: f64,
+    radius_per_distance: f64,
+}
+
+/// A point accepted by a `PickRay`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayPickHit {
+    pub index: usize,
+    pub position: DVec3,
+    pub distance_along_ray: f64,
+    pub distance_to_ray: f64,
+}
+
+impl RayPickHit {
+    /// Hits nearer the ray origin are closer. Ties are broken by distance to the ray and then by
+    /// index, so that picking does not depend on the octree order of the points
+    pub fn is_closer_than(&self, other: &RayPickHit) -> bool {
+        self.distance_along_ray
+            .total_cmp(&other.distance_along_ray)
+            .then(self.distance_to_ray.total_cmp(&other.distance_to_ray))
+            .then(self.index.cmp(&other.index))
+            .is_lt()
+    }
+}
+
+impl PickRay {
+    pub fn new(
+        origin: DVec3,
+        direction: &DVec3,
+        radius: f64,
+        radius_per_distance: f64,
+    ) -> Result<PickRay, String> {
+        let length = direction.magnitude();
+
+        if !(length.is_finite() && length > 0.0) {
+            return Err("Pick ray direction must be a finite, non-zero vector".to_string());
+        }
+
+        if radius.is_nan()
+            || radius < 0.0
+            || radius_per_distance.is_nan()
+            || radius_per_distance < 0.0
+        {
+            return Err(format!(
+                "Pick ray radius and radius per distance must be non-negative, got {} and {}",
+                radius, radius_per_distance
+            ));
+        }
+
+        Ok(PickRay {
+            origin,
+            direction: direction / length,
+            radius,
+            radius_per_distance,
+        })
+    }
+
+    pub fn radius_at(&self, distance_along_ray: f64) -> f64 {
+        self.radius + distance_along_ray * self.radius_per_distance
+    }
+
+    /// Range of distances along the ray covered by the box, which may start behind the origin
+    pub fn get_distance_range(&self, bounding_box: &BoundingBox) -> (f64, f64) {
+        let center_distance = dot(&(bounding_box.center() - self.origin), &self.direction);
+        let half_length = dot(&(bounding_box.extent() / 2.0), &self.direction.abs());
+
+        (center_distance - half_length, center_distance + half_length)
+    }
+
+    /// Conservative test for whether any point in the box can be hit
+    pub fn may_hit_box(&self, bounding_box: &BoundingBox) -> bool {
+        let (_, far_distance) = self.get_distance_range(bounding_box);
+
+        if far_distance < 0.0 {
+            return false;
+        }
+
+        let max_radius = self.radius_at(far_distance);
+        let expanded_box = BoundingBox {
+            min: bounding_box.min.add_scalar(-max_radius),
+            max: bounding_box.max.add_scalar(max_radius),
+        };
+
+        expanded_box.intersects_ray(&self.origin, &self.direction)
+    }
+
+    /// Points behind the origin are never hit
+    pub fn hit_point(&self, point: &Vec3WithIndex) -> Option<RayPickHit> {
+        let offset = point.vec - self.origin;
+        let distance_along_ray = dot(&offset, &self.direction);
+
+        if distance_along_ray < 0.0 {
+            return None;
+        }
+
+        let distance_to_ray = (offset - self.direction * distance_along_ray).magnitude();
+
+        (distance_to_ray <= self.radius_at(distance_along_ray)).then_some(RayPickHit {
+            index: point.index,
+            position: point.vec,
+            distance_along_ray,
+            distance_to_ray,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::PickRay;
+    use crate::linalg::{BoundingBox, Vec3WithIndex};
+
+    #[test]
+    fn cone_radius_grows_with_distance_along_ray() {
+        let ray = PickRay::new(vec3(0.0, 0.0, 0.0), &vec3(0.0, 0.0, 2.0), 0.0, 0.1).unwrap();
+
+        let near_point = Vec3WithIndex {
+            vec: vec3(0.15, 0.0, 1.0),
+            index: 0,
+        };
+        let far_point = Vec3WithIndex {
+            vec: vec3(0.15, 0.0, 2.0),
+            index: 1,
+        };
+
+        assert!(ray.hit_point(&near_point).is_none());
+
+        let hit = ray.hit_point(&far_point).unwrap();
+        assert!((hit.distance_along_ray - 2.0).abs() < 1e-12);
+        assert!((hit.distance_to_ray - 0.15).abs() < 1e-12);
+    }
+
+    #[test]
+    fn boxes_behind_or_beside_ray_are_rejected() {
+        let ray = PickRay::new(vec3(0.0, 0.0, 0.0), &vec3(1.0, 0.0, 0.0), 0.5, 0.0).unwrap();
+
+        let behind = BoundingBox {
+            min: vec3(-3.0, -1.0, -1.0),
+            max: vec3(-2.0, 1.0, 1.0),
+        };
+        let beside = BoundingBox {
+            min: vec3(1.0, 0.6, -1.0),
+            max: vec3(2.0, 1.0, 1.0),
+        };
+        let within_radius = BoundingBox {
+            min: vec3(1.0, 0.4, -1.0),
+            max: vec3(2.0, 1.0, 1.0),
+        };
+
+        assert!(!ray.may_hit_box(&behind));
+        assert!(!ray.may_hit_box(&beside));
+        assert!(ray.may_hit_box(&within_radius));
+    }
+
+    #[test]
+    fn zero_direction_is_rejected() {
+        assert!(PickRay::new(vec3(0.0, 0.0, 0.0), &vec3(0.0, 0.0, 0.0), 1.0, 0.0).is_err());
+        assert!(PickRay::new(vec3(0.0, 0.0, 0.0), &vec3(1.0, 0.0, 0.0), -1.0, 0.0).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use std::f64::consts::PI;
+
+use nalgebra_glm::{distance, max2, min2, vec3, DVec3};
+
+use crate::linalg::{closest_point_on_segment, BoundingBox};
+use crate::shapes::shape::Shape;
+
+/// A cylinder with hemispherical caps, i.e. all points within `radius` of the segment between
+/// `center_a` and `center_b`
+pub struct Capsule {
+    center_a: DVec3,
+    center_b: DVec3,
+    radius: f64,
+    object_id: u32,
+}
+
+impl Capsule {
+    pub fn new(center_a: DVec3, center_b: DVec3, radius: f64, object_id: u32) -> Self {
+        Capsule {
+            center_a,
+            center_b,
+            radius,
+            object_id,
+        }
+    }
+}
+
+impl Capsule {
+    fn get_distance_to_axis(&self, point: &DVec3) -> f64 {
+        distance(
+            point,
+            &closest_point_on_segment(point, &self.center_a, &self.center_b),
+        )
+    }
+}
+
+impl Shape for Capsule {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        self.get_distance_to_axis(point) < self.radius
+    }
+
+    /// Measured from the center line, so this is also the distance to the core
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        self.get_distance_to_axis(point) - self.radius
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        let extent = vec3(self.radius, self.radius, self.radius);
+
+        BoundingBox {
+            min: min2(&self.center_a, &self.center_b) - extent,
+            max: max2(&self.center_a, &self.center_b) + extent,
+        }
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+
+    fn get_volume(&self) -> f64 {
+        let length = distance(&self.center_a, &self.center_b);
+        PI * self.radius * self.radius * (length + 4.0 / 3.0 * self.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::Capsule;
+    use crate::shapes::Shape;
+
+    #[test]
+    fn capsule_at_origin_contains_middle_point() {
+        let capsule = Capsule::new(vec3(0.0, -0.5, 0.0), vec3(0.0, 0.5, 0.0), 1.0, 0);
+
+        assert!(capsule.contains_point(&vec3(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn capsule_contains_points_in_caps_but_not_cylinder_corners() {
+        let capsule = Capsule::new(vec3(0.0, -0.5, 0.0), vec3(0.0, 0.5, 0.0), 1.0, 0);
+
+        assert!(capsule.contains_point(&vec3(0.0, 1.4, 0.0)));
+        assert!(capsule.contains_point(&vec3(0.0, -1.4, 0.0)));
+        assert!(!capsule.contains_point(&vec3(0.9, 1.4, 0.0)));
+        assert!(!capsule.contains_point(&vec3(0.0, 1.6, 0.0)));
+    }
+
+    #[test]
+    fn thin_diagonal_capsule_contains_point_in_middle() {
+        let center_a = vec3(213.0, -33.0, 983.12);
+        let center_b = vec3(-12.0, 234.0, -10.0);
+
+        let middle = (center_a + center_b) / 2.0;
+        let outside_middle = middle + vec3(0.1, 0.1, 0.1);
+
+        let capsule = Capsule::new(center_a, center_b, 1e-2, 0);
+
+        assert!(capsule.contains_point(&middle));
+        assert!(!capsule.contains_point(&outside_middle));
+    }
+
+    #[test]
+    fn capsule_bounding_box_contains_caps_but_not_more_along_axis() {
+        let center_a = vec3(21.0, -33.0, 98.0);
+        let center_b = vec3(-12.0, 23.0, -10.0);
+        let radius = 0.5;
+
+        let axis = (center_a - center_b).normalize();
+
+        let capsule = Capsule::new(center_a, center_b, radius, 0);
+        let bounding_box = capsule.create_bounding_box();
+
+        assert!(bounding_box.contains_point(&(center_a + axis * radius * 0.99)));
+        assert!(bounding_box.contains_point(&(center_b - axis * radius * 0.99)));
+        assert!(!bounding_box.contains_point(&(center_a + axis * (radius + 1.0))));
+    }
+}
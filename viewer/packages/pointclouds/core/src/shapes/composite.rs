@@ -0,0 +1,104 @@
+use nalgebra_glm::DVec3;
+
+use crate::linalg::BoundingBox;
+use crate::shapes::shape::Shape;
+
+pub struct Composite {
+    inner_shapes: Vec<Box<dyn Shape>>,
+    object_id: u32,
+}
+
+impl Composite {
+    pub fn new(inner_shapes: Vec<Box<dyn Shape>>, object_id: u32) -> Self {
+        Composite {
+            inner_shapes,
+            object_id,
+        }
+    }
+}
+
+impl Shape for Composite {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        self.inner_shapes
+            .iter()
+            .any(|shape| shape.contains_point(point))
+    }
+
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        self.inner_shapes
+            .iter()
+            .map(|shape| shape.signed_distance(point))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        self.inner_shapes
+            .iter()
+            .map(|shape| shape.create_bounding_box())
+            .fold(Default::default(), |union: BoundingBox, bounding_box| {
+                union.union(&bounding_box)
+            })
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+
+    /// Ignores overlap between the inner shapes
+    fn get_volume(&self) -> f64 {
+        self.inner_shapes
+            .iter()
+            .map(|shape| shape.get_volume())
+            .sum()
+    }
+
+    fn get_distance_to_core(&self, point: &DVec3) -> f64 {
+        self.inner_shapes
+            .iter()
+            .map(|shape| shape.get_distance_to_core(point))
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{translate, vec3, DMat4};
+
+    use super::Composite;
+    use crate::shapes::{Cylinder, OrientedBox, Shape};
+
+    fn create_box_and_cylinder_composite() -> Composite {
+        let oriented_box =
+            OrientedBox::new(translate(&DMat4::identity(), &vec3(-2.0, 0.0, 0.0)), 0);
+        let cylinder = Cylinder::new(vec3(4.0, 0.0, 0.0), vec3(5.0, 0.0, 0.0), 0.5, 0);
+
+        Composite::new(vec![Box::new(oriented_box), Box::new(cylinder)], 7)
+    }
+
+    #[test]
+    fn composite_contains_points_of_all_inner_shapes() {
+        let composite = create_box_and_cylinder_composite();
+
+        assert!(composite.contains_point(&vec3(2.0, 0.0, 0.0)));
+        assert!(composite.contains_point(&vec3(4.5, 0.0, 0.0)));
+        assert!(!composite.contains_point(&vec3(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn composite_bounding_box_is_union_of_inner_bounding_boxes() {
+        let composite = create_box_and_cylinder_composite();
+        let bounding_box = composite.create_bounding_box();
+
+        assert!(bounding_box.contains_point(&vec3(1.5, 0.9, 0.9)));
+        assert!(bounding_box.contains_point(&vec3(4.9, 0.0, 0.0)));
+        assert!(!bounding_box.contains_point(&vec3(5.5, 0.0, 0.0)));
+        assert!(!bounding_box.contains_point(&vec3(0.5, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn empty_composite_contains_nothing() {
+        let composite = Composite::new(vec![], 1);
+
+        assert!(!composite.contains_point(&vec3(0.0, 0.0, 0.0)));
+    }
+}
@@ -0,0 +1,212 @@
+use std::f64::consts::PI;
+
+use nalgebra_glm::{distance, dot, vec3, DVec3};
+
+use crate::linalg::BoundingBox;
+use crate::shapes::shape::Shape;
+
+/// A truncated cone (frustum) between the disks at `center_a` and `center_b`. Either radius may
+/// be zero, which makes the cone end in an apex
+pub struct Cone {
+    center_a: DVec3,
+    center_b: DVec3,
+    radius_a: f64,
+    radius_b: f64,
+    object_id: u32,
+}
+
+impl Cone {
+    pub fn new(
+        center_a: DVec3,
+        center_b: DVec3,
+        radius_a: f64,
+        radius_b: f64,
+        object_id: u32,
+    ) -> Self {
+        Cone {
+            center_a,
+            center_b,
+            radius_a,
+            radius_b,
+            object_id,
+        }
+    }
+}
+
+/// Axis-aligned extent of a disk with the given unit normal and radius
+fn get_disk_extent(normal: &DVec3, radius: f64) -> DVec3 {
+    vec3(
+        radius * (1.0 - normal.x * normal.x).max(0.0).sqrt(),
+        radius * (1.0 - normal.y * normal.y).max(0.0).sqrt(),
+        radius * (1.0 - normal.z * normal.z).max(0.0).sqrt(),
+    )
+}
+
+impl Shape for Cone {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        let axis_vec = self.center_b - self.center_a;
+        let height = axis_vec.magnitude();
+
+        if height == 0.0 {
+            return false;
+        }
+
+        let axis = axis_vec / height;
+        let dist_along_axis = dot(&(point - self.center_a), &axis);
+
+        if dist_along_axis <= 0.0 || dist_along_axis >= height {
+            return false;
+        }
+
+        let radius_at_point =
+            self.radius_a + (self.radius_b - self.radius_a) * (dist_along_axis / height);
+        let dist_to_axis = (point - self.center_a - axis * dist_along_axis).magnitude();
+
+        dist_to_axis < radius_at_point
+    }
+
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        // Distances are computed in the 2D half-plane through the axis that contains `point`
+        let axis_vec = self.center_b - self.center_a;
+        let height_squared = axis_vec.magnitude_squared();
+        let relative_point = point - self.center_a;
+
+        let t = dot(&relative_point, &axis_vec) / height_squared;
+        let dist_to_axis = (relative_point.magnitude_squared() - t * t * height_squared)
+            .max(0.0)
+            .sqrt();
+
+        // Distance to the nearest end disk, with the axial part scaled by the height
+        let cap_radius = if t < 0.5 {
+            self.radius_a
+        } else {
+            self.radius_b
+        };
+        let cap_radial = (dist_to_axis - cap_radius).max(0.0);
+        let cap_axial = (t - 0.5).abs() - 0.5;
+
+        // Distance to the slanted side
+        let radius_change = self.radius_b - self.radius_a;
+        let side_t = ((radius_change * (dist_to_axis - self.radius_a) + t * height_squared)
+            / (radius_change * radius_change + height_squared))
+            .clamp(0.0, 1.0);
+        let side_radial = dist_to_axis - self.radius_a - side_t * radius_change;
+        let side_axial = t - side_t;
+
+        let sign = if side_radial < 0.0 && cap_axial < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+
+        sign * (cap_radial * cap_radial + cap_axial * cap_axial * height_squared)
+            .min(side_radial * side_radial + side_axial * side_axial * height_squared)
+            .sqrt()
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        let axis = (self.center_b - self.center_a).normalize();
+
+        // The cone is the convex hull of its two end disks, so bounding those is tight
+        let extent_a = get_disk_extent(&axis, self.radius_a);
+        let extent_b = get_disk_extent(&axis, self.radius_b);
+
+        [
+            self.center_a - extent_a,
+            self.center_a + extent_a,
+            self.center_b - extent_b,
+            self.center_b + extent_b,
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+
+    fn get_volume(&self) -> f64 {
+        let height = distance(&self.center_a, &self.center_b);
+        PI * height / 3.0
+            * (self.radius_a * self.radius_a
+                + self.radius_a * self.radius_b
+                + self.radius_b * self.radius_b)
+    }
+
+    fn get_distance_to_core(&self, point: &DVec3) -> f64 {
+        let axis_vec = self.center_b - self.center_a;
+        let t = (dot(&(point - self.center_a), &axis_vec) / axis_vec.magnitude_squared())
+            .clamp(0.0, 1.0);
+        let radius = self.radius_a + (self.radius_b - self.radius_a) * t;
+
+        distance(point, &(self.center_a + axis_vec * t)) - radius
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::Cone;
+    use crate::shapes::Shape;
+
+    #[test]
+    fn frustum_radius_varies_along_axis() {
+        let cone = Cone::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 2.0, 0.0), 1.0, 0.5, 0);
+
+        assert!(cone.contains_point(&vec3(0.9, 0.1, 0.0)));
+        assert!(!cone.contains_point(&vec3(0.9, 1.9, 0.0)));
+        assert!(cone.contains_point(&vec3(0.0, 1.9, 0.45)));
+        assert!(!cone.contains_point(&vec3(0.0, 2.1, 0.0)));
+    }
+
+    #[test]
+    fn cone_with_apex_contains_points_near_axis_only() {
+        let cone = Cone::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0), 1.0, 0.0, 0);
+
+        assert!(cone.contains_point(&vec3(0.0, 0.0, 0.99)));
+        assert!(!cone.contains_point(&vec3(0.1, 0.0, 0.95)));
+        assert!(!cone.contains_point(&vec3(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn cone_with_zero_height_contains_nothing() {
+        let cone = Cone::new(vec3(1.0, 1.0, 1.0), vec3(1.0, 1.0, 1.0), 1.0, 1.0, 0);
+
+        assert!(!cone.contains_point(&vec3(1.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn axis_aligned_cone_bounding_box_is_tight() {
+        let cone = Cone::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 3.0), 2.0, 0.0, 0);
+        let bounding_box = cone.create_bounding_box();
+
+        assert_eq!(bounding_box.min, vec3(-2.0, -2.0, 0.0));
+        assert_eq!(bounding_box.max, vec3(2.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn diagonal_cone_bounding_box_contains_base_rim() {
+        let center_a = vec3(21.0, -33.0, 98.0);
+        let center_b = vec3(-12.0, 23.0, -10.0);
+        let axis = (center_b - center_a).normalize();
+        let rim_direction = axis.cross(&vec3(1.0, 0.0, 0.0)).normalize();
+
+        let cone = Cone::new(center_a, center_b, 2.0, 0.5, 0);
+        let bounding_box = cone.create_bounding_box();
+
+        assert!(bounding_box.contains_point(&(center_a + rim_direction * 1.99)));
+        assert!(bounding_box.contains_point(&(center_b + rim_direction * 0.49)));
+        assert!(!bounding_box.contains_point(&(center_b + axis)));
+    }
+
+    #[test]
+    fn cone_signed_distance_to_side_and_caps() {
+        let cone = Cone::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 2.0), 2.0, 0.0, 0);
+
+        // The side is the line x + z = 2, whose distance from the origin is sqrt(2)
+        assert!((cone.signed_distance(&vec3(2.0, 0.0, 2.0)) - 2.0_f64.sqrt()).abs() < 1e-9);
+        assert!((cone.signed_distance(&vec3(0.5, 0.0, -1.0)) - 1.0).abs() < 1e-9);
+        assert!((cone.signed_distance(&vec3(0.0, 0.0, 0.1)) + 0.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::HashSet;
+
+use nalgebra_glm::{DMat3, DVec3};
+
+use crate::linalg::{BoundingBox, Plane};
+use crate::shapes::shape::Shape;
+
+/// Tolerance for deciding whether a point lies on a plane, relative to the size of the input
+const RELATIVE_EPSILON: f64 = 1e-9;
+
+/// The intersection of a set of half-spaces, each containing the points behind one of `planes`
+pub struct ConvexPolyhedron {
+    planes: Vec<Plane>,
+    vertices: Vec<DVec3>,
+    object_id: u32,
+}
+
+impl ConvexPolyhedron {
+    /// The planes must enclose a bounded volume. Each plane's normal points out of the polyhedron
+    pub fn from_planes(planes: Vec<Plane>, object_id: u32) -> Result<Self, String> {
+        let vertices = find_vertices(&planes);
+
+        if vertices.len() < 4 || !is_bounded(&planes) {
+            return Err("Convex polyhedron planes do not enclose a volume".to_string());
+        }
+
+        Ok(ConvexPolyhedron {
+            planes,
+            vertices,
+            object_id,
+        })
+    }
+
+    /// Creates the convex hull of `points`
+    pub fn from_points(points: &[DVec3], object_id: u32) -> Result<Self, String> {
+        let faces = compute_convex_hull(points)?;
+
+        let planes = faces
+            .iter()
+            .map(|[a, b, c]| Plane::from_points(&points[*a], &points[*b], &points[*c]))
+            .collect();
+
+        let vertex_indices: HashSet<usize> = faces.iter().flatten().copied().collect();
+        let vertices = vertex_indices.into_iter().map(|i| points[i]).collect();
+
+        Ok(ConvexPolyhedron {
+            planes,
+            vertices,
+            object_id,
+        })
+    }
+
+    /// Moves each face plane so that its distance `d` from the centroid of the vertices becomes
+    /// `inflate_distance(d)`
+    pub fn inflated(self, inflate_distance: impl Fn(f64) -> f64) -> Self {
+        let centroid = self.vertices.iter().sum::<DVec3>() / self.vertices.len() as f64;
+
+        let mut max_scale: f64 = 0.0;
+        let planes = self
+            .planes
+            .iter()
+            .map(|plane| {
+                let distance = plane.signed_distance(&centroid).abs();
+                let inflated_distance = inflate_distance(distance);
+                max_scale = max_scale.max(inflated_distance / distance);
+
+                Plane {
+                    normal: plane.normal,
+                    offset: plane.offset + inflated_distance - distance,
+                }
+            })
+            .collect();
+
+        // No plane moved out further than the largest relative change, so scaling the old corners
+        // by it gives a conservative bounding box without finding the new corners
+        ConvexPolyhedron {
+            planes,
+            vertices: self
+                .vertices
+                .iter()
+                .map(|vertex| centroid + (vertex - centroid) * max_scale)
+                .collect(),
+            object_id: self.object_id,
+        }
+    }
+}
+
+impl Shape for ConvexPolyhedron {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) < 0.0)
+    }
+
+    /// Exact inside. Outside, this is the distance to the furthest face plane, which
+    /// underestimates the distance to edges and corners
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        self.planes
+            .iter()
+            .map(|plane| plane.signed_distance(point))
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        self.vertices.iter().copied().collect()
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+}
+
+fn get_epsilon<'a>(points: impl Iterator<Item = &'a DVec3>) -> f64 {
+    let bounding_box: BoundingBox = points.copied().collect();
+    bounding_box.extent().amax().max(1.0) * RELATIVE_EPSILON
+}
+
+/// Finds the corners of the polyhedron by intersecting every triple of planes and keeping the
+/// intersections that are inside all the other planes. Annotation volumes have few faces, so
+/// the quartic running time is not a concern
+fn find_vertices(planes: &[Plane]) -> Vec<DVec3> {
+    let epsilon = RELATIVE_EPSILON * planes.iter().map(|p| p.offset.abs()).fold(1.0, f64::max);
+    let mut vertices: Vec<DVec3> = vec![];
+
+    for i in 0..planes.len() {
+        for j in (i + 1)..planes.len() {
+            for k in (j + 1)..planes.len() {
+                let Some(vertex) = intersect_planes(&planes[i], &planes[j], &planes[k]) else {
+                    continue;
+                };
+
+                let is_inside = planes
+                    .iter()
+                    .all(|plane| plane.signed_distance(&vertex) <= epsilon);
+                let is_duplicate = vertices
+                    .iter()
+                    .any(|other| (other - vertex).amax() <= epsilon);
+
+                if is_inside && !is_duplicate {
+                    vertices.push(vertex);
+                }
+            }
+        }
+    }
+
+    vertices
+}
+
+/// The planes bound a volume if there is no direction in which it extends forever, that is, no
+/// direction `d` with `dot(normal, d) <= 0` for every plane. When there is such a direction, and
+/// the planes have corners, there is also one along an edge, i.e. orthogonal to two of the normals
+fn is_bounded(planes: &[Plane]) -> bool {
+    let normals: Vec<DVec3> = planes
+        .iter()
+        .map(|plane| plane.normal.normalize())
+        .collect();
+
+    for i in 0..normals.len() {
+        for j in (i + 1)..normals.len() {
+            let edge_direction = normals[i].cross(&normals[j]);
+            if edge_direction.amax() <= RELATIVE_EPSILON {
+                continue;
+            }
+
+            for direction in [edge_direction, -edge_direction] {
+                let direction = direction.normalize();
+                if normals
+                    .iter()
+                    .all(|normal| normal.dot(&direction) <= RELATIVE_EPSILON)
+                {
+                    return false;
+                }
+            }
+        }
+    }
+
+    true
+}
+
+fn intersect_planes(a: &Plane, b: &Plane, c: &Plane) -> Option<DVec3> {
+    let normals = DMat3::from_rows(&[
+        a.normal.transpose(),
+        b.normal.transpose(),
+        c.normal.transpose(),
+    ]);
+    normals
+        .try_inverse()
+        .map(|inverse| inverse * DVec3::new(a.offset, b.offset, c.offset))
+}
+
+/// Incremental convex hull. Returns triangles as point indices, counter-clockwise when seen from
+/// outside the hull
+fn compute_convex_hull(points: &[DVec3]) -> Result<Vec<[usize; 3]>, String> {
+    let epsilon = get_epsilon(points.iter());
+    let mut faces = create_initial_tetrahedron(points, epsilon)?;
+
+    let face_plane = |face: &[usize; 3]| {
+        Plane::from_points(&points[face[0]], &points[face[1]], &points[face[2]])
+    };
+
+    for (point_index, point) in points.iter().enumerate() {
+        let (visible, hidden): (Vec<[usize; 3]>, Vec<[usize; 3]>) = faces
+            .into_iter()
+            .partition(|face| face_plane(face).signed_distance(point) > epsilon);
+
+        faces = hidden;
+
+        if visible.is_empty() {
+            continue;
+        }
+
+        // The horizon consists of the edges of visible faces whose neighbouring face is hidden.
+        // Visible faces share edges in opposite directions
+        let visible_edges: HashSet<(usize, usize)> = visible
+            .iter()
+            .flat_map(|[a, b, c]| [(*a, *b), (*b, *c), (*c, *a)])
+            .collect();
+
+        faces.extend(
+            visible_edges
+                .iter()
+                .filter(|(a, b)| !visible_edges.contains(&(*b, *a)))
+                .map(|(a, b)| [*a, *b, point_index]),
+        );
+    }
+
+    Ok(faces)
+}
+
+fn create_initial_tetrahedron(points: &[DVec3], epsilon: f64) -> Result<Vec<[usize; 3]>, String> {
+    let degenerate_error = || "Convex hull points must span a volume".to_string();
+
+    let a = 0;
+    let b = (0..points.len())
+        .find(|i| (points[*i] - points[a]).amax() > epsilon)
+        .ok_or_else(degenerate_error)?;
+    let c = (0..points.len())
+        .find(|i| {
+            (points[b] - points[a])
+                .cross(&(points[*i] - points[a]))
+                .amax()
+                > epsilon * epsilon
+        })
+        .ok_or_else(degenerate_error)?;
+
+    let base_plane = Plane::from_points(&points[a], &points[b], &points[c]);
+    let d = (0..points.len())
+        .find(|i| base_plane.signed_distance(&points[*i]).abs() > epsilon)
+        .ok_or_else(degenerate_error)?;
+
+    // Orient the base so that the fourth point is behind it
+    let (b, c) = if base_plane.signed_distance(&points[d]) > 0.0 {
+        (c, b)
+    } else {
+        (b, c)
+    };
+
+    Ok(vec![[a, b, c], [a, d, b], [b, d, c], [c, d, a]])
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{vec3, DVec3};
+    use rand::prelude::*;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::ConvexPolyhedron;
+    use crate::linalg::Plane;
+    use crate::shapes::Shape;
+
+    fn create_wedge_planes() -> Vec<Plane> {
+        vec![
+            Plane::new(vec3(0.0, 0.0, -1.0), 0.0),
+            Plane::new(vec3(0.0, -1.0, 0.0), 0.0),
+            Plane::new(vec3(1.0, 0.0, 0.0), 1.0),
+            Plane::new(vec3(-1.0, 0.0, 0.0), 1.0),
+            Plane::new(vec3(0.0, 1.0, 1.0), 1.0),
+        ]
+    }
+
+    #[test]
+    fn wedge_from_planes_contains_points_below_slope() {
+        let wedge = ConvexPolyhedron::from_planes(create_wedge_planes(), 0).unwrap();
+
+        assert!(wedge.contains_point(&vec3(0.5, 0.2, 0.2)));
+        assert!(!wedge.contains_point(&vec3(0.5, 0.6, 0.6)));
+        assert!(!wedge.contains_point(&vec3(1.5, 0.2, 0.2)));
+    }
+
+    #[test]
+    fn wedge_bounding_box_is_bounding_box_of_corners() {
+        let wedge = ConvexPolyhedron::from_planes(create_wedge_planes(), 0).unwrap();
+        let bounding_box = wedge.create_bounding_box();
+
+        assert!((bounding_box.min - vec3(-1.0, 0.0, 0.0)).amax() < 1e-9);
+        assert!((bounding_box.max - vec3(1.0, 1.0, 1.0)).amax() < 1e-9);
+    }
+
+    #[test]
+    fn unbounded_planes_are_rejected() {
+        let planes = vec![
+            Plane::new(vec3(0.0, 0.0, 1.0), 0.0),
+            Plane::new(vec3(0.0, 1.0, 0.0), 0.0),
+        ];
+
+        assert!(ConvexPolyhedron::from_planes(planes, 0).is_err());
+    }
+
+    #[test]
+    fn open_prism_is_rejected() {
+        let planes = vec![
+            Plane::new(vec3(1.0, 0.0, 0.0), 1.0),
+            Plane::new(vec3(-1.0, 0.0, 0.0), 1.0),
+            Plane::new(vec3(0.0, 1.0, 0.0), 1.0),
+            Plane::new(vec3(0.0, -1.0, 0.0), 1.0),
+            Plane::new(vec3(0.0, 0.0, -1.0), 0.0),
+        ];
+
+        assert!(ConvexPolyhedron::from_planes(planes.clone(), 0).is_err());
+
+        let mut closed_planes = planes;
+        closed_planes.push(Plane::new(vec3(0.0, 0.0, 1.0), 2.0));
+        assert!(ConvexPolyhedron::from_planes(closed_planes, 0).is_ok());
+    }
+
+    #[test]
+    fn hull_of_cube_corners_and_interior_points_is_cube() {
+        let mut points: Vec<DVec3> = (0..8)
+            .map(|i| {
+                vec3(
+                    (i & 1) as f64 * 2.0 - 1.0,
+                    ((i >> 1) & 1) as f64 * 2.0 - 1.0,
+                    ((i >> 2) & 1) as f64 * 2.0 - 1.0,
+                )
+            })
+            .collect();
+        points.insert(3, vec3(0.1, 0.2, 0.3));
+        points.push(vec3(-0.5, 0.5, 0.0));
+
+        let cube = ConvexPolyhedron::from_points(&points, 0).unwrap();
+
+        assert!(cube.contains_point(&vec3(0.99, -0.99, 0.99)));
+        assert!(!cube.contains_point(&vec3(1.01, 0.0, 0.0)));
+        assert_eq!(cube.vertices.len(), 8);
+    }
+
+    #[test]
+    fn hull_of_random_points_contains_all_points_when_inflated() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0xbaadf00d);
+        let points: Vec<DVec3> = (0..200)
+            .map(|_| {
+                vec3(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-3.0..3.0),
+                    rng.gen_range(5.0..6.0),
+                )
+            })
+            .collect();
+
+        let hull = ConvexPolyhedron::from_points(&points, 0)
+            .unwrap()
+            .inflated(|distance| distance * 1.01);
+        let bounding_box = hull.create_bounding_box();
+
+        for point in points.iter() {
+            assert!(hull.contains_point(point));
+            assert!(bounding_box.contains_point(point));
+        }
+        assert!(!hull.contains_point(&vec3(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn coplanar_points_are_rejected() {
+        let points = vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(1.0, 1.0, 0.0),
+        ];
+
+        assert!(ConvexPolyhedron::from_points(&points, 0).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use nalgebra_glm::DVec3;
+
+use crate::linalg::BoundingBox;
+use crate::shapes::shape::Shape;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CsgOperator {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Boolean combination of two shapes. For `Difference`, points in `right` are removed from `left`
+pub struct Csg {
+    operator: CsgOperator,
+    left: Box<dyn Shape>,
+    right: Box<dyn Shape>,
+    object_id: u32,
+}
+
+impl Csg {
+    pub fn new(
+        operator: CsgOperator,
+        left: Box<dyn Shape>,
+        right: Box<dyn Shape>,
+        object_id: u32,
+    ) -> Self {
+        Csg {
+            operator,
+            left,
+            right,
+            object_id,
+        }
+    }
+}
+
+impl Shape for Csg {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        match self.operator {
+            CsgOperator::Union => {
+                self.left.contains_point(point) || self.right.contains_point(point)
+            }
+            CsgOperator::Intersection => {
+                self.left.contains_point(point) && self.right.contains_point(point)
+            }
+            CsgOperator::Difference => {
+                self.left.contains_point(point) && !self.right.contains_point(point)
+            }
+        }
+    }
+
+    /// Combines the distances of the operands, which is exact outside of unions and inside of
+    /// intersections, and a bound elsewhere
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        let left_distance = self.left.signed_distance(point);
+        let right_distance = self.right.signed_distance(point);
+
+        match self.operator {
+            CsgOperator::Union => left_distance.min(right_distance),
+            CsgOperator::Intersection => left_distance.max(right_distance),
+            CsgOperator::Difference => left_distance.max(-right_distance),
+        }
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        let left_box = self.left.create_bounding_box();
+
+        match self.operator {
+            CsgOperator::Union => left_box.union(&self.right.create_bounding_box()),
+            CsgOperator::Intersection => left_box.intersection(&self.right.create_bounding_box()),
+            // Subtracting can only shrink the shape, so the left box is the tightest cheap bound
+            CsgOperator::Difference => left_box,
+        }
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{inverse, scale, vec3, DMat4};
+
+    use super::{Csg, CsgOperator};
+    use crate::shapes::{Cylinder, HalfSpace, OrientedBox, Shape};
+
+    fn create_pipe() -> Csg {
+        let outer = Cylinder::new(vec3(0.0, 0.0, -1.0), vec3(0.0, 0.0, 1.0), 1.0, 0);
+        let bore = Cylinder::new(vec3(0.0, 0.0, -2.0), vec3(0.0, 0.0, 2.0), 0.8, 0);
+
+        Csg::new(CsgOperator::Difference, Box::new(outer), Box::new(bore), 3)
+    }
+
+    #[test]
+    fn pipe_contains_wall_but_not_bore() {
+        let pipe = create_pipe();
+
+        assert!(pipe.contains_point(&vec3(0.9, 0.0, 0.0)));
+        assert!(!pipe.contains_point(&vec3(0.0, 0.0, 0.0)));
+        assert!(!pipe.contains_point(&vec3(1.1, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn difference_bounding_box_is_left_bounding_box() {
+        let pipe = create_pipe();
+        let bounding_box = pipe.create_bounding_box();
+
+        assert!(bounding_box.contains_point(&vec3(0.0, 0.0, 0.9)));
+        assert!(!bounding_box.contains_point(&vec3(0.0, 0.0, 1.5)));
+    }
+
+    #[test]
+    fn box_clipped_by_half_space_contains_only_lower_half() {
+        let oriented_box = OrientedBox::new(DMat4::identity(), 0);
+        let half_space = HalfSpace::new(vec3(0.0, 0.0, 1.0), 0.0, 0);
+        let clipped = Csg::new(
+            CsgOperator::Intersection,
+            Box::new(oriented_box),
+            Box::new(half_space),
+            0,
+        );
+
+        assert!(clipped.contains_point(&vec3(0.5, 0.5, -0.5)));
+        assert!(!clipped.contains_point(&vec3(0.5, 0.5, 0.5)));
+
+        let bounding_box = clipped.create_bounding_box();
+        assert_eq!(bounding_box.max.z, 0.0);
+        assert_eq!(bounding_box.min.z, -1.0);
+    }
+
+    #[test]
+    fn union_contains_points_from_both_shapes() {
+        let left = OrientedBox::new(DMat4::identity(), 0);
+        let right = OrientedBox::new(inverse(&scale(&DMat4::identity(), &vec3(3.0, 0.5, 0.5))), 0);
+        let union = Csg::new(CsgOperator::Union, Box::new(left), Box::new(right), 0);
+
+        assert!(union.contains_point(&vec3(0.0, 0.9, 0.0)));
+        assert!(union.contains_point(&vec3(2.5, 0.0, 0.0)));
+        assert!(!union.contains_point(&vec3(2.5, 0.9, 0.0)));
+
+        let bounding_box = union.create_bounding_box();
+        assert!(bounding_box.contains_point(&vec3(2.9, 0.9, 0.0)));
+    }
+
+    #[test]
+    fn pipe_wall_signed_distance_is_negative_in_wall_only() {
+        let pipe = create_pipe();
+
+        assert!((pipe.signed_distance(&vec3(0.9, 0.0, 0.0)) + 0.1).abs() < 1e-9);
+        assert!((pipe.signed_distance(&vec3(0.5, 0.0, 0.0)) - 0.3).abs() < 1e-9);
+        assert!((pipe.signed_distance(&vec3(1.5, 0.0, 0.0)) - 0.5).abs() < 1e-9);
+    }
+}
@@ -1,22 +1,24 @@
-use nalgebra_glm::{dot, mat3_to_mat4, vec3, vec4, DMat3, DMat4, DVec3};
+use std::f64::consts::PI;
 
-use crate::linalg::BoundingBox;
+use nalgebra_glm::{distance, dot, mat3_to_mat4, vec3, vec4, DMat3, DMat4, DVec3};
+
+use crate::linalg::{closest_point_on_segment, signed_distance_to_extrusion, BoundingBox};
 use crate::shapes::shape::Shape;
 
 pub struct Cylinder {
     center_a: DVec3,
     center_b: DVec3,
     radius: f64,
-    object_id: u16,
+    object_id: u32,
 }
 
 impl Cylinder {
-    pub fn new(center_a: DVec3, center_b: DVec3, radius: f64, object_id: u16) -> Self {
+    pub fn new(center_a: DVec3, center_b: DVec3, radius: f64, object_id: u32) -> Self {
         Cylinder {
-            center_a: center_a,
-            center_b: center_b,
-            radius: radius,
-            object_id: object_id,
+            center_a,
+            center_b,
+            radius,
+            object_id,
         }
     }
 
@@ -69,6 +71,20 @@ impl Shape for Cylinder {
         dist_along_axis.abs() < half_height && dist_to_axis < self.radius
     }
 
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        let center = self.get_center();
+        let axis = (self.center_a - self.center_b).normalize();
+        let half_height = (self.center_a - self.center_b).magnitude() / 2.0;
+
+        let dist_along_axis = dot(&(point - center), &axis);
+        let dist_to_axis = (point - center - axis * dist_along_axis).magnitude();
+
+        signed_distance_to_extrusion(
+            dist_to_axis - self.radius,
+            dist_along_axis.abs() - half_height,
+        )
+    }
+
     fn create_bounding_box(&self) -> BoundingBox {
         let center = self.get_center();
         let scaled_basis = self.get_scaled_orthogonal_basis();
@@ -77,27 +93,37 @@ impl Shape for Cylinder {
         BoundingBox::get_transformed_base_cube(&matrix)
     }
 
-    fn get_object_id(&self) -> u16 {
+    fn get_object_id(&self) -> u32 {
         self.object_id
     }
+
+    fn get_volume(&self) -> f64 {
+        PI * self.radius * self.radius * distance(&self.center_a, &self.center_b)
+    }
+
+    fn get_distance_to_core(&self, point: &DVec3) -> f64 {
+        distance(
+            point,
+            &closest_point_on_segment(point, &self.center_a, &self.center_b),
+        ) - self.radius
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use nalgebra_glm::vec3;
-    use wasm_bindgen_test::wasm_bindgen_test;
 
     use super::Cylinder;
     use crate::shapes::Shape;
 
-    #[wasm_bindgen_test]
+    #[test]
     fn cylinder_at_origin_contains_middle_point() {
         let cylinder = Cylinder::new(vec3(0.0, -0.5, 0.0), vec3(0.0, 0.5, 0.0), 1.0, 0);
 
         assert!(cylinder.contains_point(&vec3(0.0, 0.0, 0.0)));
     }
 
-    #[wasm_bindgen_test]
+    #[test]
     fn thin_diagonal_cylinder_contains_point_in_middle() {
         let center_a = vec3(213.0, -33.0, 983.12);
         let center_b = vec3(-12.0, 234.0, -10.0);
@@ -111,7 +137,7 @@ mod tests {
         assert!(!cylinder.contains_point(&outside_middle));
     }
 
-    #[wasm_bindgen_test]
+    #[test]
     fn cylinder_bounding_box_contains_centers_but_not_more_along_axis() {
         let center_a = vec3(21.0, -33.0, 98.0);
         let center_b = vec3(-12.0, 23.0, -10.0);
@@ -125,4 +151,14 @@ mod tests {
         assert!(bounding_box.contains_point(&center_b));
         assert!(!bounding_box.contains_point(&(center_a + axis)));
     }
+
+    #[test]
+    fn cylinder_signed_distance_to_side_caps_and_rim() {
+        let cylinder = Cylinder::new(vec3(0.0, 0.0, -1.0), vec3(0.0, 0.0, 1.0), 1.0, 0);
+
+        assert!((cylinder.signed_distance(&vec3(0.0, 0.0, 0.0)) + 1.0).abs() < 1e-9);
+        assert!((cylinder.signed_distance(&vec3(3.0, 0.0, 0.0)) - 2.0).abs() < 1e-9);
+        assert!((cylinder.signed_distance(&vec3(0.0, 0.5, 1.5)) - 0.5).abs() < 1e-9);
+        assert!((cylinder.signed_distance(&vec3(4.0, 0.0, 5.0)) - 5.0).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,123 @@
+use std::f64::consts::PI;
+
+use crate::linalg::{get_instance_half_extents, BoundingBox};
+
+use nalgebra_glm::{inverse, mat4_to_mat3, vec4, vec4_to_vec3, DMat4, DVec3};
+
+use crate::shapes::shape;
+
+pub struct Ellipsoid {
+    inv_instance_matrix: DMat4,
+    object_id: u32,
+}
+
+impl Ellipsoid {
+    pub fn new(inv_instance_matrix: DMat4, object_id: u32) -> Self {
+        Ellipsoid {
+            inv_instance_matrix,
+            object_id,
+        }
+    }
+}
+
+impl shape::Shape for Ellipsoid {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        let transformed_point =
+            vec4_to_vec3(&(self.inv_instance_matrix * vec4(point.x, point.y, point.z, 1.0)));
+        transformed_point.magnitude_squared() < 1.0
+    }
+
+    /// Approximation that is exact on the surface and for spheres
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        let radii = get_instance_half_extents(&self.inv_instance_matrix);
+        let transformed_point =
+            vec4_to_vec3(&(self.inv_instance_matrix * vec4(point.x, point.y, point.z, 1.0)));
+        let local_point = transformed_point.component_mul(&radii);
+
+        let gradient_length = local_point
+            .component_div(&radii.component_mul(&radii))
+            .magnitude();
+
+        if gradient_length == 0.0 {
+            return -radii.min();
+        }
+
+        let scaled_length = transformed_point.magnitude();
+        scaled_length * (scaled_length - 1.0) / gradient_length
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        let instance_matrix = inverse(&self.inv_instance_matrix);
+
+        BoundingBox::get_transformed_base_cube(&instance_matrix)
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+
+    fn get_volume(&self) -> f64 {
+        4.0 / 3.0 * PI / mat4_to_mat3(&self.inv_instance_matrix).determinant().abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Ellipsoid;
+    use crate::shapes::Shape;
+
+    use nalgebra_glm::{inverse, rotate_z, scale, translate, vec3, vec4, vec4_to_vec3, DMat4};
+
+    #[test]
+    fn identity_ellipsoid_is_unit_sphere() {
+        let ellipsoid = Ellipsoid::new(DMat4::identity(), 0);
+
+        assert!(ellipsoid.contains_point(&vec3(0.0, 0.0, 0.0)));
+        assert!(ellipsoid.contains_point(&vec3(0.0, 0.99, 0.0)));
+        assert!(!ellipsoid.contains_point(&vec3(0.6, 0.6, 0.6)));
+    }
+
+    #[test]
+    fn scaled_ellipsoid_contains_only_points_in_scaled_direction() {
+        let ellipsoid =
+            Ellipsoid::new(inverse(&scale(&DMat4::identity(), &vec3(3.0, 1.0, 1.0))), 0);
+
+        assert!(ellipsoid.contains_point(&vec3(2.5, 0.0, 0.0)));
+        assert!(!ellipsoid.contains_point(&vec3(0.0, 2.5, 0.0)));
+        assert!(!ellipsoid.contains_point(&vec3(2.5, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn bounding_box_contains_transformed_axis_ends() {
+        let matrix = scale(
+            &rotate_z(&translate(&DMat4::identity(), &vec3(5.0, -2.0, 1.0)), 0.7),
+            &vec3(4.0, 1.0, 2.0),
+        );
+
+        let ellipsoid = Ellipsoid::new(inverse(&matrix), 0);
+        let bounding_box = ellipsoid.create_bounding_box();
+
+        for axis_end in [
+            vec4(0.99, 0.0, 0.0, 1.0),
+            vec4(0.0, -0.99, 0.0, 1.0),
+            vec4(0.0, 0.0, 0.99, 1.0),
+        ] {
+            let world_point = vec4_to_vec3(&(matrix * axis_end));
+            assert!(ellipsoid.contains_point(&world_point));
+            assert!(bounding_box.contains_point(&world_point));
+        }
+    }
+
+    #[test]
+    fn ellipsoid_signed_distance_has_correct_sign_and_surface_value() {
+        let instance_matrix = scale(&DMat4::identity(), &vec3(3.0, 1.0, 1.0));
+        let ellipsoid = Ellipsoid::new(inverse(&instance_matrix), 0);
+
+        assert!(ellipsoid.signed_distance(&vec3(0.0, 0.0, 0.0)) < 0.0);
+        assert!(ellipsoid.signed_distance(&vec3(2.5, 0.0, 0.0)) < 0.0);
+        assert!(ellipsoid.signed_distance(&vec3(0.0, 1.5, 0.0)) > 0.0);
+        assert!(ellipsoid.signed_distance(&vec3(3.0, 0.0, 0.0)).abs() < 1e-9);
+        assert!((ellipsoid.signed_distance(&vec3(4.0, 0.0, 0.0)) - 1.0).abs() < 0.5);
+    }
+}
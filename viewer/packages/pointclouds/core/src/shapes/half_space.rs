@@ -0,0 +1,90 @@
+use nalgebra_glm::DVec3;
+
+use crate::linalg::{BoundingBox, Plane};
+use crate::shapes::shape::Shape;
+
+/// All points `p` satisfying `dot(normal, p) <= offset`, i.e. the side of the plane
+/// opposite to where the normal points.
+pub struct HalfSpace {
+    plane: Plane,
+    object_id: u32,
+}
+
+impl HalfSpace {
+    pub fn new(normal: DVec3, offset: f64, object_id: u32) -> Self {
+        HalfSpace {
+            plane: Plane::new(normal, offset),
+            object_id,
+        }
+    }
+}
+
+impl Shape for HalfSpace {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        self.plane.signed_distance(point) <= 0.0
+    }
+
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        self.plane.signed_distance(point)
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        let mut bounding_box = BoundingBox::infinite();
+
+        // Only half-spaces aligned with a coordinate axis have a finite side
+        for axis in 0..3 {
+            let normal = &self.plane.normal;
+            let is_axis_aligned = (0..3).all(|other| other == axis || normal[other] == 0.0);
+            if !is_axis_aligned {
+                continue;
+            }
+
+            if normal[axis] > 0.0 {
+                bounding_box.max[axis] = self.plane.offset / normal[axis];
+            } else {
+                bounding_box.min[axis] = self.plane.offset / normal[axis];
+            }
+        }
+
+        bounding_box
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::HalfSpace;
+    use crate::shapes::Shape;
+
+    #[test]
+    fn half_space_contains_points_behind_plane_only() {
+        let half_space = HalfSpace::new(vec3(0.0, 0.0, 2.0), 2.0, 0);
+
+        assert!(half_space.contains_point(&vec3(10.0, -3.0, 0.5)));
+        assert!(!half_space.contains_point(&vec3(0.0, 0.0, 1.5)));
+    }
+
+    #[test]
+    fn axis_aligned_half_space_bounding_box_is_bounded_on_one_side() {
+        let half_space = HalfSpace::new(vec3(0.0, -1.0, 0.0), 3.0, 0);
+        let bounding_box = half_space.create_bounding_box();
+
+        assert_eq!(bounding_box.min.y, -3.0);
+        assert_eq!(bounding_box.max.y, f64::INFINITY);
+        assert_eq!(bounding_box.min.x, f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn tilted_half_space_bounding_box_is_infinite() {
+        let half_space = HalfSpace::new(vec3(1.0, 1.0, 0.0), 0.0, 0);
+        let bounding_box = half_space.create_bounding_box();
+
+        assert_eq!(bounding_box.max.x, f64::INFINITY);
+        assert_eq!(bounding_box.min.y, f64::NEG_INFINITY);
+    }
+}
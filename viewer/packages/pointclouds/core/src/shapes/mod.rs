@@ -0,0 +1,31 @@
+mod capsule;
+mod composite;
+mod cone;
+mod convex_polyhedron;
+mod csg;
+mod cylinder;
+mod ellipsoid;
+mod half_space;
+mod oriented_box;
+mod prism;
+mod shape;
+mod sphere;
+mod swept_pipe;
+mod torus_segment;
+mod triangle_mesh;
+
+pub use capsule::Capsule;
+pub use composite::Composite;
+pub use cone::Cone;
+pub use convex_polyhedron::ConvexPolyhedron;
+pub use csg::{Csg, CsgOperator};
+pub use cylinder::Cylinder;
+pub use ellipsoid::Ellipsoid;
+pub use half_space::HalfSpace;
+pub use oriented_box::OrientedBox;
+pub use prism::Prism;
+pub use shape::Shape;
+pub use sphere::Sphere;
+pub use swept_pipe::SweptPipe;
+pub use torus_segment::TorusSegment;
+pub use triangle_mesh::TriangleMeshShape;
@@ -1,19 +1,19 @@
-use crate::linalg::BoundingBox;
+use crate::linalg::{get_instance_half_extents, signed_distance_to_box, BoundingBox};
 
-use nalgebra_glm::{inverse, vec4, vec4_to_vec3, DMat4, DVec3};
+use nalgebra_glm::{inverse, mat4_to_mat3, vec4, vec4_to_vec3, DMat4, DVec3};
 
 use crate::shapes::shape;
 
 pub struct OrientedBox {
     inv_instance_matrix: DMat4,
-    object_id: u16,
+    object_id: u32,
 }
 
 impl OrientedBox {
-    pub fn new(inv_instance_matrix: DMat4, object_id: u16) -> Self {
+    pub fn new(inv_instance_matrix: DMat4, object_id: u32) -> Self {
         OrientedBox {
-            inv_instance_matrix: inv_instance_matrix,
-            object_id: object_id,
+            inv_instance_matrix,
+            object_id,
         }
     }
 }
@@ -25,20 +25,34 @@ impl shape::Shape for OrientedBox {
         BoundingBox::get_base_cube_bounding_box().contains_point(&transformed_point)
     }
 
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        let half_extents = get_instance_half_extents(&self.inv_instance_matrix);
+        let transformed_point =
+            vec4_to_vec3(&(self.inv_instance_matrix * vec4(point.x, point.y, point.z, 1.0)));
+
+        signed_distance_to_box(
+            &transformed_point.component_mul(&half_extents),
+            &half_extents,
+        )
+    }
+
     fn create_bounding_box(&self) -> BoundingBox {
         let instance_matrix = inverse(&self.inv_instance_matrix);
 
         BoundingBox::get_transformed_base_cube(&instance_matrix)
     }
 
-    fn get_object_id(&self) -> u16 {
+    fn get_object_id(&self) -> u32 {
         self.object_id
     }
+
+    fn get_volume(&self) -> f64 {
+        8.0 / mat4_to_mat3(&self.inv_instance_matrix).determinant().abs()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use wasm_bindgen_test::wasm_bindgen_test;
 
     use super::OrientedBox;
     use crate::shapes::Shape;
@@ -48,13 +62,13 @@ mod tests {
         DMat4,
     };
 
-    #[wasm_bindgen_test]
+    #[test]
     fn identity_oriented_box_contains_origin() {
         let ob = OrientedBox::new(DMat4::identity(), 0);
         assert!(ob.contains_point(&vec3(0.0, 0.0, 0.0)));
     }
 
-    #[wasm_bindgen_test]
+    #[test]
     fn identity_oriented_box_does_not_contain_doubled_cardinal_unit_vectors() {
         let ob = OrientedBox::new(DMat4::identity(), 0);
         assert!(!ob.contains_point(&vec3(2.0, 0.0, 0.0)));
@@ -62,7 +76,7 @@ mod tests {
         assert!(!ob.contains_point(&vec3(0.0, 0.0, 2.0)));
     }
 
-    #[wasm_bindgen_test]
+    #[test]
     fn translated_oriented_box_contains_only_translated_origin() {
         let ob = OrientedBox::new(
             nalgebra_glm::inverse(&translate(&DMat4::identity(), &vec3(2.0, 2.0, 2.0))),
@@ -72,7 +86,7 @@ mod tests {
         assert!(ob.contains_point(&vec3(2.0, 2.0, 2.0)));
     }
 
-    #[wasm_bindgen_test]
+    #[test]
     fn scaled_oriented_box_contains_only_points_in_scaled_direction() {
         let ob = OrientedBox::new(inverse(&scale(&DMat4::identity(), &vec3(3.0, 1.0, 1.0))), 0);
         assert!(ob.contains_point(&vec3(2.0, 0.0, 0.0)));
@@ -80,7 +94,7 @@ mod tests {
         assert!(!ob.contains_point(&vec3(0.0, 0.0, 2.0)));
     }
 
-    #[wasm_bindgen_test]
+    #[test]
     fn scaled_and_rotated_oriented_box_contains_the_right_points() {
         let matrix = inverse(&scale(
             &rotate_x(&DMat4::identity(), half_pi()),
@@ -93,7 +107,7 @@ mod tests {
         assert!(ob.contains_point(&vec3(0.0, 0.0, 2.0)));
     }
 
-    #[wasm_bindgen_test]
+    #[test]
     fn identitys_bounding_box_is_identity() {
         let original_box = OrientedBox::new(DMat4::identity(), 0);
         let bounding_box = original_box.create_bounding_box();
@@ -102,7 +116,7 @@ mod tests {
         assert!(comp_max(&abs(&(bounding_box.max - vec3(1.0, 1.0, 1.0)))) < 1e-2);
     }
 
-    #[wasm_bindgen_test]
+    #[test]
     fn bounding_box_contains_transformed_origin() {
         let matrix = translate(
             &scale(&rotate_x(&DMat4::identity(), 1.0), &vec3(2.0, 1.0, 0.5)),
@@ -115,4 +129,15 @@ mod tests {
         let transformed_origin = vec4_to_vec3(&(matrix * vec4(0.0, 0.0, 0.0, 1.0)));
         bounding_box.contains_point(&transformed_origin);
     }
+
+    #[test]
+    fn scaled_box_signed_distance_is_in_world_units() {
+        let instance_matrix = scale(&DMat4::identity(), &vec3(2.0, 1.0, 3.0));
+        let oriented_box = OrientedBox::new(inverse(&instance_matrix), 0);
+
+        assert!((oriented_box.signed_distance(&vec3(0.0, 0.0, 0.0)) + 1.0).abs() < 1e-9);
+        assert!((oriented_box.signed_distance(&vec3(1.5, 0.0, 0.0)) + 0.5).abs() < 1e-9);
+        assert!((oriented_box.signed_distance(&vec3(0.0, 0.0, 4.0)) - 1.0).abs() < 1e-9);
+        assert!((oriented_box.signed_distance(&vec3(5.0, 5.0, 0.0)) - 5.0).abs() < 1e-9);
+    }
 }
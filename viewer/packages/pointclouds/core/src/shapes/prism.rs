@@ -0,0 +1,248 @@
+use nalgebra_glm::{dot, DVec2, DVec3};
+
+use crate::linalg::{signed_distance_to_extrusion, BoundingBox};
+use crate::shapes::shape::Shape;
+
+/// A 2D polygon extruded along the normal of the plane it is drawn in. The polygon lives in the
+/// local frame spanned by `x_axis` and `y_axis` at `origin`, and is extruded from `min_height`
+/// to `max_height` along their cross product. Rings are combined with the even-odd rule, so the
+/// first ring is the outline and any further rings are holes
+pub struct Prism {
+    origin: DVec3,
+    x_axis: DVec3,
+    y_axis: DVec3,
+    normal: DVec3,
+    rings: Vec<Vec<DVec2>>,
+    min_height: f64,
+    max_height: f64,
+    object_id: u32,
+}
+
+impl Prism {
+    /// The axes are orthonormalized, keeping the direction of `x_axis`
+    pub fn new(
+        origin: DVec3,
+        x_axis: DVec3,
+        y_axis: DVec3,
+        rings: Vec<Vec<DVec2>>,
+        height_range: (f64, f64),
+        object_id: u32,
+    ) -> Self {
+        let x_axis = x_axis.normalize();
+        let normal = x_axis.cross(&y_axis).normalize();
+
+        Prism {
+            origin,
+            x_axis,
+            y_axis: normal.cross(&x_axis),
+            normal,
+            rings,
+            min_height: height_range.0,
+            max_height: height_range.1,
+            object_id,
+        }
+    }
+
+    fn to_world(&self, local_point: &DVec2, height: f64) -> DVec3 {
+        self.origin
+            + self.x_axis * local_point.x
+            + self.y_axis * local_point.y
+            + self.normal * height
+    }
+}
+
+/// Even-odd crossing test of a horizontal ray from `point` against all ring edges
+fn is_inside_rings(rings: &[Vec<DVec2>], point: &DVec2) -> bool {
+    let mut inside = false;
+
+    for ring in rings {
+        for (i, current) in ring.iter().enumerate() {
+            let previous = &ring[(i + ring.len() - 1) % ring.len()];
+
+            if (current.y > point.y) != (previous.y > point.y) {
+                let crossing_x = current.x
+                    + (point.y - current.y) * (previous.x - current.x) / (previous.y - current.y);
+
+                if point.x < crossing_x {
+                    inside = !inside;
+                }
+            }
+        }
+    }
+
+    inside
+}
+
+fn distance_to_segment_2d(point: &DVec2, start: &DVec2, end: &DVec2) -> f64 {
+    let segment = end - start;
+    let length_squared = segment.magnitude_squared();
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (dot(&(point - start), &segment) / length_squared).clamp(0.0, 1.0)
+    };
+
+    (point - (start + segment * t)).magnitude()
+}
+
+impl Shape for Prism {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        let relative_point = point - self.origin;
+        let height = dot(&relative_point, &self.normal);
+
+        if height <= self.min_height || height >= self.max_height {
+            return false;
+        }
+
+        let local_point = DVec2::new(
+            dot(&relative_point, &self.x_axis),
+            dot(&relative_point, &self.y_axis),
+        );
+
+        is_inside_rings(&self.rings, &local_point)
+    }
+
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        let relative_point = point - self.origin;
+        let height = dot(&relative_point, &self.normal);
+        let local_point = DVec2::new(
+            dot(&relative_point, &self.x_axis),
+            dot(&relative_point, &self.y_axis),
+        );
+
+        let dist_to_outline = self
+            .rings
+            .iter()
+            .flat_map(|ring| {
+                ring.iter()
+                    .enumerate()
+                    .map(|(i, current)| (&ring[(i + ring.len() - 1) % ring.len()], current))
+            })
+            .map(|(previous, current)| distance_to_segment_2d(&local_point, previous, current))
+            .fold(f64::INFINITY, f64::min);
+
+        let cross_section_distance = if is_inside_rings(&self.rings, &local_point) {
+            -dist_to_outline
+        } else {
+            dist_to_outline
+        };
+
+        signed_distance_to_extrusion(
+            cross_section_distance,
+            (self.min_height - height).max(height - self.max_height),
+        )
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        // Holes cannot reach outside the outline, so the outline corners suffice
+        self.rings
+            .first()
+            .into_iter()
+            .flatten()
+            .flat_map(|corner| {
+                [
+                    self.to_world(corner, self.min_height),
+                    self.to_world(corner, self.max_height),
+                ]
+            })
+            .collect()
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{vec2, vec3, DVec2};
+
+    use super::Prism;
+    use crate::shapes::Shape;
+
+    fn create_square_ring(min: f64, max: f64) -> Vec<DVec2> {
+        vec![
+            vec2(min, min),
+            vec2(max, min),
+            vec2(max, max),
+            vec2(min, max),
+        ]
+    }
+
+    #[test]
+    fn concave_floor_area_excludes_notch_and_heights_outside_range() {
+        let outline = vec![
+            vec2(0.0, 0.0),
+            vec2(4.0, 0.0),
+            vec2(4.0, 4.0),
+            vec2(2.0, 1.0),
+            vec2(0.0, 4.0),
+        ];
+        let prism = Prism::new(
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec![outline],
+            (0.0, 3.0),
+            0,
+        );
+
+        assert!(prism.contains_point(&vec3(0.5, 0.5, 1.0)));
+        assert!(prism.contains_point(&vec3(3.5, 3.0, 1.0)));
+        assert!(!prism.contains_point(&vec3(2.0, 3.0, 1.0)));
+        assert!(!prism.contains_point(&vec3(0.5, 0.5, 3.5)));
+        assert!(!prism.contains_point(&vec3(0.5, 0.5, -0.5)));
+    }
+
+    #[test]
+    fn prism_with_hole_excludes_hole() {
+        let prism = Prism::new(
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec![create_square_ring(0.0, 3.0), create_square_ring(1.0, 2.0)],
+            (0.0, 1.0),
+            0,
+        );
+
+        assert!(prism.contains_point(&vec3(0.5, 1.5, 0.5)));
+        assert!(!prism.contains_point(&vec3(1.5, 1.5, 0.5)));
+    }
+
+    #[test]
+    fn prism_in_vertical_plane_uses_local_frame() {
+        // Polygon drawn in the XZ-plane, extruded along negative Y
+        let prism = Prism::new(
+            vec3(10.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 0.0, 1.0),
+            vec![create_square_ring(0.0, 1.0)],
+            (0.0, 2.0),
+            0,
+        );
+
+        assert!(prism.contains_point(&vec3(10.5, -1.5, 0.5)));
+        assert!(!prism.contains_point(&vec3(10.5, 1.5, 0.5)));
+
+        let bounding_box = prism.create_bounding_box();
+        assert!((bounding_box.min - vec3(10.0, -2.0, 0.0)).amax() < 1e-9);
+        assert!((bounding_box.max - vec3(11.0, 0.0, 1.0)).amax() < 1e-9);
+    }
+
+    #[test]
+    fn prism_signed_distance_to_walls_hole_and_top() {
+        let prism = Prism::new(
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec![create_square_ring(0.0, 3.0), create_square_ring(1.0, 2.0)],
+            (0.0, 1.0),
+            0,
+        );
+
+        assert!((prism.signed_distance(&vec3(0.25, 1.5, 0.5)) + 0.25).abs() < 1e-9);
+        assert!((prism.signed_distance(&vec3(1.5, 1.5, 0.5)) - 0.5).abs() < 1e-9);
+        assert!((prism.signed_distance(&vec3(0.5, 1.5, 3.0)) - 2.0).abs() < 1e-9);
+        assert!((prism.signed_distance(&vec3(-3.0, 1.5, 5.0)) - 5.0).abs() < 1e-9);
+    }
+}
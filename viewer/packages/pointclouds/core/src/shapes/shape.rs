@@ -0,0 +1,27 @@
+use nalgebra_glm::DVec3;
+
+use crate::linalg::BoundingBox;
+
+pub trait Shape {
+    fn create_bounding_box(&self) -> BoundingBox;
+    fn contains_point(&self, point: &DVec3) -> bool;
+    /// Distance from `point` to the surface of the shape, negative inside. Shapes where the exact
+    /// distance is expensive return an approximation that still has the correct sign
+    fn signed_distance(&self, point: &DVec3) -> f64;
+    fn get_object_id(&self) -> u32;
+
+    /// Used to prefer smaller shapes where shapes overlap. Shapes without a closed-form volume
+    /// report the volume of their bounding box
+    fn get_volume(&self) -> f64 {
+        let extent = self.create_bounding_box().extent();
+        extent.x * extent.y * extent.z
+    }
+
+    /// Lower the deeper `point` is inside the shape. Used to prefer the shape a point is most
+    /// centered in where shapes overlap. Swept shapes measure from their center line, as the
+    /// distance to it minus the radius, so that points near the ends of a pipe still count as
+    /// central. Other shapes use the signed distance
+    fn get_distance_to_core(&self, point: &DVec3) -> f64 {
+        self.signed_distance(point)
+    }
+}
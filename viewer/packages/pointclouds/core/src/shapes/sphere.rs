@@ -0,0 +1,84 @@
+use std::f64::consts::PI;
+
+use nalgebra_glm::{distance, vec3, DVec3};
+
+use crate::linalg::BoundingBox;
+use crate::shapes::shape::Shape;
+
+pub struct Sphere {
+    center: DVec3,
+    radius: f64,
+    object_id: u32,
+}
+
+impl Sphere {
+    pub fn new(center: DVec3, radius: f64, object_id: u32) -> Self {
+        Sphere {
+            center,
+            radius,
+            object_id,
+        }
+    }
+}
+
+impl Shape for Sphere {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        (point - self.center).magnitude_squared() < self.radius * self.radius
+    }
+
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        distance(point, &self.center) - self.radius
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        let extent = vec3(self.radius, self.radius, self.radius);
+
+        BoundingBox {
+            min: self.center - extent,
+            max: self.center + extent,
+        }
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+
+    fn get_volume(&self) -> f64 {
+        4.0 / 3.0 * PI * self.radius.powi(3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::Sphere;
+    use crate::shapes::Shape;
+
+    #[test]
+    fn sphere_contains_center_but_not_point_outside_radius() {
+        let center = vec3(3.0, -2.0, 10.0);
+        let sphere = Sphere::new(center, 0.5, 0);
+
+        assert!(sphere.contains_point(&center));
+        assert!(sphere.contains_point(&(center + vec3(0.0, 0.49, 0.0))));
+        assert!(!sphere.contains_point(&(center + vec3(0.3, 0.3, 0.3))));
+    }
+
+    #[test]
+    fn sphere_bounding_box_is_tight() {
+        let sphere = Sphere::new(vec3(1.0, 2.0, 3.0), 2.0, 0);
+        let bounding_box = sphere.create_bounding_box();
+
+        assert_eq!(bounding_box.min, vec3(-1.0, 0.0, 1.0));
+        assert_eq!(bounding_box.max, vec3(3.0, 4.0, 5.0));
+    }
+
+    #[test]
+    fn sphere_signed_distance_is_negative_inside() {
+        let sphere = Sphere::new(vec3(1.0, 0.0, 0.0), 2.0, 0);
+
+        assert!((sphere.signed_distance(&vec3(1.0, 0.0, 0.0)) + 2.0).abs() < 1e-9);
+        assert!((sphere.signed_distance(&vec3(1.0, 5.0, 0.0)) - 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,265 @@
+use std::f64::consts::PI;
+
+use nalgebra_glm::{angle, DVec3};
+
+use crate::bvh::Bvh;
+use crate::linalg::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::shapes::{Cylinder, Sphere, TorusSegment};
+
+/// Joints turning less than this are treated as straight
+const MIN_BEND_ANGLE: f64 = 1e-6;
+
+/// A pipe of constant `radius` running along a polyline. Each interior vertex may have a bend
+/// radius, in which case the straight runs are joined by a torus segment tangent to both.
+/// Other joints are rounded off with a sphere, and the two pipe ends are flat
+pub struct SweptPipe {
+    pieces: Vec<Box<dyn Shape>>,
+    piece_hierarchy: Bvh,
+    object_id: u32,
+}
+
+struct Bend {
+    trim_length: f64,
+    piece: Box<dyn Shape>,
+}
+
+impl SweptPipe {
+    /// `bend_radii[i]` belongs to `vertices[i + 1]`. Missing or zero bend radii give sharp joints.
+    /// Consecutive vertices must differ
+    pub fn new(vertices: &[DVec3], radius: f64, bend_radii: &[f64], object_id: u32) -> Self {
+        let bends: Vec<Option<Bend>> = (1..vertices.len().saturating_sub(1))
+            .map(|vertex_index| {
+                create_bend(
+                    vertices,
+                    vertex_index,
+                    radius,
+                    bend_radii.get(vertex_index - 1).copied().unwrap_or(0.0),
+                    object_id,
+                )
+            })
+            .collect();
+
+        let mut pieces: Vec<Box<dyn Shape>> = Vec::with_capacity(vertices.len() + bends.len());
+
+        for (segment_index, segment) in vertices.windows(2).enumerate() {
+            let direction = (segment[1] - segment[0]).normalize();
+
+            let start_trim = match segment_index.checked_sub(1).map(|i| &bends[i]) {
+                Some(Some(bend)) => bend.trim_length,
+                _ => 0.0,
+            };
+            let end_trim = match bends.get(segment_index) {
+                Some(Some(bend)) => bend.trim_length,
+                _ => 0.0,
+            };
+
+            pieces.push(Box::new(Cylinder::new(
+                segment[0] + direction * start_trim,
+                segment[1] - direction * end_trim,
+                radius,
+                object_id,
+            )));
+        }
+
+        pieces.extend(bends.into_iter().flatten().map(|bend| bend.piece));
+
+        let piece_hierarchy = Bvh::new(
+            pieces
+                .iter()
+                .map(|piece| piece.create_bounding_box())
+                .collect(),
+        );
+
+        SweptPipe {
+            pieces,
+            piece_hierarchy,
+            object_id,
+        }
+    }
+}
+
+fn create_bend(
+    vertices: &[DVec3],
+    vertex_index: usize,
+    radius: f64,
+    bend_radius: f64,
+    object_id: u32,
+) -> Option<Bend> {
+    let vertex = vertices[vertex_index];
+    let incoming = vertex - vertices[vertex_index - 1];
+    let outgoing = vertices[vertex_index + 1] - vertex;
+
+    let bend_angle = angle(&incoming, &outgoing);
+
+    if bend_angle < MIN_BEND_ANGLE {
+        return None;
+    }
+
+    if bend_radius <= 0.0 || bend_angle > PI - MIN_BEND_ANGLE {
+        return Some(Bend {
+            trim_length: 0.0,
+            piece: Box::new(Sphere::new(vertex, radius, object_id)),
+        });
+    }
+
+    // Never trim more than half of the adjacent segments, so that neighbouring bends don't overlap
+    let half_tan = (bend_angle / 2.0).tan();
+    let max_trim_length = incoming.magnitude().min(outgoing.magnitude()) / 2.0;
+    let trim_length = (bend_radius * half_tan).min(max_trim_length);
+    let bend_radius = trim_length / half_tan;
+
+    let incoming_direction = incoming.normalize();
+    let axis = incoming_direction.cross(&outgoing).normalize();
+    let towards_bend_center = axis.cross(&incoming_direction);
+
+    let bend_start = vertex - incoming_direction * trim_length;
+
+    Some(Bend {
+        trim_length,
+        piece: Box::new(TorusSegment::from_start_direction(
+            bend_start + towards_bend_center * bend_radius,
+            axis,
+            &(-towards_bend_center),
+            bend_radius,
+            radius,
+            bend_angle,
+            object_id,
+        )),
+    })
+}
+
+impl Shape for SweptPipe {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        self.piece_hierarchy
+            .find_containing(point, |piece_index| {
+                self.pieces[piece_index].contains_point(point)
+            })
+            .is_some()
+    }
+
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        self.piece_hierarchy
+            .find_min_distance(point, |piece_index| {
+                self.pieces[piece_index].signed_distance(point)
+            })
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        self.piece_hierarchy.bounding_box()
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+
+    /// Ignores the small overlap between pieces at the joints
+    fn get_volume(&self) -> f64 {
+        self.pieces.iter().map(|piece| piece.get_volume()).sum()
+    }
+
+    fn get_distance_to_core(&self, point: &DVec3) -> f64 {
+        self.piece_hierarchy
+            .find_min_distance(point, |piece_index| {
+                self.pieces[piece_index].get_distance_to_core(point)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{vec3, DVec3};
+
+    use super::SweptPipe;
+    use crate::shapes::Shape;
+
+    fn create_l_shaped_vertices() -> Vec<DVec3> {
+        vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(10.0, 0.0, 0.0),
+            vec3(10.0, 10.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn straight_pipe_contains_points_along_run() {
+        let pipe = SweptPipe::new(
+            &[
+                vec3(0.0, 0.0, 0.0),
+                vec3(0.0, 0.0, 5.0),
+                vec3(0.0, 0.0, 10.0),
+            ],
+            0.5,
+            &[],
+            0,
+        );
+
+        assert!(pipe.contains_point(&vec3(0.4, 0.0, 2.0)));
+        assert!(pipe.contains_point(&vec3(0.0, 0.4, 9.0)));
+        assert!(!pipe.contains_point(&vec3(0.6, 0.0, 5.0)));
+        assert!(!pipe.contains_point(&vec3(0.0, 0.0, 10.1)));
+    }
+
+    #[test]
+    fn sharp_joint_is_rounded_off() {
+        let pipe = SweptPipe::new(&create_l_shaped_vertices(), 0.5, &[], 0);
+
+        assert!(pipe.contains_point(&vec3(10.3, -0.3, 0.0)));
+        assert!(!pipe.contains_point(&vec3(10.45, -0.45, 0.0)));
+    }
+
+    #[test]
+    fn bent_joint_follows_arc_and_cuts_corner() {
+        let pipe = SweptPipe::new(&create_l_shaped_vertices(), 0.5, &[2.0], 0);
+
+        // The arc has its center at (8, 2, 0) and radius 2
+        let arc_middle = vec3(8.0, 2.0, 0.0) + vec3(1.0, -1.0, 0.0).normalize() * 2.0;
+
+        assert!(pipe.contains_point(&arc_middle));
+        assert!(pipe.contains_point(&vec3(9.0, 0.0, 0.0)));
+        assert!(!pipe.contains_point(&vec3(10.0, 0.0, 0.0)));
+        assert!(pipe.contains_point(&vec3(10.0, 5.0, 0.3)));
+    }
+
+    #[test]
+    fn long_pipe_bounding_box_encloses_all_vertices() {
+        let vertices: Vec<DVec3> = (0..200)
+            .map(|i| vec3(i as f64, (i % 2) as f64 * 3.0, 0.0))
+            .collect();
+        let pipe = SweptPipe::new(&vertices, 0.25, &vec![0.5; 198], 0);
+        let bounding_box = pipe.create_bounding_box();
+
+        for vertex in vertices.iter() {
+            assert!(bounding_box.contains_point(vertex));
+        }
+        assert!(pipe.contains_point(&vec3(100.5, 1.5, 0.0)));
+        assert!(!pipe.contains_point(&vec3(100.5, 1.5, 0.3)));
+    }
+
+    #[test]
+    fn long_pipe_distances_match_closest_piece() {
+        let vertices: Vec<DVec3> = (0..200)
+            .map(|i| vec3(i as f64, (i % 2) as f64 * 3.0, 0.0))
+            .collect();
+        let pipe = SweptPipe::new(&vertices, 0.25, &vec![0.5; 198], 0);
+
+        for i in 0..100 {
+            let point = vec3(i as f64 * 2.01, (i % 7) as f64 * 0.5, (i % 3) as f64 * 0.1);
+            let closest = |distance: &dyn Fn(&dyn Shape) -> f64| {
+                pipe.pieces
+                    .iter()
+                    .map(|piece| distance(piece.as_ref()))
+                    .fold(f64::INFINITY, f64::min)
+            };
+
+            assert_eq!(
+                pipe.signed_distance(&point),
+                closest(&|piece| piece.signed_distance(&point))
+            );
+            assert_eq!(
+                pipe.get_distance_to_core(&point),
+                closest(&|piece| piece.get_distance_to_core(&point))
+            );
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use std::f64::consts::{PI, TAU};
+
+use nalgebra_glm::{distance, dot, vec3, DVec3};
+
+use crate::linalg::BoundingBox;
+use crate::shapes::shape::Shape;
+
+/// A sector of a torus, e.g. a pipe elbow. The tube center line is the arc of radius
+/// `major_radius` around `center` in the plane orthogonal to `axis`, going counter-clockwise
+/// around `axis` from `start_angle` to `end_angle`. Angle zero is the direction of the world X
+/// axis projected into that plane, or of the world Y axis if `axis` is parallel to X
+pub struct TorusSegment {
+    center: DVec3,
+    axis: DVec3,
+    angle_zero_direction: DVec3,
+    angle_ninety_direction: DVec3,
+    major_radius: f64,
+    minor_radius: f64,
+    start_angle: f64,
+    sweep_angle: f64,
+    object_id: u32,
+}
+
+impl TorusSegment {
+    pub fn new(
+        center: DVec3,
+        axis: DVec3,
+        major_radius: f64,
+        minor_radius: f64,
+        start_angle: f64,
+        end_angle: f64,
+        object_id: u32,
+    ) -> Self {
+        let axis = axis.normalize();
+        let angle_zero_direction = get_angle_zero_direction(&axis);
+
+        TorusSegment {
+            center,
+            axis,
+            angle_zero_direction,
+            angle_ninety_direction: axis.cross(&angle_zero_direction),
+            major_radius,
+            minor_radius,
+            start_angle,
+            sweep_angle: (end_angle - start_angle).clamp(0.0, TAU),
+            object_id,
+        }
+    }
+
+    /// Creates the torus segment whose arc starts at `center + start_direction * major_radius`
+    /// and sweeps `sweep_angle` counter-clockwise around `axis`
+    pub fn from_start_direction(
+        center: DVec3,
+        axis: DVec3,
+        start_direction: &DVec3,
+        major_radius: f64,
+        minor_radius: f64,
+        sweep_angle: f64,
+        object_id: u32,
+    ) -> Self {
+        let axis = axis.normalize();
+        let angle_zero_direction = get_angle_zero_direction(&axis);
+        let start_angle = dot(start_direction, &axis.cross(&angle_zero_direction))
+            .atan2(dot(start_direction, &angle_zero_direction));
+
+        TorusSegment::new(
+            center,
+            axis,
+            major_radius,
+            minor_radius,
+            start_angle,
+            start_angle + sweep_angle,
+            object_id,
+        )
+    }
+
+    fn is_angle_in_sweep(&self, angle: f64) -> bool {
+        (angle - self.start_angle).rem_euclid(TAU) <= self.sweep_angle
+    }
+
+    fn get_arc_point(&self, angle: f64) -> DVec3 {
+        self.center
+            + (self.angle_zero_direction * angle.cos() + self.angle_ninety_direction * angle.sin())
+                * self.major_radius
+    }
+}
+
+fn get_angle_zero_direction(axis: &DVec3) -> DVec3 {
+    let x_axis = vec3(1.0, 0.0, 0.0);
+    let reference = if dot(&x_axis, axis).abs() < 1.0 - 1e-6 {
+        x_axis
+    } else {
+        vec3(0.0, 1.0, 0.0)
+    };
+
+    (reference - axis * dot(&reference, axis)).normalize()
+}
+
+impl Shape for TorusSegment {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        let relative_point = point - self.center;
+        let height = dot(&relative_point, &self.axis);
+        let in_plane = relative_point - self.axis * height;
+
+        let angle = dot(&in_plane, &self.angle_ninety_direction)
+            .atan2(dot(&in_plane, &self.angle_zero_direction));
+
+        if !self.is_angle_in_sweep(angle) {
+            return false;
+        }
+
+        let dist_to_arc_in_plane = in_plane.magnitude() - self.major_radius;
+        dist_to_arc_in_plane * dist_to_arc_in_plane + height * height
+            < self.minor_radius * self.minor_radius
+    }
+
+    /// Outside the sweep, this is the distance to the nearest of the flat end caps
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        let relative_point = point - self.center;
+        let height = dot(&relative_point, &self.axis);
+        let in_plane = relative_point - self.axis * height;
+
+        let angle = dot(&in_plane, &self.angle_ninety_direction)
+            .atan2(dot(&in_plane, &self.angle_zero_direction));
+
+        if self.is_angle_in_sweep(angle) {
+            return self.get_distance_to_arc(point) - self.minor_radius;
+        }
+
+        let end_angle = self.start_angle + self.sweep_angle;
+        [self.start_angle, end_angle]
+            .into_iter()
+            .map(|cap_angle| {
+                let cap_center = self.get_arc_point(cap_angle);
+                let cap_normal = self.axis.cross(&(cap_center - self.center)).normalize();
+
+                let cap_relative_point = point - cap_center;
+                let axial = dot(&cap_relative_point, &cap_normal);
+                let radial = (cap_relative_point - cap_normal * axial).magnitude();
+                let outside_radial = (radial - self.minor_radius).max(0.0);
+
+                (axial * axial + outside_radial * outside_radial).sqrt()
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        let end_angle = self.start_angle + self.sweep_angle;
+
+        // Each coordinate of the arc is extremal at the endpoints or at the angles where its
+        // derivative vanishes, which are half a turn apart
+        let extremal_angles = (0..3).flat_map(|i| {
+            let angle = self.angle_ninety_direction[i].atan2(self.angle_zero_direction[i]);
+            [angle, angle + PI]
+        });
+
+        let mut bounding_box: BoundingBox = [self.start_angle, end_angle]
+            .into_iter()
+            .chain(extremal_angles.filter(|angle| self.is_angle_in_sweep(*angle)))
+            .map(|angle| self.get_arc_point(angle))
+            .collect();
+
+        let extent = vec3(self.minor_radius, self.minor_radius, self.minor_radius);
+        bounding_box.min -= extent;
+        bounding_box.max += extent;
+
+        bounding_box
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+
+    fn get_volume(&self) -> f64 {
+        PI * self.minor_radius * self.minor_radius * self.major_radius * self.sweep_angle
+    }
+
+    fn get_distance_to_core(&self, point: &DVec3) -> f64 {
+        self.get_distance_to_arc(point) - self.minor_radius
+    }
+}
+
+impl TorusSegment {
+    /// Distance to the center line of the tube, which ends at the ends of the arc
+    fn get_distance_to_arc(&self, point: &DVec3) -> f64 {
+        let relative_point = point - self.center;
+        let height = dot(&relative_point, &self.axis);
+        let in_plane = relative_point - self.axis * height;
+
+        let angle = dot(&in_plane, &self.angle_ninety_direction)
+            .atan2(dot(&in_plane, &self.angle_zero_direction));
+
+        if self.is_angle_in_sweep(angle) {
+            let dist_to_arc_in_plane = in_plane.magnitude() - self.major_radius;
+            (dist_to_arc_in_plane * dist_to_arc_in_plane + height * height).sqrt()
+        } else {
+            let start = self.get_arc_point(self.start_angle);
+            let end = self.get_arc_point(self.start_angle + self.sweep_angle);
+            distance(point, &start).min(distance(point, &end))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use nalgebra_glm::{vec3, DVec3};
+
+    use super::TorusSegment;
+    use crate::shapes::Shape;
+
+    fn create_quarter_elbow() -> TorusSegment {
+        TorusSegment::new(
+            vec3(0.0, 0.0, 0.0),
+            vec3(0.0, 0.0, 1.0),
+            2.0,
+            0.5,
+            0.0,
+            FRAC_PI_2,
+            0,
+        )
+    }
+
+    #[test]
+    fn quarter_elbow_contains_points_on_arc_only_within_sweep() {
+        let elbow = create_quarter_elbow();
+
+        assert!(elbow.contains_point(&vec3(2.0, 0.1, 0.0)));
+        assert!(elbow.contains_point(&vec3(2.0_f64.sqrt(), 2.0_f64.sqrt(), 0.4)));
+        assert!(elbow.contains_point(&vec3(0.1, 2.3, 0.0)));
+        assert!(!elbow.contains_point(&vec3(2.0, -0.1, 0.0)));
+        assert!(!elbow.contains_point(&vec3(-0.1, 2.0, 0.0)));
+        assert!(!elbow.contains_point(&vec3(-2.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn elbow_does_not_contain_torus_center() {
+        let elbow = create_quarter_elbow();
+
+        assert!(!elbow.contains_point(&vec3(0.0, 0.0, 0.0)));
+        assert!(!elbow.contains_point(&vec3(1.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn quarter_elbow_bounding_box_is_tight_to_arc() {
+        let elbow = create_quarter_elbow();
+        let bounding_box = elbow.create_bounding_box();
+
+        let expected_min = vec3(-0.5, -0.5, -0.5);
+        let expected_max = vec3(2.5, 2.5, 0.5);
+
+        assert!((bounding_box.min - expected_min).amax() < 1e-9);
+        assert!((bounding_box.max - expected_max).amax() < 1e-9);
+    }
+
+    #[test]
+    fn tilted_elbow_bounding_box_contains_sampled_tube_surface() {
+        let elbow = TorusSegment::new(
+            vec3(10.0, -3.0, 4.0),
+            vec3(1.0, 2.0, -0.5),
+            3.0,
+            0.4,
+            -0.3,
+            PI,
+            0,
+        );
+        let bounding_box = elbow.create_bounding_box();
+        let axis = vec3(1.0, 2.0, -0.5).normalize();
+
+        for i in 0..=32 {
+            let angle = -0.3 + (PI + 0.3) * (i as f64) / 32.0;
+            let arc_point = elbow.get_arc_point(angle);
+            let radial: DVec3 = (arc_point - vec3(10.0, -3.0, 4.0)).normalize();
+
+            for tube_point in [
+                arc_point + radial * 0.39,
+                arc_point - radial * 0.39,
+                arc_point + axis * 0.39,
+                arc_point - axis * 0.39,
+            ] {
+                assert!(elbow.contains_point(&tube_point));
+                assert!(bounding_box.contains_point(&tube_point));
+            }
+        }
+    }
+
+    #[test]
+    fn torus_segment_signed_distance_inside_sweep_and_beyond_caps() {
+        let elbow = create_quarter_elbow();
+
+        assert!((elbow.signed_distance(&vec3(0.0, 2.0, 0.0)) + 0.5).abs() < 1e-9);
+        assert!((elbow.signed_distance(&vec3(0.0, 3.0, 0.0)) - 0.5).abs() < 1e-9);
+        // Beyond the cap at angle zero, which faces negative Y
+        assert!((elbow.signed_distance(&vec3(2.0, -1.0, 0.0)) - 1.0).abs() < 1e-9);
+    }
+}
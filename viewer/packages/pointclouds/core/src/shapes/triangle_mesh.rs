@@ -0,0 +1,282 @@
+use nalgebra_glm::{distance, dot, vec3, DVec3};
+
+use crate::bvh::Bvh;
+use crate::linalg::BoundingBox;
+use crate::shapes::shape::Shape;
+
+/// Direction of the rays used for the inside test. It is deliberately not aligned with any axis,
+/// so that rays rarely graze edges of axis-aligned CAD geometry
+const RAY_DIRECTION: [f64; 3] = [0.351_123, 0.542_718, 0.762_985];
+
+/// A closed, watertight triangle mesh. A point is inside if a ray from it crosses the surface an
+/// odd number of times
+pub struct TriangleMeshShape {
+    vertices: Vec<DVec3>,
+    triangles: Vec<[usize; 3]>,
+    triangle_hierarchy: Bvh,
+    object_id: u32,
+}
+
+impl TriangleMeshShape {
+    pub fn new(vertices: Vec<DVec3>, triangles: Vec<[usize; 3]>, object_id: u32) -> Self {
+        let triangle_hierarchy = Bvh::new(
+            triangles
+                .iter()
+                .map(|triangle| triangle.iter().map(|i| vertices[*i]).collect())
+                .collect(),
+        );
+
+        TriangleMeshShape {
+            vertices,
+            triangles,
+            triangle_hierarchy,
+            object_id,
+        }
+    }
+
+    /// Möller-Trumbore intersection test, only counting hits in front of `origin`
+    fn ray_hits_triangle(&self, origin: &DVec3, direction: &DVec3, triangle_index: usize) -> bool {
+        let [a, b, c] = self.triangles[triangle_index].map(|i| self.vertices[i]);
+
+        let edge_0 = b - a;
+        let edge_1 = c - a;
+        let p = direction.cross(&edge_1);
+        let determinant = dot(&edge_0, &p);
+
+        if determinant == 0.0 {
+            return false;
+        }
+
+        let inv_determinant = 1.0 / determinant;
+        let to_origin = origin - a;
+
+        let u = dot(&to_origin, &p) * inv_determinant;
+        if !(0.0..1.0).contains(&u) {
+            return false;
+        }
+
+        let q = to_origin.cross(&edge_0);
+        let v = dot(direction, &q) * inv_determinant;
+        if v < 0.0 || u + v >= 1.0 {
+            return false;
+        }
+
+        dot(&edge_1, &q) * inv_determinant > 0.0
+    }
+
+    fn distance_to_triangle(&self, point: &DVec3, triangle_index: usize) -> f64 {
+        let [a, b, c] = self.triangles[triangle_index].map(|i| self.vertices[i]);
+        distance(point, &closest_point_on_triangle(point, &a, &b, &c))
+    }
+}
+
+/// Finds the closest point by checking which vertex, edge or face region `point` projects into,
+/// following Ericson, Real-Time Collision Detection, section 5.1.5
+fn closest_point_on_triangle(point: &DVec3, a: &DVec3, b: &DVec3, c: &DVec3) -> DVec3 {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = point - a;
+
+    let d1 = dot(&ab, &ap);
+    let d2 = dot(&ac, &ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return *a;
+    }
+
+    let bp = point - b;
+    let d3 = dot(&ab, &bp);
+    let d4 = dot(&ac, &bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return *b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = point - c;
+    let d5 = dot(&ab, &cp);
+    let d6 = dot(&ac, &cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return *c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denominator = 1.0 / (va + vb + vc);
+    a + ab * (vb * denominator) + ac * (vc * denominator)
+}
+
+impl Shape for TriangleMeshShape {
+    fn contains_point(&self, point: &DVec3) -> bool {
+        if !self.triangle_hierarchy.bounding_box().contains_point(point) {
+            return false;
+        }
+
+        let direction = vec3(RAY_DIRECTION[0], RAY_DIRECTION[1], RAY_DIRECTION[2]);
+        let mut num_crossings = 0;
+
+        self.triangle_hierarchy
+            .for_each_hit_by_ray(point, &direction, |triangle_index| {
+                if self.ray_hits_triangle(point, &direction, triangle_index) {
+                    num_crossings += 1;
+                }
+            });
+
+        num_crossings % 2 == 1
+    }
+
+    fn signed_distance(&self, point: &DVec3) -> f64 {
+        let unsigned_distance = self
+            .triangle_hierarchy
+            .find_min_distance(point, |triangle_index| {
+                self.distance_to_triangle(point, triangle_index)
+            });
+
+        if self.contains_point(point) {
+            -unsigned_distance
+        } else {
+            unsigned_distance
+        }
+    }
+
+    fn create_bounding_box(&self) -> BoundingBox {
+        self.triangle_hierarchy.bounding_box()
+    }
+
+    fn get_object_id(&self) -> u32 {
+        self.object_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{vec3, DVec3};
+
+    use super::TriangleMeshShape;
+    use crate::shapes::Shape;
+
+    fn create_cube_mesh(min: DVec3, max: DVec3) -> TriangleMeshShape {
+        let vertices = (0..8)
+            .map(|i| {
+                vec3(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z },
+                )
+            })
+            .collect();
+
+        let triangles = vec![
+            [0, 2, 1],
+            [1, 2, 3],
+            [4, 5, 6],
+            [5, 7, 6],
+            [0, 1, 4],
+            [1, 5, 4],
+            [2, 6, 3],
+            [3, 6, 7],
+            [0, 4, 2],
+            [2, 4, 6],
+            [1, 3, 5],
+            [3, 7, 5],
+        ];
+
+        TriangleMeshShape::new(vertices, triangles, 0)
+    }
+
+    /// An L-shaped prism, which is not convex
+    fn create_l_mesh() -> TriangleMeshShape {
+        let outline = [
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ];
+
+        let mut vertices: Vec<DVec3> = outline.iter().map(|(x, y)| vec3(*x, *y, 0.0)).collect();
+        vertices.extend(outline.iter().map(|(x, y)| vec3(*x, *y, 1.0)));
+
+        let bottom = [[0, 3, 1], [1, 3, 2], [0, 5, 3], [3, 5, 4]];
+        let mut triangles = bottom.to_vec();
+        triangles.extend(bottom.iter().map(|[a, b, c]| [c + 6, b + 6, a + 6]));
+
+        for i in 0..6 {
+            let next = (i + 1) % 6;
+            triangles.push([i, next, next + 6]);
+            triangles.push([i, next + 6, i + 6]);
+        }
+
+        TriangleMeshShape::new(vertices, triangles, 0)
+    }
+
+    #[test]
+    fn cube_mesh_contains_inner_points_only() {
+        let cube = create_cube_mesh(vec3(-1.0, -1.0, -1.0), vec3(1.0, 1.0, 1.0));
+
+        assert!(cube.contains_point(&vec3(0.0, 0.0, 0.0)));
+        assert!(cube.contains_point(&vec3(0.9, -0.9, 0.5)));
+        assert!(!cube.contains_point(&vec3(1.1, 0.0, 0.0)));
+        assert!(!cube.contains_point(&vec3(-3.0, -3.0, -3.0)));
+    }
+
+    #[test]
+    fn concave_mesh_does_not_contain_notch() {
+        let l_mesh = create_l_mesh();
+
+        assert!(l_mesh.contains_point(&vec3(0.5, 0.5, 0.5)));
+        assert!(l_mesh.contains_point(&vec3(1.5, 0.5, 0.5)));
+        assert!(l_mesh.contains_point(&vec3(0.5, 1.5, 0.5)));
+        assert!(!l_mesh.contains_point(&vec3(1.5, 1.5, 0.5)));
+    }
+
+    #[test]
+    fn mesh_bounding_box_encloses_vertices() {
+        let l_mesh = create_l_mesh();
+        let bounding_box = l_mesh.create_bounding_box();
+
+        assert_eq!(bounding_box.min, vec3(0.0, 0.0, 0.0));
+        assert_eq!(bounding_box.max, vec3(2.0, 2.0, 1.0));
+    }
+
+    #[test]
+    fn grid_of_cubes_contains_points_in_each_cube() {
+        let cubes: Vec<_> = (0..10)
+            .flat_map(|x| (0..10).map(move |y| (x as f64 * 2.0, y as f64 * 2.0)))
+            .map(|(x, y)| create_cube_mesh(vec3(x, y, 0.0), vec3(x + 1.0, y + 1.0, 1.0)))
+            .collect();
+
+        let mut vertices = vec![];
+        let mut triangles = vec![];
+        for cube in cubes {
+            let offset = vertices.len();
+            vertices.extend(cube.vertices);
+            triangles.extend(cube.triangles.iter().map(|t| t.map(|i| i + offset)));
+        }
+
+        let grid = TriangleMeshShape::new(vertices, triangles, 0);
+
+        assert!(grid.contains_point(&vec3(6.5, 10.5, 0.5)));
+        assert!(!grid.contains_point(&vec3(7.5, 10.5, 0.5)));
+    }
+
+    #[test]
+    fn concave_mesh_signed_distance_to_nearest_face() {
+        let l_mesh = create_l_mesh();
+
+        assert!((l_mesh.signed_distance(&vec3(0.5, 0.5, 0.25)) + 0.25).abs() < 1e-9);
+        assert!((l_mesh.signed_distance(&vec3(1.5, 1.75, 0.5)) - 0.5).abs() < 1e-9);
+        assert!((l_mesh.signed_distance(&vec3(3.0, 0.5, 0.5)) - 1.0).abs() < 1e-9);
+    }
+}
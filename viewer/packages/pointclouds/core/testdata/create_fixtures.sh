@@ -0,0 +1,86 @@
+#!/bin/sh
+# Writes the LAZ and COPC fixtures of the tests with PDAL, which compresses them with LASzip, and
+# dumps their points to CSV files with PDAL's text writer. The tests decompress the fixtures and
+# compare the points against the dumps. Needs PDAL 2.6 or later on the path
+set -eu
+cd "$(dirname "$0")"
+
+# Points with every field the tests compare set, with values that vary from point to point
+points() {
+    cat <<EOF
+    {
+        "type": "readers.faux",
+        "mode": "uniform",
+        "count": 1500,
+        "bounds": "([0, 100], [0, 100], [0, 20])"
+    },
+    {
+        "type": "filters.ferry",
+        "dimensions": "=>Intensity, =>Classification, =>PointSourceId, =>GpsTime, =>Red, =>Green, =>Blue, =>Infrared, =>Extra"
+    },
+    {
+        "type": "filters.assign",
+        "value": [
+            "Intensity = X * 600",
+            "NumberOfReturns = 3",
+            "ReturnNumber = 1",
+            "ReturnNumber = 2 WHERE Z > 8",
+            "ReturnNumber = 3 WHERE Z > 15",
+            "Classification = 2",
+            "Classification = 6 WHERE Z > 10",
+            "PointSourceId = 7",
+            "GpsTime = 300000 + X * 10 + Y / 10",
+            "Red = X * 600",
+            "Green = Y * 600",
+            "Blue = Z * 3000",
+            "Infrared = 65535 - X * 600",
+            "Extra = Y * 1000"
+        ]
+    },
+EOF
+}
+
+# Usage: write_laz NAME MINOR_VERSION FORMAT EXTRA_DIMS
+write_laz() {
+    pdal pipeline --stdin <<EOF
+[
+$(points)
+    {
+        "type": "writers.las",
+        "filename": "$1.laz",
+        "compression": "laszip",
+        "minor_version": $2,
+        "dataformat_id": $3,
+        "extra_dims": "$4",
+        "scale_x": 0.01, "scale_y": 0.01, "scale_z": 0.01,
+        "offset_x": 0, "offset_y": 0, "offset_z": 0
+    }
+]
+EOF
+    pdal translate "$1.laz" "$1.csv" --writers.text.precision=8
+}
+
+# Formats 0 to 5 are compressed point by point, 6 to 10 in layers. Waveform formats need LAS 1.3
+for format in 0 1 2 3; do
+    write_laz "format-$format" 2 "$format" ""
+done
+for format in 4 5; do
+    write_laz "format-$format" 3 "$format" ""
+done
+for format in 6 7 8 9 10; do
+    write_laz "format-$format" 4 "$format" ""
+done
+write_laz format-7-extra-bytes 4 7 "Extra=uint32"
+
+pdal pipeline --stdin <<EOF
+[
+$(points)
+    {
+        "type": "writers.copc",
+        "filename": "points.copc.laz",
+        "scale_x": 0.01, "scale_y": 0.01, "scale_z": 0.01,
+        "offset_x": 0, "offset_y": 0, "offset_z": 0
+    }
+]
+EOF
+pdal translate points.copc.laz points.copc.csv --writers.text.precision=8
@@ -86,7 +86,7 @@ export class PointCloudEptGeometry implements IPointCloudTreeGeometry {
   }
 
   constructor(url: string, info: EptJson, dataLoader: ModelDataProvider, stylableObjects: StylableObject[]) {
-    if (info.dataType !== 'binary') {
+    if (info.dataType !== 'binary' && info.dataType !== 'laszip') {
       throw new Error('Could not read data type: ' + info.dataType);
     }
 
@@ -110,7 +110,7 @@ export class PointCloudEptGeometry implements IPointCloudTreeGeometry {
 
     this._spacing = (this._boundingBox.max.x - this._boundingBox.min.x) / this._span;
 
-    this._loader = new EptBinaryLoader(dataLoader, stylableObjects);
+    this._loader = new EptBinaryLoader(dataLoader, stylableObjects, info.dataType);
   }
 
   dispose(): void {}
@@ -10,28 +10,29 @@ import { WorkerPool } from '../utils/WorkerPool';
 import { ILoader } from './ILoader';
 import { ModelDataProvider, SerializableStylableObject, StylableObject } from '@reveal/data-providers';
 import { PointCloudEptGeometryNode } from '../geometry/PointCloudEptGeometryNode';
+import { EptJson } from './EptJson';
 import * as EptDecoderWorker from '../workers/eptBinaryDecoder.worker';
 
 import { ParsedEptData, EptInputData } from '../workers/types';
 
-import { decomposeStylableObjects } from '../../decomposeStylableObjects';
-
 import { fromThreeVector3, setupTransferableMethodsOnMain } from '@reveal/utilities';
 import { MetricsLogger } from '@reveal/metrics';
 
 export class EptBinaryLoader implements ILoader {
   private readonly _dataLoader: ModelDataProvider;
   private readonly _stylableObjectsWithBox: [SerializableStylableObject, THREE.Box3][];
+  private readonly _dataType: EptJson['dataType'];
 
   static readonly WORKER_POOL = new WorkerPool(8, EptDecoderWorker as unknown as new () => Worker);
 
   extension(): string {
-    return '.bin';
+    return this._dataType === 'laszip' ? '.laz' : '.bin';
   }
 
-  constructor(dataLoader: ModelDataProvider, stylableObjects: StylableObject[]) {
+  constructor(dataLoader: ModelDataProvider, stylableObjects: StylableObject[], dataType: EptJson['dataType']) {
     this._dataLoader = dataLoader;
-    this._stylableObjectsWithBox = decomposeStylableObjects(stylableObjects).map(obj => {
+    this._dataType = dataType;
+    this._stylableObjectsWithBox = stylableObjects.map(obj => {
       const serializableShape = obj.shape.getSerializableShape();
 
       const boundingBox = obj.shape.createBoundingBox();
@@ -79,6 +80,7 @@ export class EptBinaryLoader implements ILoader {
     const eptDecoderWorker = autoTerminatingWorker.worker as unknown as typeof EptDecoderWorker;
     const eptData: EptInputData = {
       buffer: data,
+      dataType: this._dataType,
       schema: node.ept.schema,
       scale: node.ept.eptScale.toArray(),
       offset: node.ept.eptOffset.toArray(),
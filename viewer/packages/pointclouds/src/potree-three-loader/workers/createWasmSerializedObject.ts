@@ -0,0 +1,52 @@
+/*!
+ * Copyright 2022 Cognite AS
+ */
+
+import { SerializableStylableObject } from '@reveal/data-providers';
+import {
+  assertNever,
+  ISerializableShape,
+  SerializableCylinder,
+  SerializableBox,
+  SerializableCompositeShape,
+  ShapeType
+} from '@reveal/utilities';
+
+import { WasmSerializedPointCloudObject, WasmSerializedShape } from '../../../wasm';
+
+function createWasmSerializedShape(shape: ISerializableShape): WasmSerializedShape {
+  switch (shape.shapeType) {
+    case ShapeType.Cylinder: {
+      const cylinder = shape as SerializableCylinder;
+      return {
+        cylinder: {
+          center_a: cylinder.centerA,
+          center_b: cylinder.centerB,
+          radius: cylinder.radius
+        }
+      };
+    }
+    case ShapeType.Box: {
+      const box = shape as SerializableBox;
+      return {
+        oriented_box: {
+          inv_instance_matrix: box.invMatrix.data
+        }
+      };
+    }
+    case ShapeType.Composite: {
+      const composite = shape as SerializableCompositeShape;
+      return {
+        composite: {
+          inner_shapes: composite.innerShapes.map(innerShape => createWasmSerializedShape(innerShape))
+        }
+      };
+    }
+    default:
+      assertNever(shape.shapeType);
+  }
+}
+
+export function createWasmSerializedObject(obj: SerializableStylableObject): WasmSerializedPointCloudObject {
+  return { object_id: obj.objectId, ...createWasmSerializedShape(obj.shape) };
+}
@@ -27,6 +27,7 @@ export type AttributeSchema = {
 
 export type EptInputData = {
   buffer: ArrayBuffer;
+  dataType: 'binary' | 'laszip';
   schema: AttributeSchema[];
   scale: Vec3;
   offset: Vec3;
@@ -2,11 +2,20 @@
  * Copyright 2022 Cognite AS
  */
 
-import init, { assign_points } from './pkg/pointclouds_wasm';
+import init, {
+  assign_points,
+  assign_points_multi_label,
+  assign_points_with_distances,
+  decode_ept_tile,
+  export_las,
+  PointOctreeHandle
+} from './pkg/pointclouds_wasm';
 import wasm from './pkg/pointclouds_wasm_bg.wasm';
 
 import { AABB, Vec3 } from '@reveal/utilities';
 
+import * as THREE from 'three';
+
 function getWasmInitPromise(): Promise<void> {
   return typeof init === 'function' ? (init as (buffer: any) => Promise<any>)(wasm).then(() => {}) : Promise.resolve();
 }
@@ -23,20 +32,469 @@ export type WasmSerializedOrientedBox = {
   inv_instance_matrix: number[];
 };
 
-export type WasmSerializedPointCloudObject = {
-  object_id: number;
+export type WasmSerializedEllipsoid = {
+  inv_instance_matrix: number[];
+};
+
+export type WasmSerializedSphere = {
+  center: Vec3;
+  radius: number;
+};
+
+export type WasmSerializedCapsule = {
+  center_a: Vec3;
+  center_b: Vec3;
+  radius: number;
+};
+
+export type WasmSerializedCone = {
+  center_a: Vec3;
+  center_b: Vec3;
+  radius_a: number;
+  radius_b: number;
+};
+
+// Angles are in radians, counter-clockwise around `axis`. Angle zero points along
+// the world X axis projected into the torus plane (world Y if `axis` is parallel to X)
+export type WasmSerializedTorusSegment = {
+  center: Vec3;
+  axis: Vec3;
+  major_radius: number;
+  minor_radius: number;
+  start_angle: number;
+  end_angle: number;
+};
+
+// `bend_radii` has one entry per interior vertex
+export type WasmSerializedSweptPipe = {
+  vertices: Vec3[];
+  radius: number;
+  bend_radii?: number[] | undefined;
+};
+
+// Exactly one of `planes` (with outward normals) and `points` (to take the convex hull of) should be given
+export type WasmSerializedConvexPolyhedron = {
+  planes?: WasmSerializedHalfSpace[] | undefined;
+  points?: Vec3[] | undefined;
+};
+
+// Refers to the buffers at `mesh_index` in the `input_meshes` list passed to `assignPoints`
+export type WasmSerializedTriangleMesh = {
+  mesh_index: number;
+};
+
+export type WasmTriangleMeshBuffers = {
+  vertices: Float32Array;
+  indices: Uint32Array;
+};
+
+// `rings[0]` is the outline and any further rings are holes, in coordinates of the frame spanned
+// by `x_axis` and `y_axis`. The polygon is extruded along their cross product
+export type WasmSerializedPrism = {
+  origin: Vec3;
+  x_axis: Vec3;
+  y_axis: Vec3;
+  rings: [number, number][][];
+  min_height: number;
+  max_height: number;
+};
+
+export type WasmSerializedComposite = {
+  inner_shapes: WasmSerializedShape[];
+};
+
+export type WasmSerializedHalfSpace = {
+  normal: Vec3;
+  offset: number;
+};
+
+export type WasmSerializedCsg = {
+  operator: 'union' | 'intersection' | 'difference';
+  left: WasmSerializedShape;
+  right: WasmSerializedShape;
+};
+
+export type WasmSerializedShape = {
   cylinder?: WasmSerializedCylinder | undefined;
   oriented_box?: WasmSerializedOrientedBox | undefined;
+  ellipsoid?: WasmSerializedEllipsoid | undefined;
+  sphere?: WasmSerializedSphere | undefined;
+  capsule?: WasmSerializedCapsule | undefined;
+  cone?: WasmSerializedCone | undefined;
+  torus_segment?: WasmSerializedTorusSegment | undefined;
+  swept_pipe?: WasmSerializedSweptPipe | undefined;
+  convex_polyhedron?: WasmSerializedConvexPolyhedron | undefined;
+  triangle_mesh?: WasmSerializedTriangleMesh | undefined;
+  prism?: WasmSerializedPrism | undefined;
+  composite?: WasmSerializedComposite | undefined;
+  half_space?: WasmSerializedHalfSpace | undefined;
+  csg?: WasmSerializedCsg | undefined;
+};
+
+// Shapes are grown by turning each radius or half extent l into l * scale_factor + margin, with the
+// change clamped to [min_increase, max_increase]. Defaults are scale_factor 1.15 and max_increase 0.06,
+// except for boxes and ellipsoids, which are not capped by default. Half-spaces only move by the clamped margin
+export type WasmInflation = {
+  scale_factor?: number;
+  margin?: number;
+  min_increase?: number;
+  max_increase?: number;
+};
+
+export type WasmSerializedPointCloudObject = WasmSerializedShape & {
+  object_id: number;
+  // Overrides the inflation settings of the call for this object
+  inflation?: WasmInflation;
+  // Only used with the 'priority' overlap policy, higher wins. Defaults to 0
+  priority?: number;
 };
 
-export async function assignPoints(
+// Decides which object a point is assigned to when it is inside several shapes
+export type WasmOverlapPolicy = 'first_wins' | 'last_wins' | 'priority' | 'smallest_volume' | 'nearest_center';
+
+// Element type of the returned object id arrays. Object ids that do not fit cause an error
+export type WasmObjectIdWidth = 'u16' | 'u32';
+
+export type WasmObjectIdArray<W extends WasmObjectIdWidth> = W extends 'u32' ? Uint32Array : Uint16Array;
+
+// The object ids of point i are object_ids[offsets[i]] up to, but not including, object_ids[offsets[i + 1]]
+export type WasmPointMemberships<W extends WasmObjectIdWidth = 'u16'> = {
+  offsets: Uint32Array;
+  object_ids: WasmObjectIdArray<W>;
+};
+
+// Signed distances to the assigned shape before inflation, negative inside it, and NaN for points without an object
+export type WasmPointAssignmentWithDistances<W extends WasmObjectIdWidth = 'u16'> = {
+  object_ids: WasmObjectIdArray<W>;
+  distances: Float32Array;
+};
+
+// Indices of the points found near a position with their distances to it, nearest first
+export type WasmPointNeighbours = {
+  indices: Uint32Array;
+  distances: Float32Array;
+};
+
+// Points are picked within radius + t * radius_per_distance of the ray, where t is the distance along it
+export type WasmPickRay = {
+  origin: Vec3;
+  direction: Vec3;
+  radius?: number;
+  radius_per_distance?: number;
+};
+
+// The hit closest to the ray origin. object_id is what assigning the same objects would give the point
+export type WasmRayPickHit = {
+  index: number;
+  object_id: number;
+  position: Float64Array;
+  distance_along_ray: number;
+  distance_to_ray: number;
+};
+
+// Radius per distance that makes a pick ray cover a circle of the given radius in pixels, for a
+// perspective camera with the given vertical field of view
+export function getScreenSpacePickRadiusPerDistance(
+  pixel_radius: number,
+  viewport_height: number,
+  vertical_fov_degrees: number
+): number {
+  const halfFovRadians = (vertical_fov_degrees * Math.PI) / 360;
+  return (2 * Math.tan(halfFovRadians) * pixel_radius) / viewport_height;
+}
+
+// Keeps the points p with dot(normal, p) <= offset, like a half-space shape
+export type WasmClipPlane = {
+  normal: Vec3;
+  offset: number;
+};
+
+// Three.js planes keep the points on the side their normal points to, e.g. the planes of a THREE.Frustum
+export function toWasmClipPlanes(planes: THREE.Plane[]): WasmClipPlane[] {
+  return planes.map(plane => ({
+    normal: [-plane.normal.x, -plane.normal.y, -plane.normal.z],
+    offset: plane.constant
+  }));
+}
+
+export async function assignPoints<W extends WasmObjectIdWidth = 'u16'>(
+  input_shapes: Array<WasmSerializedPointCloudObject>,
+  input_points: Float32Array,
+  input_bounding_box: AABB,
+  input_point_offset: Vec3,
+  input_meshes: Array<WasmTriangleMeshBuffers> = [],
+  input_overlap_policy: WasmOverlapPolicy = 'last_wins',
+  input_object_id_width?: W,
+  input_inflation?: WasmInflation
+): Promise<WasmObjectIdArray<W>> {
+  const wasm_init = getWasmInitPromise();
+  return wasm_init.then(
+    () =>
+      assign_points(
+        input_shapes,
+        input_meshes,
+        input_points,
+        input_bounding_box,
+        new Float64Array(input_point_offset),
+        input_overlap_policy,
+        input_object_id_width,
+        input_inflation
+      ) as WasmObjectIdArray<W>
+  );
+}
+
+export async function assignPointsWithDistances<W extends WasmObjectIdWidth = 'u16'>(
   input_shapes: Array<WasmSerializedPointCloudObject>,
   input_points: Float32Array,
   input_bounding_box: AABB,
-  input_point_offset: Vec3
-): Promise<Uint16Array> {
+  input_point_offset: Vec3,
+  input_meshes: Array<WasmTriangleMeshBuffers> = [],
+  input_overlap_policy: WasmOverlapPolicy = 'last_wins',
+  input_object_id_width?: W,
+  input_inflation?: WasmInflation
+): Promise<WasmPointAssignmentWithDistances<W>> {
+  const wasm_init = getWasmInitPromise();
+  return wasm_init.then(
+    () =>
+      assign_points_with_distances(
+        input_shapes,
+        input_meshes,
+        input_points,
+        input_bounding_box,
+        new Float64Array(input_point_offset),
+        input_overlap_policy,
+        input_object_id_width,
+        input_inflation
+      ) as WasmPointAssignmentWithDistances<W>
+  );
+}
+
+export async function assignPointsMultiLabel<W extends WasmObjectIdWidth = 'u16'>(
+  input_shapes: Array<WasmSerializedPointCloudObject>,
+  input_points: Float32Array,
+  input_bounding_box: AABB,
+  input_point_offset: Vec3,
+  input_meshes: Array<WasmTriangleMeshBuffers> = [],
+  input_object_id_width?: W,
+  input_inflation?: WasmInflation
+): Promise<WasmPointMemberships<W>> {
+  const wasm_init = getWasmInitPromise();
+  return wasm_init.then(
+    () =>
+      assign_points_multi_label(
+        input_shapes,
+        input_meshes,
+        input_points,
+        input_bounding_box,
+        new Float64Array(input_point_offset),
+        input_object_id_width,
+        input_inflation
+      ) as WasmPointMemberships<W>
+  );
+}
+
+// How to decode the points of an EPT tile. Decoded positions are relative to mins. LAZ tiles use the
+// scale and offset of their LAS header
+export type WasmEptTileLayout = {
+  data_type?: 'binary' | 'laszip';
+  schema: { name: string; size: number; type: 'signed' | 'unsigned' | 'float' }[];
+  scale: Vec3;
+  offset: Vec3;
+  mins: Vec3;
+};
+
+// Attribute buffers of a decoded EPT tile. Attributes that are not in the schema are left out
+export type WasmDecodedEptTile<W extends WasmObjectIdWidth = 'u16'> = {
+  num_points: number;
+  tight_bounding_box: AABB;
+  mean: Vec3;
+  position: Float32Array;
+  color?: Uint8Array;
+  intensity?: Float32Array;
+  classification?: Uint8Array;
+  return_number?: Uint8Array;
+  number_of_returns?: Uint8Array;
+  point_source_id?: Uint16Array;
+  indices: Uint32Array;
+  object_ids: WasmObjectIdArray<W>;
+};
+
+export async function decodeEptTile<W extends WasmObjectIdWidth = 'u16'>(
+  input_buffer: ArrayBuffer,
+  input_layout: WasmEptTileLayout,
+  input_shapes: Array<WasmSerializedPointCloudObject>,
+  input_bounding_box: AABB,
+  input_point_offset: Vec3,
+  input_meshes: Array<WasmTriangleMeshBuffers> = [],
+  input_overlap_policy: WasmOverlapPolicy = 'last_wins',
+  input_object_id_width?: W,
+  input_inflation?: WasmInflation
+): Promise<WasmDecodedEptTile<W>> {
+  const wasm_init = getWasmInitPromise();
+  return wasm_init.then(
+    () =>
+      decode_ept_tile(
+        new Uint8Array(input_buffer),
+        input_layout,
+        input_shapes,
+        input_meshes,
+        input_bounding_box,
+        new Float64Array(input_point_offset),
+        input_overlap_policy,
+        input_object_id_width,
+        input_inflation
+      ) as WasmDecodedEptTile<W>
+  );
+}
+
+// Points to write to a LAS file: those assigned to any of `objectIds`, or those set in `mask`
+export type WasmLasExportSelection = { objectIds: number[] } | { mask: Uint8Array };
+
+// Optional attribute buffers, with one value per point. A decoded EPT tile can be passed as is
+export type WasmLasExportAttributes = Pick<
+  WasmDecodedEptTile,
+  'color' | 'intensity' | 'classification' | 'return_number' | 'number_of_returns' | 'point_source_id'
+>;
+
+// Writes a subset of the points to a LAS 1.4 file, with the object ids from assignment in an
+// `object_id` extra bytes dimension. Coordinates are stored in millimeters relative to the point offset
+export async function exportLas(
+  input_points: Float32Array,
+  input_point_offset: Vec3,
+  input_object_ids: Uint16Array | Uint32Array,
+  input_selection: WasmLasExportSelection,
+  input_attributes: WasmLasExportAttributes = {}
+): Promise<Uint8Array> {
   const wasm_init = getWasmInitPromise();
   return wasm_init.then(() =>
-    assign_points(input_shapes, input_points, input_bounding_box, new Float64Array(input_point_offset))
+    export_las(
+      input_points,
+      new Float64Array(input_point_offset),
+      input_object_ids,
+      'objectIds' in input_selection ? new Uint32Array(input_selection.objectIds) : undefined,
+      'mask' in input_selection ? input_selection.mask : undefined,
+      input_attributes
+    )
   );
 }
+
+// Keeps the octree over a set of points alive in wasm memory, so that the points can be assigned to new
+// objects or queried without rebuilding it. Must be disposed when no longer needed
+export class WasmPointOctree {
+  private readonly _handle: PointOctreeHandle;
+
+  private constructor(handle: PointOctreeHandle) {
+    this._handle = handle;
+  }
+
+  static async create(
+    input_points: Float32Array,
+    input_bounding_box: AABB,
+    input_point_offset: Vec3
+  ): Promise<WasmPointOctree> {
+    await getWasmInitPromise();
+    return new WasmPointOctree(
+      new PointOctreeHandle(input_points, input_bounding_box, new Float64Array(input_point_offset))
+    );
+  }
+
+  get numPoints(): number {
+    return this._handle.num_points;
+  }
+
+  assign<W extends WasmObjectIdWidth = 'u16'>(
+    input_shapes: Array<WasmSerializedPointCloudObject>,
+    input_meshes: Array<WasmTriangleMeshBuffers> = [],
+    input_overlap_policy: WasmOverlapPolicy = 'last_wins',
+    input_object_id_width?: W,
+    input_inflation?: WasmInflation
+  ): WasmObjectIdArray<W> {
+    return this._handle.assign(
+      input_shapes,
+      input_meshes,
+      input_overlap_policy,
+      input_object_id_width,
+      input_inflation
+    ) as WasmObjectIdArray<W>;
+  }
+
+  assignWithDistances<W extends WasmObjectIdWidth = 'u16'>(
+    input_shapes: Array<WasmSerializedPointCloudObject>,
+    input_meshes: Array<WasmTriangleMeshBuffers> = [],
+    input_overlap_policy: WasmOverlapPolicy = 'last_wins',
+    input_object_id_width?: W,
+    input_inflation?: WasmInflation
+  ): WasmPointAssignmentWithDistances<W> {
+    return this._handle.assign_with_distances(
+      input_shapes,
+      input_meshes,
+      input_overlap_policy,
+      input_object_id_width,
+      input_inflation
+    ) as WasmPointAssignmentWithDistances<W>;
+  }
+
+  assignMultiLabel<W extends WasmObjectIdWidth = 'u16'>(
+    input_shapes: Array<WasmSerializedPointCloudObject>,
+    input_meshes: Array<WasmTriangleMeshBuffers> = [],
+    input_object_id_width?: W,
+    input_inflation?: WasmInflation
+  ): WasmPointMemberships<W> {
+    return this._handle.assign_multi_label(
+      input_shapes,
+      input_meshes,
+      input_object_id_width,
+      input_inflation
+    ) as WasmPointMemberships<W>;
+  }
+
+  // Indices of the points inside the box, in ascending order
+  queryBox(input_bounding_box: AABB): Uint32Array {
+    return this._handle.query_box(input_bounding_box);
+  }
+
+  // Indices of the points inside the shape, in ascending order
+  queryShape(
+    input_shape: WasmSerializedShape,
+    input_meshes: Array<WasmTriangleMeshBuffers> = [],
+    input_inflation?: WasmInflation
+  ): Uint32Array {
+    return this._handle.query_shape(input_shape, input_meshes, input_inflation);
+  }
+
+  // Points at most radius away from the position, which is given in the same coordinates as the shapes
+  queryRadius(position: Vec3, radius: number): WasmPointNeighbours {
+    return this._handle.query_radius(new Float64Array(position), radius) as WasmPointNeighbours;
+  }
+
+  // The k points nearest to the position, or all points if there are fewer
+  queryNearest(position: Vec3, k: number): WasmPointNeighbours {
+    return this._handle.query_nearest(new Float64Array(position), k) as WasmPointNeighbours;
+  }
+
+  // Deterministic CPU alternative to GPU picking. Returns null if no point is within the ray's radius
+  pick(
+    input_ray: WasmPickRay,
+    input_shapes: Array<WasmSerializedPointCloudObject> = [],
+    input_meshes: Array<WasmTriangleMeshBuffers> = [],
+    input_overlap_policy: WasmOverlapPolicy = 'last_wins',
+    input_inflation?: WasmInflation
+  ): WasmRayPickHit | null {
+    return this._handle.pick(
+      input_ray,
+      input_shapes,
+      input_meshes,
+      input_overlap_policy,
+      input_inflation
+    ) as WasmRayPickHit | null;
+  }
+
+  // One byte per point, 1 for points inside all the planes and 0 for the rest
+  computeVisibility(input_planes: WasmClipPlane[]): Uint8Array {
+    return this._handle.compute_visibility(input_planes);
+  }
+
+  dispose(): void {
+    this._handle.dispose();
+  }
+}
@@ -0,0 +1,25 @@
+use wasm_bindgen::JsValue;
+
+use pointclouds_core::object_id_width::ObjectIdWidth;
+
+/// Object ids must have been checked against `object_id_width` when parsing the objects
+pub fn create_object_id_array(object_id_width: ObjectIdWidth, object_ids: &[u32]) -> JsValue {
+    match object_id_width {
+        ObjectIdWidth::U16 => {
+            let narrowed: Vec<u16> = object_ids.iter().map(|id| *id as u16).collect();
+            js_sys::Uint16Array::from(&narrowed[..]).into()
+        }
+        ObjectIdWidth::U32 => js_sys::Uint32Array::from(object_ids).into(),
+    }
+}
+
+pub fn create_result_object(fields: &[(&str, JsValue)]) -> Result<js_sys::Object, String> {
+    let result = js_sys::Object::new();
+
+    for (name, value) in fields {
+        js_sys::Reflect::set(&result, &JsValue::from_str(name), value)
+            .map_err(|_| format!("Failed to set '{}' on result object", name))?;
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,115 @@
+use nalgebra_glm::DVec3;
+use wasm_bindgen::JsValue;
+
+use pointclouds_core::assignment::{self, ObjectSet};
+use pointclouds_core::ept;
+use pointclouds_core::point_octree::PointOctree;
+
+use crate::create_outputs::{create_object_id_array, create_result_object};
+use crate::parse_inputs;
+
+fn create_vec3_array(vec: &DVec3) -> JsValue {
+    js_sys::Array::of3(&vec.x.into(), &vec.y.into(), &vec.z.into()).into()
+}
+
+/// See `decode_ept_tile` in `lib.rs`
+#[allow(clippy::too_many_arguments)]
+pub fn decode_ept_tile(
+    input_buffer: &[u8],
+    input_layout: JsValue,
+    input_objects: Vec<JsValue>,
+    input_meshes: Vec<JsValue>,
+    input_bounding_box: js_sys::Object,
+    input_point_offset: Vec<f64>,
+    input_overlap_policy: JsValue,
+    input_object_id_width: JsValue,
+    input_inflation: JsValue,
+) -> Result<js_sys::Object, String> {
+    let layout = parse_inputs::parse_ept_tile_layout(input_layout)?;
+    let point_offset = parse_inputs::parse_position(input_point_offset)?;
+    let bounding_box = parse_inputs::parse_bounding_box(input_bounding_box)?;
+    let object_id_width = parse_inputs::parse_object_id_width(input_object_id_width)?;
+    let inflation = parse_inputs::parse_inflation(input_inflation)?;
+    let meshes = parse_inputs::parse_meshes(input_meshes)?;
+    let objects =
+        parse_inputs::try_parse_objects(input_objects, &meshes, object_id_width, &inflation)?;
+    let overlap_policy = parse_inputs::parse_overlap_policy(input_overlap_policy)?;
+
+    let mut decoded = ept::decode_ept_tile(input_buffer, &layout, &point_offset)?;
+
+    let octree = PointOctree::new(bounding_box, std::mem::take(&mut decoded.points));
+    let mut object_ids = vec![0; decoded.num_points];
+    assignment::assign_object_ids(
+        &octree,
+        &ObjectSet::new(objects),
+        overlap_policy,
+        &mut object_ids,
+    );
+
+    let indices: Vec<u32> = (0..decoded.num_points as u32).collect();
+    let tight_bounding_box = create_result_object(&[
+        ("min", create_vec3_array(&decoded.tight_min)),
+        ("max", create_vec3_array(&decoded.tight_max)),
+    ])?;
+
+    let mut fields = vec![
+        ("num_points", JsValue::from(decoded.num_points as u32)),
+        ("tight_bounding_box", tight_bounding_box.into()),
+        ("mean", create_vec3_array(&decoded.mean)),
+        (
+            "position",
+            js_sys::Float32Array::from(&decoded.positions[..]).into(),
+        ),
+        ("indices", js_sys::Uint32Array::from(&indices[..]).into()),
+        (
+            "object_ids",
+            create_object_id_array(object_id_width, &object_ids),
+        ),
+    ];
+
+    let optional_fields = [
+        (
+            "color",
+            decoded
+                .colors
+                .map(|colors| js_sys::Uint8Array::from(&colors[..]).into()),
+        ),
+        (
+            "intensity",
+            decoded
+                .intensities
+                .map(|intensities| js_sys::Float32Array::from(&intensities[..]).into()),
+        ),
+        (
+            "classification",
+            decoded
+                .classifications
+                .map(|classifications| js_sys::Uint8Array::from(&classifications[..]).into()),
+        ),
+        (
+            "return_number",
+            decoded
+                .return_numbers
+                .map(|return_numbers| js_sys::Uint8Array::from(&return_numbers[..]).into()),
+        ),
+        (
+            "number_of_returns",
+            decoded
+                .numbers_of_returns
+                .map(|numbers_of_returns| js_sys::Uint8Array::from(&numbers_of_returns[..]).into()),
+        ),
+        (
+            "point_source_id",
+            decoded
+                .point_source_ids
+                .map(|point_source_ids| js_sys::Uint16Array::from(&point_source_ids[..]).into()),
+        ),
+    ];
+    fields.extend(
+        optional_fields
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| (name, value))),
+    );
+
+    create_result_object(&fields)
+}
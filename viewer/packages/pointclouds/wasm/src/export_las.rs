@@ -0,0 +1,72 @@
+use wasm_bindgen::JsValue;
+
+use pointclouds_core::las_export::{self, PointAttributes, PointSelection};
+
+use crate::parse_inputs;
+
+/// See `export_las` in `lib.rs`
+pub fn export_las(
+    input_points: js_sys::Float32Array,
+    input_point_offset: Vec<f64>,
+    input_object_ids: JsValue,
+    input_selected_object_ids: Option<Vec<u32>>,
+    input_selection_mask: Option<Vec<u8>>,
+    input_attributes: JsValue,
+) -> Result<Vec<u8>, String> {
+    let positions = input_points.to_vec();
+    let point_offset = parse_inputs::parse_position(input_point_offset)?;
+    let object_ids = parse_inputs::parse_object_id_array(&input_object_ids)?;
+
+    let colors =
+        parse_inputs::parse_optional_typed_array::<js_sys::Uint8Array>(&input_attributes, "color")?
+            .map(|array| array.to_vec());
+    let intensities = parse_inputs::parse_optional_typed_array::<js_sys::Float32Array>(
+        &input_attributes,
+        "intensity",
+    )?
+    .map(|array| array.to_vec());
+    let classifications = parse_inputs::parse_optional_typed_array::<js_sys::Uint8Array>(
+        &input_attributes,
+        "classification",
+    )?
+    .map(|array| array.to_vec());
+    let return_numbers = parse_inputs::parse_optional_typed_array::<js_sys::Uint8Array>(
+        &input_attributes,
+        "return_number",
+    )?
+    .map(|array| array.to_vec());
+    let numbers_of_returns = parse_inputs::parse_optional_typed_array::<js_sys::Uint8Array>(
+        &input_attributes,
+        "number_of_returns",
+    )?
+    .map(|array| array.to_vec());
+    let point_source_ids = parse_inputs::parse_optional_typed_array::<js_sys::Uint16Array>(
+        &input_attributes,
+        "point_source_id",
+    )?
+    .map(|array| array.to_vec());
+
+    let attributes = PointAttributes {
+        positions: &positions,
+        colors: colors.as_deref(),
+        intensities: intensities.as_deref(),
+        classifications: classifications.as_deref(),
+        return_numbers: return_numbers.as_deref(),
+        numbers_of_returns: numbers_of_returns.as_deref(),
+        point_source_ids: point_source_ids.as_deref(),
+    };
+
+    let mask: Option<Vec<bool>> =
+        input_selection_mask.map(|mask| mask.iter().map(|&value| value != 0).collect());
+    let selection = match (&input_selected_object_ids, &mask) {
+        (Some(ids), None) => PointSelection::ObjectIds(ids),
+        (None, Some(mask)) => PointSelection::Mask(mask),
+        _ => {
+            return Err(
+                "Expected either selected object ids or a selection mask, but not both".to_string(),
+            )
+        }
+    };
+
+    las_export::export_las(&attributes, &object_ids, selection, &point_offset)
+}
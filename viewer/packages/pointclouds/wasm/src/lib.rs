@@ -5,22 +5,15 @@
 use wasm_bindgen::prelude::*;
 extern crate console_error_panic_hook;
 
-#[cfg(test)]
-mod test_setup {
-    use wasm_bindgen_test::wasm_bindgen_test_configure;
-
-    wasm_bindgen_test_configure!(run_in_browser);
-}
-
-mod linalg;
+mod create_outputs;
+mod decode_ept;
+mod export_las;
 mod parse_inputs;
-mod point_octree;
-mod shapes;
+mod point_octree_handle;
 
-use linalg::BoundingBox;
-use parse_inputs::InputBoundingBox;
+pub use point_octree_handle::PointOctreeHandle;
 
-fn init() -> () {
+fn init() {
     // This provides better error messages in debug mode.
     // It's disabled in release mode so it doesn't bloat up the file size.
     #[cfg(debug_assertions)]
@@ -28,38 +21,132 @@ fn init() -> () {
 }
 
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
 pub fn assign_points(
     input_objects: Vec<JsValue>,
+    input_meshes: Vec<JsValue>,
     input_points: js_sys::Float32Array,
     input_bounding_box: js_sys::Object,
     input_point_offset: Vec<f64>,
-) -> Result<js_sys::Uint16Array, String> {
-    init();
+    input_overlap_policy: JsValue,
+    input_object_id_width: JsValue,
+    input_inflation: JsValue,
+) -> Result<JsValue, String> {
+    PointOctreeHandle::new(input_points, input_bounding_box, input_point_offset)?.assign(
+        input_objects,
+        input_meshes,
+        input_overlap_policy,
+        input_object_id_width,
+        input_inflation,
+    )
+}
 
-    let mut point_vec = parse_inputs::parse_points(&input_points, input_point_offset);
-    let bounding_box: BoundingBox =
-        serde_wasm_bindgen::from_value::<InputBoundingBox>(input_bounding_box.into())
-            .map_err(|serde_error| {
-                format!(
-                    "Got error while deserializing bounding box: {}",
-                    serde_error
-                )
-            })?
-            .into();
+/// Like `assign_points`, but also reports how far each point is from the surface of the shape it
+/// was assigned to. Returns an object with `object_ids` and `distances`, a `Float32Array` of signed
+/// distances that are negative inside the shape, and NaN for points that were not assigned.
+/// Distances are measured to the shape as given, before inflation
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn assign_points_with_distances(
+    input_objects: Vec<JsValue>,
+    input_meshes: Vec<JsValue>,
+    input_points: js_sys::Float32Array,
+    input_bounding_box: js_sys::Object,
+    input_point_offset: Vec<f64>,
+    input_overlap_policy: JsValue,
+    input_object_id_width: JsValue,
+    input_inflation: JsValue,
+) -> Result<js_sys::Object, String> {
+    PointOctreeHandle::new(input_points, input_bounding_box, input_point_offset)?
+        .assign_with_distances(
+            input_objects,
+            input_meshes,
+            input_overlap_policy,
+            input_object_id_width,
+            input_inflation,
+        )
+}
 
-    let shape_vec = parse_inputs::try_parse_objects(input_objects)?;
+/// Like `assign_points`, but reports every object a point is inside instead of a single winner.
+/// Returns an object with `offsets`, a `Uint32Array` with one more element than there are points,
+/// and `object_ids`, a typed array of the chosen object id width where the ids of point `i` are
+/// found between `offsets[i]` and `offsets[i + 1]`
+#[wasm_bindgen]
+pub fn assign_points_multi_label(
+    input_objects: Vec<JsValue>,
+    input_meshes: Vec<JsValue>,
+    input_points: js_sys::Float32Array,
+    input_bounding_box: js_sys::Object,
+    input_point_offset: Vec<f64>,
+    input_object_id_width: JsValue,
+    input_inflation: JsValue,
+) -> Result<js_sys::Object, String> {
+    PointOctreeHandle::new(input_points, input_bounding_box, input_point_offset)?
+        .assign_multi_label(
+            input_objects,
+            input_meshes,
+            input_object_id_width,
+            input_inflation,
+        )
+}
 
-    let object_ids = js_sys::Uint16Array::new_with_length(input_points.length() / 3).fill(
-        0,
-        0,
-        input_points.length() / 3,
-    );
+/// Decodes an EPT tile and assigns its points to the objects in the same call, so the positions
+/// never pass through JavaScript before assignment. `input_layout` holds the `data_type`
+/// (`binary` or `laszip`), `schema`, `scale`, `offset` and `mins` of the tile. Returns an object
+/// with `num_points`, `tight_bounding_box`, `mean`, the typed arrays `position`, `indices` and
+/// `object_ids`, and `color`, `intensity`, `classification`, `return_number`,
+/// `number_of_returns` and `point_source_id` for the attributes that are in the schema
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn decode_ept_tile(
+    input_buffer: &[u8],
+    input_layout: JsValue,
+    input_objects: Vec<JsValue>,
+    input_meshes: Vec<JsValue>,
+    input_bounding_box: js_sys::Object,
+    input_point_offset: Vec<f64>,
+    input_overlap_policy: JsValue,
+    input_object_id_width: JsValue,
+    input_inflation: JsValue,
+) -> Result<js_sys::Object, String> {
+    init();
 
-    let octree = point_octree::PointOctree::new(bounding_box, &mut point_vec);
+    decode_ept::decode_ept_tile(
+        input_buffer,
+        input_layout,
+        input_objects,
+        input_meshes,
+        input_bounding_box,
+        input_point_offset,
+        input_overlap_policy,
+        input_object_id_width,
+        input_inflation,
+    )
+}
 
-    shape_vec.iter().for_each(|shape| {
-        octree.assign_object_ids(&shape.create_bounding_box(), shape, &object_ids);
-    });
+/// Writes the points assigned to `input_selected_object_ids`, or the points set in
+/// `input_selection_mask`, to a LAS 1.4 file. `input_object_ids` is the object id array from
+/// assignment, and is stored as the `object_id` extra bytes dimension. `input_attributes` may hold
+/// the typed arrays `color`, `intensity`, `classification`, `return_number`,
+/// `number_of_returns` and `point_source_id`, as returned from `decode_ept_tile`. Coordinates are
+/// stored in millimeters relative to `input_point_offset`
+#[wasm_bindgen]
+pub fn export_las(
+    input_points: js_sys::Float32Array,
+    input_point_offset: Vec<f64>,
+    input_object_ids: JsValue,
+    input_selected_object_ids: Option<Vec<u32>>,
+    input_selection_mask: Option<Vec<u8>>,
+    input_attributes: JsValue,
+) -> Result<Vec<u8>, String> {
+    init();
 
-    Ok(object_ids)
+    export_las::export_las(
+        input_points,
+        input_point_offset,
+        input_object_ids,
+        input_selected_object_ids,
+        input_selection_mask,
+        input_attributes,
+    )
 }
@@ -1,116 +1,156 @@
-use nalgebra_glm::{scaling, vec3, DMat4};
+use nalgebra_glm::{vec3, DVec3};
 use std::vec::Vec;
 
-use crate::linalg::BoundingBox;
-use crate::linalg::Vec3WithIndex;
-use crate::shapes;
-
-use serde::Deserialize;
-
-#[derive(Debug, Deserialize)]
-struct InputCylinder {
-    center_a: [f64; 3],
-    center_b: [f64; 3],
-    radius: f64,
+use pointclouds_core::clip_volume::ClipVolume;
+use pointclouds_core::ept::EptTileLayout;
+use pointclouds_core::inflation::InputInflation;
+use pointclouds_core::inputs::{
+    self, InputBoundingBox, InputGeometry, InputHalfSpace, InputPickRay, InputShape,
+    InputTriangleMeshBuffers, ParsedObject,
+};
+use pointclouds_core::linalg::{BoundingBox, Vec3WithIndex};
+use pointclouds_core::object_id_width::ObjectIdWidth;
+use pointclouds_core::overlap_policy::OverlapPolicy;
+use pointclouds_core::point_octree::PickRay;
+use pointclouds_core::shapes;
+
+use serde::de::DeserializeOwned;
+use wasm_bindgen::{JsCast, JsValue};
+
+fn deserialize<T: DeserializeOwned>(input: JsValue, name: &str) -> Result<T, String> {
+    serde_wasm_bindgen::from_value::<T>(input)
+        .map_err(|serde_error| format!("Got error while deserializing {}: {}", name, serde_error))
 }
 
-#[derive(Debug, Deserialize)]
-struct InputOrientedBox {
-    inv_instance_matrix: [f64; 16],
+pub fn parse_bounding_box(input_bounding_box: js_sys::Object) -> Result<BoundingBox, String> {
+    deserialize::<InputBoundingBox>(input_bounding_box.into(), "bounding box").map(Into::into)
 }
 
-#[derive(Debug, Deserialize)]
-pub struct InputShape {
-    object_id: u16,
-    cylinder: Option<Box<InputCylinder>>,
-    oriented_box: Option<Box<InputOrientedBox>>,
+pub fn parse_pick_ray(input_ray: JsValue) -> Result<PickRay, String> {
+    inputs::create_pick_ray(deserialize::<InputPickRay>(input_ray, "pick ray")?)
 }
 
-#[derive(Deserialize)]
-pub struct InputBoundingBox {
-    pub min: [f64; 3],
-    pub max: [f64; 3],
+pub fn parse_clip_volume(input_planes: JsValue) -> Result<ClipVolume, String> {
+    inputs::create_clip_volume(&deserialize::<Vec<InputHalfSpace>>(
+        input_planes,
+        "clip planes",
+    )?)
 }
 
-impl Into<BoundingBox> for InputBoundingBox {
-    fn into(self) -> BoundingBox {
-        BoundingBox {
-            min: vec3(self.min[0], self.min[1], self.min[2]),
-            max: vec3(self.max[0], self.max[1], self.max[2]),
-        }
-    }
+pub fn parse_ept_tile_layout(input_layout: JsValue) -> Result<EptTileLayout, String> {
+    deserialize::<EptTileLayout>(input_layout, "EPT tile layout")
 }
 
 pub fn parse_points(
     input_array: &js_sys::Float32Array,
     input_point_offset: Vec<f64>,
-) -> Vec<Vec3WithIndex> {
-    let point_offset = vec3(
-        input_point_offset[0],
-        input_point_offset[1],
-        input_point_offset[2],
-    );
-
-    let point_vec = input_array
-        .to_vec()
-        .chunks(3)
-        .enumerate()
-        .map(|(i, p)| Vec3WithIndex {
-            vec: vec3(p[0] as f64, p[1] as f64, p[2] as f64) + point_offset,
-            index: i,
-        })
-        .collect();
+) -> Result<Vec<Vec3WithIndex>, String> {
+    let point_offset = parse_position(input_point_offset)?;
 
-    point_vec
+    Ok(inputs::create_points(&input_array.to_vec(), &point_offset))
 }
 
-const SHAPE_SCALE_FACTOR: f64 = 1.15;
-const MAX_RADIUS_INCREASE_METER: f64 = 0.06;
+pub fn parse_position(input_position: Vec<f64>) -> Result<DVec3, String> {
+    match input_position[..] {
+        [x, y, z] => Ok(vec3(x, y, z)),
+        _ => Err(format!(
+            "Expected position with 3 coordinates, got {}",
+            input_position.len()
+        )),
+    }
+}
 
-fn create_cylinder(input: InputCylinder, id: u16) -> Box<shapes::Cylinder> {
-    let radius = (input.radius * SHAPE_SCALE_FACTOR).min(input.radius + MAX_RADIUS_INCREASE_METER);
-    Box::new(shapes::Cylinder::new(
-        vec3(input.center_a[0], input.center_a[1], input.center_a[2]),
-        vec3(input.center_b[0], input.center_b[1], input.center_b[2]),
-        radius,
-        id,
-    ))
+fn get_typed_array_property<T: JsCast>(object: &JsValue, name: &str) -> Result<T, String> {
+    js_sys::Reflect::get(object, &JsValue::from_str(name))
+        .ok()
+        .and_then(|value| value.dyn_into::<T>().ok())
+        .ok_or_else(|| format!("Triangle mesh is missing typed array '{}'", name))
 }
 
-fn create_box(input: InputOrientedBox, id: u16) -> Box<shapes::OrientedBox> {
-    let matrix = DMat4::from_column_slice(&input.inv_instance_matrix);
-    let scaled_matrix = scaling(&vec3(
-        1.0 / SHAPE_SCALE_FACTOR,
-        1.0 / SHAPE_SCALE_FACTOR,
-        1.0 / SHAPE_SCALE_FACTOR,
-    )) * matrix;
-    Box::new(shapes::OrientedBox::new(scaled_matrix, id))
+/// Attributes that are left out or `undefined` are `None`
+pub fn parse_optional_typed_array<T: JsCast>(
+    object: &JsValue,
+    name: &str,
+) -> Result<Option<T>, String> {
+    let value = js_sys::Reflect::get(object, &JsValue::from_str(name))
+        .map_err(|_| format!("Failed to read attribute '{}'", name))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    value
+        .dyn_into::<T>()
+        .map(Some)
+        .map_err(|_| format!("Attribute '{}' has the wrong typed array type", name))
 }
 
-fn create_shape(obj: InputShape) -> Result<Box<dyn shapes::Shape>, String> {
-    if let Some(input_cylinder) = obj.cylinder {
-        Ok(create_cylinder(*input_cylinder, obj.object_id))
-    } else if let Some(input_box) = obj.oriented_box {
-        Ok(create_box(*input_box, obj.object_id))
+/// Object ids as returned from assignment, in a `Uint16Array` or a `Uint32Array`
+pub fn parse_object_id_array(input_object_ids: &JsValue) -> Result<Vec<u32>, String> {
+    if let Some(array) = input_object_ids.dyn_ref::<js_sys::Uint16Array>() {
+        Ok(array.to_vec().into_iter().map(u32::from).collect())
+    } else if let Some(array) = input_object_ids.dyn_ref::<js_sys::Uint32Array>() {
+        Ok(array.to_vec())
     } else {
-        Err("Unrecognized geometry type found while parsing".to_string())
+        Err("Expected object ids in a Uint16Array or a Uint32Array".to_string())
     }
 }
 
+pub fn parse_meshes(input_meshes: Vec<JsValue>) -> Result<Vec<InputTriangleMeshBuffers>, String> {
+    input_meshes
+        .iter()
+        .map(|input_mesh| {
+            let vertices =
+                get_typed_array_property::<js_sys::Float32Array>(input_mesh, "vertices")?;
+            let indices = get_typed_array_property::<js_sys::Uint32Array>(input_mesh, "indices")?;
+
+            Ok(InputTriangleMeshBuffers::new(
+                vertices.to_vec(),
+                indices.to_vec(),
+            ))
+        })
+        .collect()
+}
+
+/// Parses a shape without object id, for queries
+pub fn try_parse_shape(
+    input_shape: JsValue,
+    meshes: &[InputTriangleMeshBuffers],
+    default_inflation: &InputInflation,
+) -> Result<Box<dyn shapes::Shape>, String> {
+    let geometry = deserialize::<InputGeometry>(input_shape, "shape")?;
+
+    inputs::create_shape(geometry, meshes, default_inflation)
+}
+
 pub fn try_parse_objects(
-    input_objects: Vec<wasm_bindgen::prelude::JsValue>,
-) -> Result<Vec<Box<dyn shapes::Shape>>, String> {
-    let objects_result: Result<_, _> = input_objects
+    input_objects: Vec<JsValue>,
+    meshes: &[InputTriangleMeshBuffers],
+    object_id_width: ObjectIdWidth,
+    default_inflation: &InputInflation,
+) -> Result<Vec<ParsedObject>, String> {
+    let input_shapes = input_objects
         .into_iter()
-        .map(|input_object| {
-            let input_shape =
-                serde_wasm_bindgen::from_value::<InputShape>(input_object).map_err(|serde_error| {
-                    format!("Got error while deserializing shape: {}", serde_error)
-                });
+        .map(|input_object| deserialize::<InputShape>(input_object, "shape"))
+        .collect::<Result<Vec<_>, String>>()?;
 
-            create_shape(input_shape?)
-        })
-        .collect();
+    inputs::create_objects(input_shapes, meshes, object_id_width, default_inflation)
+}
+
+/// Settings that are left out, `null` or `undefined` get their default value
+fn parse_optional_setting<T: DeserializeOwned + Default>(
+    input_setting: JsValue,
+    name: &str,
+) -> Result<T, String> {
+    deserialize::<Option<T>>(input_setting, name).map(Option::unwrap_or_default)
+}
+
+pub fn parse_overlap_policy(input_overlap_policy: JsValue) -> Result<OverlapPolicy, String> {
+    parse_optional_setting(input_overlap_policy, "overlap policy")
+}
+
+pub fn parse_object_id_width(input_object_id_width: JsValue) -> Result<ObjectIdWidth, String> {
+    parse_optional_setting(input_object_id_width, "object id width")
+}
 
-    objects_result
+pub fn parse_inflation(input_inflation: JsValue) -> Result<InputInflation, String> {
+    parse_optional_setting(input_inflation, "inflation")
 }
@@ -1,4 +0,0 @@
-mod octree_node;
-pub mod point_octree;
-
-pub use point_octree::*;
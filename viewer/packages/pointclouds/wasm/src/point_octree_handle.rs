@@ -0,0 +1,284 @@
+use wasm_bindgen::prelude::*;
+
+use pointclouds_core::assignment::{self, ObjectSet};
+use pointclouds_core::object_id_width::ObjectIdWidth;
+use pointclouds_core::point_octree::{PointNeighbour, PointOctree};
+
+use crate::create_outputs::{create_object_id_array, create_result_object};
+use crate::parse_inputs;
+
+/// A point octree that is kept alive between calls, so that restyling or querying the points of a
+/// loaded sector does not rebuild it. Must be released with `dispose` when no longer needed
+#[wasm_bindgen]
+pub struct PointOctreeHandle {
+    octree: PointOctree,
+}
+
+/// Sorted, so that results do not depend on the internal point order of the octree
+fn create_index_array(mut point_indices: Vec<u32>) -> js_sys::Uint32Array {
+    point_indices.sort_unstable();
+    js_sys::Uint32Array::from(&point_indices[..])
+}
+
+fn create_neighbours_object(neighbours: &[PointNeighbour]) -> Result<js_sys::Object, String> {
+    let indices: Vec<u32> = neighbours
+        .iter()
+        .map(|neighbour| neighbour.index as u32)
+        .collect();
+    let distances: Vec<f32> = neighbours
+        .iter()
+        .map(|neighbour| neighbour.distance as f32)
+        .collect();
+
+    create_result_object(&[
+        ("indices", js_sys::Uint32Array::from(&indices[..]).into()),
+        (
+            "distances",
+            js_sys::Float32Array::from(&distances[..]).into(),
+        ),
+    ])
+}
+
+#[wasm_bindgen]
+impl PointOctreeHandle {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        input_points: js_sys::Float32Array,
+        input_bounding_box: js_sys::Object,
+        input_point_offset: Vec<f64>,
+    ) -> Result<PointOctreeHandle, String> {
+        crate::init();
+
+        let point_vec = parse_inputs::parse_points(&input_points, input_point_offset)?;
+        let bounding_box = parse_inputs::parse_bounding_box(input_bounding_box)?;
+
+        Ok(PointOctreeHandle {
+            octree: PointOctree::new(bounding_box, point_vec),
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn num_points(&self) -> usize {
+        self.octree.points().len()
+    }
+
+    /// See `assign_points`
+    pub fn assign(
+        &self,
+        input_objects: Vec<JsValue>,
+        input_meshes: Vec<JsValue>,
+        input_overlap_policy: JsValue,
+        input_object_id_width: JsValue,
+        input_inflation: JsValue,
+    ) -> Result<JsValue, String> {
+        let object_id_width = parse_inputs::parse_object_id_width(input_object_id_width)?;
+        let inflation = parse_inputs::parse_inflation(input_inflation)?;
+        let meshes = parse_inputs::parse_meshes(input_meshes)?;
+        let objects =
+            parse_inputs::try_parse_objects(input_objects, &meshes, object_id_width, &inflation)?;
+        let overlap_policy = parse_inputs::parse_overlap_policy(input_overlap_policy)?;
+
+        let mut object_ids = vec![0; self.octree.points().len()];
+        assignment::assign_object_ids(
+            &self.octree,
+            &ObjectSet::new(objects),
+            overlap_policy,
+            &mut object_ids,
+        );
+
+        Ok(create_object_id_array(object_id_width, &object_ids))
+    }
+
+    /// See `assign_points_with_distances`
+    pub fn assign_with_distances(
+        &self,
+        input_objects: Vec<JsValue>,
+        input_meshes: Vec<JsValue>,
+        input_overlap_policy: JsValue,
+        input_object_id_width: JsValue,
+        input_inflation: JsValue,
+    ) -> Result<js_sys::Object, String> {
+        let object_id_width = parse_inputs::parse_object_id_width(input_object_id_width)?;
+        let inflation = parse_inputs::parse_inflation(input_inflation)?;
+        let meshes = parse_inputs::parse_meshes(input_meshes)?;
+        let objects =
+            parse_inputs::try_parse_objects(input_objects, &meshes, object_id_width, &inflation)?;
+        let overlap_policy = parse_inputs::parse_overlap_policy(input_overlap_policy)?;
+
+        let num_points = self.octree.points().len();
+        let mut object_ids = vec![0; num_points];
+        let mut distances = vec![0.0; num_points];
+        assignment::assign_object_ids_with_distances(
+            &self.octree,
+            &ObjectSet::new(objects),
+            overlap_policy,
+            &mut object_ids,
+            &mut distances,
+        );
+
+        create_result_object(&[
+            (
+                "object_ids",
+                create_object_id_array(object_id_width, &object_ids),
+            ),
+            (
+                "distances",
+                js_sys::Float32Array::from(&distances[..]).into(),
+            ),
+        ])
+    }
+
+    /// See `assign_points_multi_label`
+    pub fn assign_multi_label(
+        &self,
+        input_objects: Vec<JsValue>,
+        input_meshes: Vec<JsValue>,
+        input_object_id_width: JsValue,
+        input_inflation: JsValue,
+    ) -> Result<js_sys::Object, String> {
+        let object_id_width = parse_inputs::parse_object_id_width(input_object_id_width)?;
+        let inflation = parse_inputs::parse_inflation(input_inflation)?;
+        let meshes = parse_inputs::parse_meshes(input_meshes)?;
+        let objects =
+            parse_inputs::try_parse_objects(input_objects, &meshes, object_id_width, &inflation)?;
+
+        let memberships = assignment::assign_memberships(&self.octree, &ObjectSet::new(objects));
+
+        create_result_object(&[
+            (
+                "offsets",
+                js_sys::Uint32Array::from(&memberships.offsets[..]).into(),
+            ),
+            (
+                "object_ids",
+                create_object_id_array(object_id_width, &memberships.object_ids),
+            ),
+        ])
+    }
+
+    /// Indices of the points inside the axis-aligned `input_bounding_box`, in ascending order
+    pub fn query_box(
+        &self,
+        input_bounding_box: js_sys::Object,
+    ) -> Result<js_sys::Uint32Array, String> {
+        let bounding_box = parse_inputs::parse_bounding_box(input_bounding_box)?;
+
+        let mut point_indices = vec![];
+        self.octree.for_each_point_in_box(&bounding_box, |point| {
+            point_indices.push(point.index as u32)
+        });
+
+        Ok(create_index_array(point_indices))
+    }
+
+    /// Indices of the points inside `input_shape`, in ascending order. The shape is given like the
+    /// objects passed to `assign`, but without an object id, and is inflated the same way
+    pub fn query_shape(
+        &self,
+        input_shape: JsValue,
+        input_meshes: Vec<JsValue>,
+        input_inflation: JsValue,
+    ) -> Result<js_sys::Uint32Array, String> {
+        let inflation = parse_inputs::parse_inflation(input_inflation)?;
+        let meshes = parse_inputs::parse_meshes(input_meshes)?;
+        let shape = parse_inputs::try_parse_shape(input_shape, &meshes, &inflation)?;
+
+        let mut point_indices = vec![];
+        self.octree.for_each_point_in_shape(
+            &shape.create_bounding_box(),
+            shape.as_ref(),
+            |point| point_indices.push(point.index as u32),
+        );
+
+        Ok(create_index_array(point_indices))
+    }
+
+    /// Points at most `radius` away from `input_position`, which is given in the same coordinates
+    /// as the shapes. Returns an object with the point `indices` and their `distances`, nearest
+    /// first
+    pub fn query_radius(
+        &self,
+        input_position: Vec<f64>,
+        radius: f64,
+    ) -> Result<js_sys::Object, String> {
+        let position = parse_inputs::parse_position(input_position)?;
+
+        if radius.is_nan() || radius < 0.0 {
+            return Err(format!("Radius must be non-negative, got {}", radius));
+        }
+
+        create_neighbours_object(&self.octree.find_points_within_radius(&position, radius))
+    }
+
+    /// The `k` points nearest to `input_position`, returned like in `query_radius`
+    pub fn query_nearest(
+        &self,
+        input_position: Vec<f64>,
+        k: usize,
+    ) -> Result<js_sys::Object, String> {
+        let position = parse_inputs::parse_position(input_position)?;
+
+        create_neighbours_object(&self.octree.find_nearest_points(&position, k))
+    }
+
+    /// Picks the point hit by `input_ray` that is closest to the ray origin, see `InputPickRay`.
+    /// Returns `null` if no point is hit, and otherwise an object with the point `index`, its
+    /// `position`, `distance_along_ray` and `distance_to_ray`, and the `object_id` it would be
+    /// given by `assign` with the same objects and settings
+    pub fn pick(
+        &self,
+        input_ray: JsValue,
+        input_objects: Vec<JsValue>,
+        input_meshes: Vec<JsValue>,
+        input_overlap_policy: JsValue,
+        input_inflation: JsValue,
+    ) -> Result<JsValue, String> {
+        let ray = parse_inputs::parse_pick_ray(input_ray)?;
+        let inflation = parse_inputs::parse_inflation(input_inflation)?;
+        let meshes = parse_inputs::parse_meshes(input_meshes)?;
+        let objects = parse_inputs::try_parse_objects(
+            input_objects,
+            &meshes,
+            ObjectIdWidth::U32,
+            &inflation,
+        )?;
+        let overlap_policy = parse_inputs::parse_overlap_policy(input_overlap_policy)?;
+
+        let Some(hit) = self.octree.find_closest_ray_hit(&ray) else {
+            return Ok(JsValue::NULL);
+        };
+
+        let object_id = assignment::get_object_id_at(&hit.position, &objects, overlap_policy);
+
+        create_result_object(&[
+            ("index", JsValue::from_f64(hit.index as f64)),
+            ("object_id", JsValue::from_f64(object_id as f64)),
+            (
+                "position",
+                js_sys::Float64Array::from(hit.position.as_slice()).into(),
+            ),
+            (
+                "distance_along_ray",
+                JsValue::from_f64(hit.distance_along_ray),
+            ),
+            ("distance_to_ray", JsValue::from_f64(hit.distance_to_ray)),
+        ])
+        .map(Into::into)
+    }
+
+    /// One byte per point, which is 1 for the points inside the convex volume bounded by
+    /// `input_planes`, like a camera frustum or a section box, and 0 for the rest. Planes are given
+    /// like half-space shapes, and keep the points on the side opposite to their normal
+    pub fn compute_visibility(&self, input_planes: JsValue) -> Result<js_sys::Uint8Array, String> {
+        let clip_volume = parse_inputs::parse_clip_volume(input_planes)?;
+
+        let mut visibility = vec![0u8; self.octree.points().len()];
+        self.octree
+            .for_each_point_in_clip_volume(&clip_volume, |point| visibility[point.index] = 1);
+
+        Ok(js_sys::Uint8Array::from(&visibility[..]))
+    }
+
+    /// Frees the octree and its points. The handle cannot be used afterwards
+    pub fn dispose(self) {}
+}
@@ -1,7 +0,0 @@
-mod cylinder;
-mod oriented_box;
-mod shape;
-
-pub use cylinder::Cylinder;
-pub use oriented_box::OrientedBox;
-pub use shape::Shape;